@@ -198,15 +198,24 @@ impl AchievementNFT {
         env.storage().persistent().get(&DataKey::Achievement(token_id))
     }
 
+    /// Returns the puzzle a token was minted for.
+    pub fn puzzle_of(env: Env, token_id: u32) -> u32 {
+        let achievement: Achievement = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Achievement(token_id))
+            .expect("Token does not exist");
+        achievement.puzzle_id
+    }
+
     /// Returns unique puzzle IDs owned by `owner` (derived from achievement NFTs).
     pub fn puzzle_ids_of(env: Env, owner: Address) -> Vec<u32> {
         let token_ids = Self::get_collection(env.clone(), owner);
         let mut puzzles = Vec::new(&env);
 
-        for tid in token_ids.iter() {
-            let token_id = tid.clone();
+        for token_id in token_ids.iter() {
             if let Some(a) = Self::get_achievement(env.clone(), token_id) {
-                if !puzzles.contains(&a.puzzle_id) {
+                if !puzzles.contains(a.puzzle_id) {
                     puzzles.push_back(a.puzzle_id);
                 }
             }
@@ -218,7 +227,7 @@ impl AchievementNFT {
     /// True if `owner` owns an achievement NFT whose `puzzle_id` equals `puzzle_id`.
     pub fn has_puzzle(env: Env, owner: Address, puzzle_id: u32) -> bool {
         let puzzles = Self::puzzle_ids_of(env, owner);
-        puzzles.contains(&puzzle_id)
+        puzzles.contains(puzzle_id)
     }
 }
 
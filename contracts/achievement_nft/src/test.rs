@@ -30,6 +30,7 @@ fn test_nft_lifecycle() {
 
     let achievement = client.get_achievement(&token_id).unwrap();
     assert_eq!(achievement.puzzle_id, puzzle_id);
+    assert_eq!(client.puzzle_of(&token_id), puzzle_id);
     assert_eq!(achievement.owner, user_a);
     assert_eq!(achievement.metadata, metadata);
 
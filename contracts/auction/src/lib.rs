@@ -1,6 +1,7 @@
 #![no_std]
 use soroban_sdk::{
-    contract, contractimpl, contracttype, token, Address, Env, IntoVal, Symbol, Val,
+    contract, contractimpl, contracttype, token, xdr::ToXdr, Address, Bytes, BytesN, Env, IntoVal,
+    Symbol,
 };
 
 // 1. DATA STRUCTURES
@@ -9,6 +10,7 @@ use soroban_sdk::{
 pub enum AuctionType {
     English = 1,
     Dutch = 2,
+    SealedBid = 3,
 }
 
 // NEW: Grouping settings to avoid the 10-parameter limit
@@ -37,14 +39,60 @@ pub struct AuctionInfo {
     pub highest_bidder: Option<Address>,
     pub current_bid: i128,
     pub settled: bool,
+    pub cancelled: bool,
+}
+
+/// Bids placed within `window` seconds of the end push the end time out to
+/// `extension` seconds after the bid
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SoftCloseConfig {
+    pub window: u64,
+    pub extension: u64,
+}
+
+/// A sealed bid: `commitment` is sha256(auction_id || bidder || amount || salt), backed by `deposit`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SealedBid {
+    pub commitment: BytesN<32>,
+    pub deposit: i128,
+    pub revealed: bool,
+    pub amount: i128,
+}
+
+/// Reveal phase of a sealed-bid auction. Bids are committed between
+/// `start_time` and `end_time` and revealed until `reveal_end`.
+/// `unrevealed` is the total deposit behind bids not yet revealed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SealedRound {
+    pub reveal_end: u64,
+    pub bid_count: u32,
+    pub unrevealed: i128,
+    pub second_price: i128,
 }
 
 #[contracttype]
 pub enum DataKey {
     Auction(u64),
     AuctionCount,
+    Admin,
+    SoftClose,
+    KeeperRewardBps,
+    RevealPeriod,
+    SealedRound(u64),
+    // Persistent, so any number of bids doesn't grow the instance entry
+    SealedBid(u64, Address),
 }
 
+const DEFAULT_SOFT_CLOSE: SoftCloseConfig = SoftCloseConfig {
+    window: 300,
+    extension: 300,
+};
+const BASIS_POINTS: i128 = 10_000;
+const DEFAULT_REVEAL_PERIOD: u64 = 86_400;
+
 // 2. CONTRACT LOGIC
 #[contract]
 pub struct AuctionContract;
@@ -52,10 +100,53 @@ pub struct AuctionContract;
 #[contractimpl]
 impl AuctionContract {
     /// Initialize the contract
-    pub fn init(env: Env, _admin: Address) {
+    pub fn init(env: Env, admin: Address) {
         if !env.storage().instance().has(&DataKey::AuctionCount) {
             env.storage().instance().set(&DataKey::AuctionCount, &0u64);
         }
+        if !env.storage().instance().has(&DataKey::Admin) {
+            env.storage().instance().set(&DataKey::Admin, &admin);
+        }
+    }
+
+    /// Configure the anti-sniping extension (admin only). A zero window disables it.
+    pub fn set_soft_close(env: Env, admin: Address, window: u64, extension: u64) {
+        Self::require_admin(&env, &admin);
+
+        env.storage()
+            .instance()
+            .set(&DataKey::SoftClose, &SoftCloseConfig { window, extension });
+    }
+
+    /// Share of the sale price paid to whoever settles an auction (admin only)
+    pub fn set_keeper_reward(env: Env, admin: Address, reward_bps: u32) {
+        Self::require_admin(&env, &admin);
+
+        if reward_bps as i128 > BASIS_POINTS {
+            panic!("Keeper reward too high");
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::KeeperRewardBps, &reward_bps);
+    }
+
+    /// Length of the reveal phase for new sealed-bid auctions (admin only)
+    pub fn set_reveal_period(env: Env, admin: Address, period: u64) {
+        Self::require_admin(&env, &admin);
+
+        if period == 0 {
+            panic!("Reveal period must be positive");
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::RevealPeriod, &period);
+    }
+
+    pub fn get_soft_close(env: Env) -> SoftCloseConfig {
+        env.storage()
+            .instance()
+            .get(&DataKey::SoftClose)
+            .unwrap_or(DEFAULT_SOFT_CLOSE)
     }
 
     /// Creates a new auction
@@ -74,6 +165,15 @@ impl AuctionContract {
             panic!("End time must be after start time");
         }
 
+        // Hold the NFT so the auction can settle without the seller
+        Self::transfer_nft(
+            &env,
+            &nft_contract,
+            &seller,
+            &env.current_contract_address(),
+            nft_id,
+        );
+
         // Generate ID
         let mut id: u64 = env
             .storage()
@@ -95,6 +195,7 @@ impl AuctionContract {
             highest_bidder: None,
             current_bid: 0,
             settled: false,
+            cancelled: false,
         };
 
         // Save
@@ -102,6 +203,23 @@ impl AuctionContract {
             .instance()
             .set(&DataKey::Auction(id), &auction);
 
+        if auction.auction_type == AuctionType::SealedBid {
+            let reveal_period: u64 = env
+                .storage()
+                .instance()
+                .get(&DataKey::RevealPeriod)
+                .unwrap_or(DEFAULT_REVEAL_PERIOD);
+            let round = SealedRound {
+                reveal_end: auction.settings.end_time + reveal_period,
+                bid_count: 0,
+                unrevealed: 0,
+                second_price: 0,
+            };
+            env.storage()
+                .instance()
+                .set(&DataKey::SealedRound(id), &round);
+        }
+
         id
     }
 
@@ -163,10 +281,14 @@ impl AuctionContract {
         token_client.transfer(&bidder, &env.current_contract_address(), &bid_amount);
 
         // 6. Anti-Sniping (Extension)
-        // If bid is placed in the last 5 minutes (300 seconds), extend end time by 5 mins.
+        // If bid is placed inside the soft-close window, push the end time out.
+        let soft_close = Self::get_soft_close(env.clone());
         let time_remaining = auction.settings.end_time - current_time;
-        if time_remaining < 300 {
-            auction.settings.end_time = current_time + 300;
+        if time_remaining < soft_close.window {
+            auction.settings.end_time = auction
+                .settings
+                .end_time
+                .max(current_time + soft_close.extension);
         }
 
         // 7. Update State & Save
@@ -232,7 +354,14 @@ impl AuctionContract {
         // 4. Process Payment
         // Buyer pays the calculated CURRENT price (not their max)
         let token_client = token::Client::new(&env, &auction.payment_token);
-        token_client.transfer(&buyer, &env.current_contract_address(), &current_price);
+        token_client.transfer(&buyer, &auction.seller, &current_price);
+        Self::transfer_nft(
+            &env,
+            &auction.nft_contract,
+            &env.current_contract_address(),
+            &buyer,
+            auction.nft_id,
+        );
 
         // 5. End the Auction Immediately
         auction.highest_bidder = Some(buyer);
@@ -244,45 +373,250 @@ impl AuctionContract {
             .set(&DataKey::Auction(auction_id), &auction);
     }
 
-    /// Finalize the auction (Send money to seller, NFT to winner)
-    pub fn settle_auction(env: Env, auction_id: u64) {
-        let mut auction: AuctionInfo = env
+    /// Commit a sealed bid. The deposit must cover the bid and is held until settlement.
+    pub fn commit_bid(
+        env: Env,
+        bidder: Address,
+        auction_id: u64,
+        commitment: BytesN<32>,
+        deposit: i128,
+    ) {
+        bidder.require_auth();
+
+        let auction: AuctionInfo = env
             .storage()
             .instance()
             .get(&DataKey::Auction(auction_id))
             .unwrap();
 
+        if auction.auction_type != AuctionType::SealedBid {
+            panic!("Not a sealed-bid auction");
+        }
         if auction.settled {
             panic!("Auction is already settled");
         }
 
-        // For English auctions, ensure time has passed
-        if auction.auction_type == AuctionType::English {
-            if env.ledger().timestamp() < auction.settings.end_time {
-                panic!("Auction is still ongoing");
+        let current_time = env.ledger().timestamp();
+        if current_time < auction.settings.start_time {
+            panic!("Auction has not started");
+        }
+        if current_time > auction.settings.end_time {
+            panic!("Commit phase has ended");
+        }
+        if deposit < auction.settings.starting_price || deposit <= 0 {
+            panic!("Deposit too low");
+        }
+
+        let bid_key = DataKey::SealedBid(auction_id, bidder.clone());
+        if env.storage().persistent().has(&bid_key) {
+            panic!("Bid already committed");
+        }
+
+        let token_client = token::Client::new(&env, &auction.payment_token);
+        token_client.transfer(&bidder, &env.current_contract_address(), &deposit);
+
+        let bid = SealedBid {
+            commitment,
+            deposit,
+            revealed: false,
+            amount: 0,
+        };
+        env.storage().persistent().set(&bid_key, &bid);
+
+        let mut round = Self::sealed_round(&env, auction_id);
+        round.bid_count += 1;
+        round.unrevealed += deposit;
+        env.storage()
+            .instance()
+            .set(&DataKey::SealedRound(auction_id), &round);
+    }
+
+    /// Reveal a sealed bid after the commit phase. The highest revealed bid leads
+    /// and the runner-up sets the price it pays.
+    pub fn reveal_bid(env: Env, bidder: Address, auction_id: u64, amount: i128, salt: BytesN<32>) {
+        bidder.require_auth();
+
+        let mut auction: AuctionInfo = env
+            .storage()
+            .instance()
+            .get(&DataKey::Auction(auction_id))
+            .unwrap();
+        let mut round = Self::sealed_round(&env, auction_id);
+
+        let current_time = env.ledger().timestamp();
+        if current_time <= auction.settings.end_time {
+            panic!("Commit phase is still open");
+        }
+        if current_time > round.reveal_end {
+            panic!("Reveal phase has ended");
+        }
+
+        let bid_key = DataKey::SealedBid(auction_id, bidder.clone());
+        let mut bid: SealedBid = env
+            .storage()
+            .persistent()
+            .get(&bid_key)
+            .expect("No committed bid");
+
+        if bid.revealed {
+            panic!("Bid already revealed");
+        }
+        if Self::bid_commitment(env.clone(), auction_id, bidder.clone(), amount, salt) != bid.commitment {
+            panic!("Commitment mismatch");
+        }
+        if amount > bid.deposit {
+            panic!("Deposit does not cover bid");
+        }
+
+        bid.revealed = true;
+        bid.amount = amount;
+        env.storage().persistent().set(&bid_key, &bid);
+        round.unrevealed -= bid.deposit;
+
+        // Bids under the starting price are refunded but can't win
+        if amount >= auction.settings.starting_price {
+            if auction.highest_bidder.is_none() || amount > auction.current_bid {
+                round.second_price = auction.current_bid;
+                auction.highest_bidder = Some(bidder);
+                auction.current_bid = amount;
+            } else if amount > round.second_price {
+                round.second_price = amount;
             }
         }
 
-        // If there is a winner...
-        if let Some(winner) = auction.highest_bidder.clone() {
-            // 1. Pay the Seller
-            let token_client = token::Client::new(&env, &auction.payment_token);
-            token_client.transfer(
-                &env.current_contract_address(),
-                &auction.seller,
-                &auction.current_bid,
-            );
+        env.storage()
+            .instance()
+            .set(&DataKey::Auction(auction_id), &auction);
+        env.storage()
+            .instance()
+            .set(&DataKey::SealedRound(auction_id), &round);
+    }
+
+    /// Hash a bid the way `reveal_bid` checks it: sha256(auction_id as 8 big-endian
+    /// bytes || bidder XDR || amount as 16 big-endian bytes || salt). Binding the
+    /// auction and bidder stops a commitment being copied and replayed by someone else.
+    pub fn bid_commitment(
+        env: Env,
+        auction_id: u64,
+        bidder: Address,
+        amount: i128,
+        salt: BytesN<32>,
+    ) -> BytesN<32> {
+        let mut data = Bytes::from_array(&env, &auction_id.to_be_bytes());
+        data.append(&bidder.to_xdr(&env));
+        data.append(&Bytes::from_array(&env, &amount.to_be_bytes()));
+        data.append(&salt.into());
+        env.crypto().sha256(&data).into()
+    }
 
-            // 2. Transfer the NFT
-            // We invoke the NFT contract's "transfer" function dynamically.
-            // Args: (from, to, token_id)
-            let transfer_args = (auction.seller.clone(), winner, auction.nft_id);
+    pub fn get_sealed_round(env: Env, auction_id: u64) -> Option<SealedRound> {
+        env.storage()
+            .instance()
+            .get(&DataKey::SealedRound(auction_id))
+    }
 
-            env.invoke_contract::<()>(
-                &auction.nft_contract,
-                &Symbol::new(&env, "transfer"),
-                transfer_args.into_val(&env),
-            );
+    pub fn get_sealed_bid(env: Env, auction_id: u64, bidder: Address) -> Option<SealedBid> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::SealedBid(auction_id, bidder))
+    }
+
+    /// Refund a revealed sealed bid that didn't win, once the auction is settled.
+    /// Returns the amount refunded.
+    pub fn withdraw_bid(env: Env, bidder: Address, auction_id: u64) -> i128 {
+        bidder.require_auth();
+
+        let auction: AuctionInfo = env
+            .storage()
+            .instance()
+            .get(&DataKey::Auction(auction_id))
+            .unwrap();
+        if !auction.settled {
+            panic!("Auction is not settled");
+        }
+
+        let bid_key = DataKey::SealedBid(auction_id, bidder.clone());
+        let bid: SealedBid = env
+            .storage()
+            .persistent()
+            .get(&bid_key)
+            .expect("No bid to refund");
+        if !bid.revealed {
+            panic!("Unrevealed deposit was forfeited");
+        }
+        env.storage().persistent().remove(&bid_key);
+
+        let token_client = token::Client::new(&env, &auction.payment_token);
+        token_client.transfer(&env.current_contract_address(), &bidder, &bid.deposit);
+        bid.deposit
+    }
+
+    /// Finalize the auction (Send money to seller, NFT to winner).
+    /// Anyone can settle once the auction has ended; the keeper earns a share
+    /// of the sale. If the reserve isn't met the bid is refunded and the NFT
+    /// goes back to the seller.
+    pub fn settle_auction(env: Env, keeper: Address, auction_id: u64) {
+        keeper.require_auth();
+
+        let mut auction: AuctionInfo = env
+            .storage()
+            .instance()
+            .get(&DataKey::Auction(auction_id))
+            .unwrap();
+
+        if auction.settled {
+            panic!("Auction is already settled");
+        }
+
+        if auction.auction_type == AuctionType::SealedBid {
+            Self::settle_sealed(&env, &keeper, &mut auction);
+            env.storage()
+                .instance()
+                .set(&DataKey::Auction(auction_id), &auction);
+            return;
+        }
+
+        if env.ledger().timestamp() < auction.settings.end_time {
+            panic!("Auction is still ongoing");
+        }
+
+        let token_client = token::Client::new(&env, &auction.payment_token);
+        let contract = env.current_contract_address();
+
+        match auction.highest_bidder.clone() {
+            Some(winner) if auction.current_bid >= auction.settings.reserve_price => {
+                // 1. Pay the keeper and the Seller
+                Self::pay_seller(&env, &auction, &keeper, auction.current_bid);
+
+                // 2. Transfer the NFT
+                Self::transfer_nft(
+                    &env,
+                    &auction.nft_contract,
+                    &contract,
+                    &winner,
+                    auction.nft_id,
+                );
+            }
+            Some(bidder) => {
+                // Reserve not met: refund the bid and return the NFT
+                token_client.transfer(&contract, &bidder, &auction.current_bid);
+                Self::transfer_nft(
+                    &env,
+                    &auction.nft_contract,
+                    &contract,
+                    &auction.seller,
+                    auction.nft_id,
+                );
+            }
+            None => {
+                Self::transfer_nft(
+                    &env,
+                    &auction.nft_contract,
+                    &contract,
+                    &auction.seller,
+                    auction.nft_id,
+                );
+            }
         }
 
         // Mark as settled so it can't be processed again
@@ -292,10 +626,150 @@ impl AuctionContract {
             .set(&DataKey::Auction(auction_id), &auction);
     }
 
+    /// Withdraw an auction that has no bids and return the NFT
+    pub fn cancel_auction(env: Env, seller: Address, auction_id: u64) {
+        seller.require_auth();
+
+        let mut auction: AuctionInfo = env
+            .storage()
+            .instance()
+            .get(&DataKey::Auction(auction_id))
+            .unwrap();
+
+        if auction.seller != seller {
+            panic!("Only the seller can cancel");
+        }
+        if auction.settled {
+            panic!("Auction is already settled");
+        }
+        let has_sealed_bids = Self::get_sealed_round(env.clone(), auction_id)
+            .map(|round| round.bid_count > 0)
+            .unwrap_or(false);
+        if auction.highest_bidder.is_some() || has_sealed_bids {
+            panic!("Auction has bids");
+        }
+
+        Self::transfer_nft(
+            &env,
+            &auction.nft_contract,
+            &env.current_contract_address(),
+            &seller,
+            auction.nft_id,
+        );
+
+        auction.settled = true;
+        auction.cancelled = true;
+        env.storage()
+            .instance()
+            .set(&DataKey::Auction(auction_id), &auction);
+    }
+
     /// Helper to fetch auction data
     pub fn get_auction(env: Env, auction_id: u64) -> Option<AuctionInfo> {
         env.storage().instance().get(&DataKey::Auction(auction_id))
     }
+
+    /// Close a sealed-bid auction once reveals are over. The winner pays the
+    /// second-highest revealed price (never less than the reserve) and
+    /// unrevealed deposits go to the seller. Other revealed bidders reclaim
+    /// their deposits with `withdraw_bid`.
+    fn settle_sealed(env: &Env, keeper: &Address, auction: &mut AuctionInfo) {
+        let round = Self::sealed_round(env, auction.auction_id);
+        if env.ledger().timestamp() <= round.reveal_end {
+            panic!("Reveal phase is still open");
+        }
+
+        let token_client = token::Client::new(env, &auction.payment_token);
+        let contract = env.current_contract_address();
+
+        let winner = auction
+            .highest_bidder
+            .clone()
+            .filter(|_| auction.current_bid >= auction.settings.reserve_price);
+        let price = round
+            .second_price
+            .max(auction.settings.starting_price)
+            .max(auction.settings.reserve_price);
+
+        if round.unrevealed > 0 {
+            token_client.transfer(&contract, &auction.seller, &round.unrevealed);
+        }
+
+        match winner {
+            Some(winner) => {
+                let bid_key = DataKey::SealedBid(auction.auction_id, winner.clone());
+                let bid: SealedBid = env.storage().persistent().get(&bid_key).unwrap();
+                env.storage().persistent().remove(&bid_key);
+                if bid.deposit > price {
+                    token_client.transfer(&contract, &winner, &(bid.deposit - price));
+                }
+
+                Self::pay_seller(env, auction, keeper, price);
+                Self::transfer_nft(
+                    env,
+                    &auction.nft_contract,
+                    &contract,
+                    &winner,
+                    auction.nft_id,
+                );
+                auction.current_bid = price;
+            }
+            None => {
+                Self::transfer_nft(
+                    env,
+                    &auction.nft_contract,
+                    &contract,
+                    &auction.seller,
+                    auction.nft_id,
+                );
+            }
+        }
+
+        auction.settled = true;
+    }
+
+    /// Pay the sale proceeds to the seller, less the keeper reward
+    fn pay_seller(env: &Env, auction: &AuctionInfo, keeper: &Address, amount: i128) {
+        let token_client = token::Client::new(env, &auction.payment_token);
+        let contract = env.current_contract_address();
+
+        let reward_bps: u32 = env
+            .storage()
+            .instance()
+            .get(&DataKey::KeeperRewardBps)
+            .unwrap_or(0);
+        let reward = amount * reward_bps as i128 / BASIS_POINTS;
+        if reward > 0 {
+            token_client.transfer(&contract, keeper, &reward);
+        }
+        token_client.transfer(&contract, &auction.seller, &(amount - reward));
+    }
+
+    fn sealed_round(env: &Env, auction_id: u64) -> SealedRound {
+        env.storage()
+            .instance()
+            .get(&DataKey::SealedRound(auction_id))
+            .expect("Not a sealed-bid auction")
+    }
+
+    fn require_admin(env: &Env, admin: &Address) {
+        admin.require_auth();
+        let stored: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        if stored != *admin {
+            panic!("Unauthorized");
+        }
+    }
+
+    /// Move the NFT by invoking the NFT contract's "transfer" function.
+    /// Args: (from, to, token_id)
+    fn transfer_nft(env: &Env, nft_contract: &Address, from: &Address, to: &Address, nft_id: u64) {
+        let transfer_args = (from.clone(), to.clone(), nft_id);
+        env.invoke_contract::<()>(
+            nft_contract,
+            &Symbol::new(env, "transfer"),
+            transfer_args.into_val(env),
+        );
+    }
 }
 
 #[cfg(test)]
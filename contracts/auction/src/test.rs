@@ -2,10 +2,40 @@
 
 use super::*;
 use soroban_sdk::{
+    contract, contractimpl,
     testutils::{Address as _, Ledger},
-    token, Address, Env,
+    token, Address, BytesN, Env,
 };
 
+// Minimal NFT used to check custody of the auctioned token
+#[contract]
+pub struct MockNft;
+
+#[contractimpl]
+impl MockNft {
+    pub fn mint(env: Env, to: Address, token_id: u64) {
+        env.storage().persistent().set(&token_id, &to);
+    }
+
+    pub fn transfer(env: Env, from: Address, to: Address, token_id: u64) {
+        from.require_auth();
+        let owner: Address = env.storage().persistent().get(&token_id).unwrap();
+        assert_eq!(owner, from);
+        env.storage().persistent().set(&token_id, &to);
+    }
+
+    pub fn owner_of(env: Env, token_id: u64) -> Address {
+        env.storage().persistent().get(&token_id).unwrap()
+    }
+}
+
+fn create_nft(env: &Env, owner: &Address) -> (Address, MockNftClient<'static>) {
+    let nft_id = env.register_contract(None, MockNft);
+    let nft = MockNftClient::new(env, &nft_id);
+    nft.mint(owner, &1u64);
+    (nft_id, nft)
+}
+
 #[test]
 fn test_bidding_and_refunds() {
     let env = Env::default();
@@ -46,16 +76,21 @@ fn test_bidding_and_refunds() {
         min_bid_increment: 10,
     };
 
+    let (nft_id, nft) = create_nft(&env, &seller);
+
     // create_auction args...
     let auction_id = client.create_auction(
         &seller,
-        &Address::generate(&env), // Random NFT
+        &nft_id,
         &1u64,
         &token_contract_id, // Use our fake token
         &AuctionType::English,
         &settings,
     );
 
+    // The contract holds the NFT while the auction runs
+    assert_eq!(nft.owner_of(&1u64), contract_id);
+
     // Move time forward so the auction is "Open"
     env.ledger().set_timestamp(1500);
 
@@ -126,9 +161,11 @@ fn test_dutch_auction_decay() {
         min_bid_increment: 0,
     };
 
+    let (nft_id, nft) = create_nft(&env, &seller);
+
     let auction_id = client.create_auction(
         &seller,
-        &Address::generate(&env),
+        &nft_id,
         &1u64,
         &token_contract_id,
         &AuctionType::Dutch,
@@ -150,7 +187,7 @@ fn test_dutch_auction_decay() {
     let auction = client.get_auction(&auction_id).unwrap();
 
     // Status checks
-    assert_eq!(auction.settled, true);
+    assert!(auction.settled);
     assert_eq!(auction.highest_bidder, Some(buyer.clone()));
 
     // Price check: Did they pay 300?
@@ -158,4 +195,363 @@ fn test_dutch_auction_decay() {
 
     // Balance check: Buyer started with 1000, paid 300. Remainder: 700.
     assert_eq!(token_client.balance(&buyer), 700);
-}
\ No newline at end of file
+    assert_eq!(token_client.balance(&seller), 300);
+    assert_eq!(nft.owner_of(&1u64), buyer);
+}
+
+#[test]
+fn test_soft_close_and_keeper_settlement() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let token_contract_id = env.register_stellar_asset_contract_v2(admin.clone()).address();
+    let token_client = token::Client::new(&env, &token_contract_id);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract_id);
+
+    let contract_id = env.register_contract(None, AuctionContract);
+    let client = AuctionContractClient::new(&env, &contract_id);
+    client.init(&admin);
+    client.set_soft_close(&admin, &600, &900);
+    client.set_keeper_reward(&admin, &100); // 1%
+
+    let seller = Address::generate(&env);
+    let bidder = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    token_admin_client.mint(&bidder, &1000);
+    let (nft_id, nft) = create_nft(&env, &seller);
+
+    let settings = AuctionSettings {
+        start_time: 0,
+        end_time: 2000,
+        starting_price: 100,
+        reserve_price: 150,
+        buy_now_price: 0,
+        min_bid_increment: 10,
+    };
+    let auction_id = client.create_auction(
+        &seller,
+        &nft_id,
+        &1u64,
+        &token_contract_id,
+        &AuctionType::English,
+        &settings,
+    );
+
+    // A bid 500s before the end lands inside the 600s window
+    env.ledger().set_timestamp(1500);
+    client.place_bid(&bidder, &auction_id, &500);
+    let auction = client.get_auction(&auction_id).unwrap();
+    assert_eq!(auction.settings.end_time, 2400);
+
+    // Too early to settle
+    env.ledger().set_timestamp(2000);
+    assert!(client.try_settle_auction(&keeper, &auction_id).is_err());
+
+    env.ledger().set_timestamp(2400);
+    client.settle_auction(&keeper, &auction_id);
+
+    assert_eq!(token_client.balance(&keeper), 5);
+    assert_eq!(token_client.balance(&seller), 495);
+    assert_eq!(nft.owner_of(&1u64), bidder);
+    assert!(client.get_auction(&auction_id).unwrap().settled);
+}
+
+#[test]
+fn test_reserve_not_met_and_cancel() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let token_contract_id = env.register_stellar_asset_contract_v2(admin.clone()).address();
+    let token_client = token::Client::new(&env, &token_contract_id);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract_id);
+
+    let contract_id = env.register_contract(None, AuctionContract);
+    let client = AuctionContractClient::new(&env, &contract_id);
+    client.init(&admin);
+    client.set_keeper_reward(&admin, &100);
+
+    let seller = Address::generate(&env);
+    let bidder = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    token_admin_client.mint(&bidder, &1000);
+    let (nft_id, nft) = create_nft(&env, &seller);
+    nft.mint(&seller, &2u64);
+
+    let settings = AuctionSettings {
+        start_time: 0,
+        end_time: 1000,
+        starting_price: 100,
+        reserve_price: 300,
+        buy_now_price: 0,
+        min_bid_increment: 10,
+    };
+    let auction_id = client.create_auction(
+        &seller,
+        &nft_id,
+        &1u64,
+        &token_contract_id,
+        &AuctionType::English,
+        &settings,
+    );
+    let unsold_id = client.create_auction(
+        &seller,
+        &nft_id,
+        &2u64,
+        &token_contract_id,
+        &AuctionType::English,
+        &settings,
+    );
+
+    env.ledger().set_timestamp(100);
+    client.place_bid(&bidder, &auction_id, &200);
+
+    // Only auctions without bids can be cancelled
+    assert!(client.try_cancel_auction(&seller, &auction_id).is_err());
+    client.cancel_auction(&seller, &unsold_id);
+    assert_eq!(nft.owner_of(&2u64), seller);
+    assert!(client.get_auction(&unsold_id).unwrap().cancelled);
+
+    // Settling below the reserve refunds the bidder and returns the NFT
+    env.ledger().set_timestamp(1000);
+    client.settle_auction(&keeper, &auction_id);
+
+    assert_eq!(token_client.balance(&bidder), 1000);
+    assert_eq!(token_client.balance(&keeper), 0);
+    assert_eq!(token_client.balance(&seller), 0);
+    assert_eq!(nft.owner_of(&1u64), seller);
+}
+
+#[test]
+fn test_sealed_bid_second_price() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let token_contract_id = env.register_stellar_asset_contract_v2(admin.clone()).address();
+    let token_client = token::Client::new(&env, &token_contract_id);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract_id);
+
+    let contract_id = env.register_contract(None, AuctionContract);
+    let client = AuctionContractClient::new(&env, &contract_id);
+    client.init(&admin);
+    client.set_reveal_period(&admin, &500);
+
+    let seller = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let carol = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    for bidder in [&alice, &bob, &carol] {
+        token_admin_client.mint(bidder, &1000);
+    }
+    let (nft_id, nft) = create_nft(&env, &seller);
+
+    let settings = AuctionSettings {
+        start_time: 0,
+        end_time: 1000,
+        starting_price: 100,
+        reserve_price: 150,
+        buy_now_price: 0,
+        min_bid_increment: 0,
+    };
+    let auction_id = client.create_auction(
+        &seller,
+        &nft_id,
+        &1u64,
+        &token_contract_id,
+        &AuctionType::SealedBid,
+        &settings,
+    );
+    assert_eq!(
+        client.get_sealed_round(&auction_id).unwrap().reveal_end,
+        1500
+    );
+
+    // Commit phase: deposits hide the real bids
+    let alice_salt = BytesN::from_array(&env, &[1; 32]);
+    let bob_salt = BytesN::from_array(&env, &[2; 32]);
+    let carol_salt = BytesN::from_array(&env, &[3; 32]);
+    env.ledger().set_timestamp(100);
+    client.commit_bid(
+        &alice,
+        &auction_id,
+        &client.bid_commitment(&auction_id, &alice, &400, &alice_salt),
+        &600,
+    );
+    client.commit_bid(
+        &bob,
+        &auction_id,
+        &client.bid_commitment(&auction_id, &bob, &250, &bob_salt),
+        &300,
+    );
+    client.commit_bid(
+        &carol,
+        &auction_id,
+        &client.bid_commitment(&auction_id, &carol, &900, &carol_salt),
+        &900,
+    );
+    assert_eq!(token_client.balance(&contract_id), 1800);
+
+    // Bids live in persistent storage, outside the instance entry
+    env.as_contract(&contract_id, || {
+        let key = DataKey::SealedBid(auction_id, alice.clone());
+        assert!(!env.storage().instance().has(&key));
+        assert!(env.storage().persistent().has(&key));
+    });
+    // Losers can't withdraw before settlement
+    assert!(client.try_withdraw_bid(&bob, &auction_id).is_err());
+
+    // Reveals only open once bidding closes
+    assert!(client
+        .try_reveal_bid(&alice, &auction_id, &400, &alice_salt)
+        .is_err());
+
+    env.ledger().set_timestamp(1200);
+    // A wrong amount doesn't match the commitment
+    assert!(client
+        .try_reveal_bid(&bob, &auction_id, &300, &bob_salt)
+        .is_err());
+    client.reveal_bid(&bob, &auction_id, &250, &bob_salt);
+    client.reveal_bid(&alice, &auction_id, &400, &alice_salt);
+    // Carol never reveals
+
+    let auction = client.get_auction(&auction_id).unwrap();
+    assert_eq!(auction.highest_bidder, Some(alice.clone()));
+    assert_eq!(
+        client.get_sealed_round(&auction_id).unwrap().second_price,
+        250
+    );
+
+    assert!(client.try_settle_auction(&keeper, &auction_id).is_err());
+    env.ledger().set_timestamp(1501);
+    client.settle_auction(&keeper, &auction_id);
+
+    // Alice pays Bob's price and Carol's deposit goes to the seller
+    assert_eq!(nft.owner_of(&1u64), alice);
+    assert_eq!(token_client.balance(&alice), 750);
+    assert_eq!(token_client.balance(&carol), 100);
+    assert_eq!(token_client.balance(&seller), 250 + 900);
+    assert_eq!(client.get_auction(&auction_id).unwrap().current_bid, 250);
+
+    // Bob reclaims his deposit himself; the winner and Carol have nothing to claim
+    assert_eq!(token_client.balance(&bob), 700);
+    assert_eq!(client.withdraw_bid(&bob, &auction_id), 300);
+    assert_eq!(token_client.balance(&bob), 1000);
+    assert!(client.try_withdraw_bid(&bob, &auction_id).is_err());
+    assert!(client.try_withdraw_bid(&alice, &auction_id).is_err());
+    assert!(client.try_withdraw_bid(&carol, &auction_id).is_err());
+    assert_eq!(token_client.balance(&contract_id), 0);
+}
+
+#[test]
+fn test_sealed_bid_single_revealer_pays_reserve() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let token_contract_id = env.register_stellar_asset_contract_v2(admin.clone()).address();
+    let token_client = token::Client::new(&env, &token_contract_id);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract_id);
+
+    let contract_id = env.register_contract(None, AuctionContract);
+    let client = AuctionContractClient::new(&env, &contract_id);
+    client.init(&admin);
+
+    let seller = Address::generate(&env);
+    let bidder = Address::generate(&env);
+    token_admin_client.mint(&bidder, &1000);
+    let (nft_id, nft) = create_nft(&env, &seller);
+
+    let settings = AuctionSettings {
+        start_time: 0,
+        end_time: 1000,
+        starting_price: 100,
+        reserve_price: 150,
+        buy_now_price: 0,
+        min_bid_increment: 0,
+    };
+    let auction_id = client.create_auction(
+        &seller,
+        &nft_id,
+        &1u64,
+        &token_contract_id,
+        &AuctionType::SealedBid,
+        &settings,
+    );
+
+    let salt = BytesN::from_array(&env, &[7; 32]);
+    client.commit_bid(
+        &bidder,
+        &auction_id,
+        &client.bid_commitment(&auction_id, &bidder, &500, &salt),
+        &500,
+    );
+
+    // Committed bids block cancellation
+    assert!(client.try_cancel_auction(&seller, &auction_id).is_err());
+
+    env.ledger().set_timestamp(1001);
+    client.reveal_bid(&bidder, &auction_id, &500, &salt);
+
+    // Default reveal period is one day
+    env.ledger().set_timestamp(1000 + 86_401);
+    client.settle_auction(&bidder, &auction_id);
+
+    assert_eq!(nft.owner_of(&1u64), bidder);
+    assert_eq!(token_client.balance(&bidder), 850);
+    assert_eq!(token_client.balance(&seller), 150);
+}
+
+#[test]
+fn test_sealed_bid_commitment_cannot_be_copied() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let token_contract_id = env.register_stellar_asset_contract_v2(admin.clone()).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract_id);
+
+    let contract_id = env.register_contract(None, AuctionContract);
+    let client = AuctionContractClient::new(&env, &contract_id);
+    client.init(&admin);
+
+    let seller = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let mallory = Address::generate(&env);
+    token_admin_client.mint(&alice, &1000);
+    token_admin_client.mint(&mallory, &1000);
+    let (nft_id, _) = create_nft(&env, &seller);
+
+    let settings = AuctionSettings {
+        start_time: 0,
+        end_time: 1000,
+        starting_price: 100,
+        reserve_price: 150,
+        buy_now_price: 0,
+        min_bid_increment: 0,
+    };
+    let auction_id = client.create_auction(
+        &seller,
+        &nft_id,
+        &1u64,
+        &token_contract_id,
+        &AuctionType::SealedBid,
+        &settings,
+    );
+
+    // Mallory copies Alice's visible commitment
+    let salt = BytesN::from_array(&env, &[9; 32]);
+    let commitment = client.bid_commitment(&auction_id, &alice, &500, &salt);
+    client.commit_bid(&alice, &auction_id, &commitment, &500);
+    client.commit_bid(&mallory, &auction_id, &commitment, &500);
+
+    // ...but can't replay Alice's reveal to push up the second price
+    env.ledger().set_timestamp(1001);
+    client.reveal_bid(&alice, &auction_id, &500, &salt);
+    assert!(client
+        .try_reveal_bid(&mallory, &auction_id, &500, &salt)
+        .is_err());
+    assert_eq!(client.get_sealed_round(&auction_id).unwrap().second_price, 0);
+}
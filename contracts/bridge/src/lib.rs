@@ -1,6 +1,6 @@
 #![no_std]
 
-use soroban_sdk::{contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, Bytes, BytesN, Env, Map, Vec};
+use soroban_sdk::{contract, contracterror, contractimpl, contracttype, symbol_short, token, xdr::{FromXdr, ToXdr}, Address, Bytes, BytesN, Env, IntoVal, Map, Symbol, Vec};
 
 /// Cross-Chain Asset Bridge Contract
 ///
@@ -9,8 +9,10 @@ use soroban_sdk::{contract, contracterror, contractimpl, contracttype, symbol_sh
 ///
 /// # Bridge Security Model
 /// - Multi-signature validation from authorized validators
+/// - Batched relaying via validator-attested Merkle roots
 /// - Reentrancy protection on all asset operations
-/// - Emergency pause functionality
+/// - Emergency pause functionality, tripped automatically when outflow limits are hit
+/// - Rate limits and a delay queue for large inbound transfers
 /// - Fee collection for bridge operations
 /// - Event monitoring for off-chain tracking
 
@@ -36,6 +38,7 @@ pub enum BridgeStatus {
     Completed = 2,   // Successfully processed
     Failed = 3,      // Failed validation
     Cancelled = 4,   // Cancelled by user/admin
+    Delayed = 5,     // Queued until the transfer delay elapses
 }
 
 /// Cross-chain message format for asset transfers
@@ -100,6 +103,58 @@ pub struct BridgeConfig {
     pub paused: bool,
     /// Chain ID for this bridge instance
     pub chain_id: u32,
+    /// Token fees are paid in (None = the bridged asset itself)
+    pub fee_token: Option<Address>,
+    /// Largest single token transfer allowed (0 = no cap)
+    pub max_transfer_amount: i128,
+    /// Inbound transfers at or above this amount go through the delay queue (0 = disabled)
+    pub delay_threshold: i128,
+    /// Seconds a queued transfer waits before it can be executed
+    pub transfer_delay: u64,
+    /// Length of the rolling window used for outflow limits, in seconds
+    pub limit_window: u64,
+}
+
+/// Fee parameters for transfers to one destination chain
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeSchedule {
+    /// Fee in basis points of the amount, when paid in the bridged asset
+    pub base_fee_bps: u32,
+    /// Minimum fee, and the flat fee when paid in a separate fee token
+    pub min_fee: i128,
+    /// Maximum fee
+    pub max_fee: i128,
+}
+
+/// Fee owed for a transfer and the token it is paid in
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeQuote {
+    pub fee_token: Address,
+    pub amount: i128,
+}
+
+/// Merkle root over a batch of messages from one source chain, attested by validators
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct BatchRoot {
+    pub source_chain: u32,
+    pub epoch: u64,
+    pub root: BytesN<32>,
+    pub message_count: u32,
+    /// Messages from this batch completed so far, never more than `message_count`
+    pub executed: u32,
+    pub attested_at: u64,
+}
+
+/// Large inbound transfer waiting out the delay before release
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct DelayedTransfer {
+    pub message: BridgeMessage,
+    pub queued_at: u64,
+    pub release_at: u64,
 }
 
 /// Locked asset information
@@ -145,6 +200,7 @@ pub struct WrappedNFT {
 pub enum DataKey {
     Config,
     Validators,                    // Vec<Address>
+    ValidatorKeys,                 // Map<Address, BytesN<32>> - ed25519 public keys
     ValidatorSetVersion,          // u32
     LockedAssets(BytesN<32>),     // LockedAsset
     WrappedNFTs(i128),           // WrappedNFT
@@ -154,6 +210,15 @@ pub enum DataKey {
     UserNonces(Address),         // u64
     BridgeNonces,                // u64
     FeeBalance(Address),         // i128 - accumulated fees per token
+    OutboundMessages(BytesN<32>), // BridgeMessage - messages initiated on this chain
+    BridgedNFTs(Address, i128),  // bool - native NFTs held by the bridge
+    AssetLimit(Address),         // i128 - max outflow per window for an asset
+    ChainLimit(u32, Address),    // i128 - max outflow per window for an asset from one source chain
+    AssetOutflow(Address),       // Vec<(u64, i128)> - outflow buckets (start, amount) per limited asset
+    ChainOutflow(u32, Address),  // Vec<(u64, i128)> - outflow buckets (start, amount) per limited source chain and asset
+    DelayedTransfers(BytesN<32>), // DelayedTransfer
+    BatchRoots(u32, u64),        // BatchRoot - attested root per source chain and epoch
+    ChainFeeSchedule(u32),       // FeeSchedule - overrides the default fees for a destination chain
 }
 
 /// Custom error codes for the bridge contract
@@ -166,6 +231,8 @@ pub enum Error {
     Unauthorized = 3,
     ContractPaused = 4,
     InsufficientSignatures = 5,
+    /// Reserved: `ed25519_verify` traps on a bad signature instead of
+    /// returning this, but the code stays taken for existing clients.
     InvalidSignature = 6,
     MessageAlreadyProcessed = 7,
     InvalidAssetAmount = 8,
@@ -179,12 +246,34 @@ pub enum Error {
     NFTNotWrapped = 16,
     InvalidRecipient = 17,
     ReentrantCall = 18,
+    DuplicateValidatorKey = 19,
+    TransferTooLarge = 20,
+    TransferNotDelayed = 21,
+    DelayNotElapsed = 22,
+    BatchAlreadyAttested = 23,
+    BatchNotAttested = 24,
+    InvalidMerkleProof = 25,
+    BatchExhausted = 26,
 }
 
 // Constants
 const MAX_VALIDATORS: u32 = 50;
+/// Length of a strkey-encoded account (G...) or contract (C...) address
+const STRKEY_LEN: u32 = 56;
+/// XDR discriminants used when decoding recipient addresses
+const SCV_ADDRESS: [u8; 4] = [0, 0, 0, 18];
+const SC_ADDRESS_TYPE_ACCOUNT: [u8; 4] = [0, 0, 0, 0];
+const SC_ADDRESS_TYPE_CONTRACT: [u8; 4] = [0, 0, 0, 1];
+const PUBLIC_KEY_TYPE_ED25519: [u8; 4] = [0, 0, 0, 0];
+/// Strkey version bytes for account (G...) and contract (C...) addresses
+const STRKEY_VERSION_ACCOUNT: u8 = 6 << 3;
+const STRKEY_VERSION_CONTRACT: u8 = 2 << 3;
 const BASIS_POINTS: u32 = 10000;
 const MAX_CHAIN_ID: u32 = 1000;
+const DEFAULT_LIMIT_WINDOW: u64 = 24 * 60 * 60;
+/// Number of time buckets a limit window is split into for outflow tracking
+const OUTFLOW_BUCKETS: u64 = 24;
+const DEFAULT_TRANSFER_DELAY: u64 = 6 * 60 * 60;
 
 #[contract]
 pub struct BridgeContract;
@@ -227,6 +316,11 @@ impl BridgeContract {
             max_fee: 1_000_000_000_000, // 1M XLM maximum
             paused: false,
             chain_id,
+            fee_token: None,
+            max_transfer_amount: 0,
+            delay_threshold: 0,
+            transfer_delay: DEFAULT_TRANSFER_DELAY,
+            limit_window: DEFAULT_LIMIT_WINDOW,
         };
 
         storage.set(&DataKey::Config, &config);
@@ -240,8 +334,8 @@ impl BridgeContract {
 
     // ───────────── ADMIN FUNCTIONS ─────────────
 
-    /// Add a validator (admin only)
-    pub fn add_validator(env: Env, admin: Address, validator: Address) -> Result<(), Error> {
+    /// Add a validator with its ed25519 public key (admin only)
+    pub fn add_validator(env: Env, admin: Address, validator: Address, public_key: BytesN<32>) -> Result<(), Error> {
         admin.require_auth();
         Self::assert_admin(&env, &admin)?;
         Self::assert_not_paused(&env)?;
@@ -252,12 +346,21 @@ impl BridgeContract {
             return Err(Error::InvalidMessage);
         }
 
-        if (validators.len() as u32) >= MAX_VALIDATORS {
+        if validators.len() >= MAX_VALIDATORS {
             return Err(Error::InvalidMessage);
         }
 
+        let mut keys: Map<Address, BytesN<32>> = env.storage().instance().get(&DataKey::ValidatorKeys).unwrap_or(Map::new(&env));
+        for existing in keys.values() {
+            if existing == public_key {
+                return Err(Error::DuplicateValidatorKey);
+            }
+        }
+        keys.set(validator.clone(), public_key);
+
         validators.push_back(validator.clone());
         env.storage().instance().set(&DataKey::Validators, &validators);
+        env.storage().instance().set(&DataKey::ValidatorKeys, &keys);
 
         // Increment validator set version
         let version: u32 = env.storage().instance().get(&DataKey::ValidatorSetVersion).unwrap_or(1);
@@ -277,7 +380,7 @@ impl BridgeContract {
         Self::assert_admin(&env, &admin)?;
         Self::assert_not_paused(&env)?;
 
-        let mut validators: Vec<Address> = env.storage().instance().get(&DataKey::Validators).unwrap_or(Vec::new(&env));
+        let validators: Vec<Address> = env.storage().instance().get(&DataKey::Validators).unwrap_or(Vec::new(&env));
 
         let mut new_validators: Vec<Address> = Vec::new(&env);
         let mut found = false;
@@ -296,6 +399,10 @@ impl BridgeContract {
 
         env.storage().instance().set(&DataKey::Validators, &new_validators);
 
+        let mut keys: Map<Address, BytesN<32>> = env.storage().instance().get(&DataKey::ValidatorKeys).unwrap_or(Map::new(&env));
+        keys.remove(validator.clone());
+        env.storage().instance().set(&DataKey::ValidatorKeys, &keys);
+
         // Increment validator set version
         let version: u32 = env.storage().instance().get(&DataKey::ValidatorSetVersion).unwrap_or(1);
         env.storage().instance().set(&DataKey::ValidatorSetVersion, &(version + 1));
@@ -319,6 +426,8 @@ impl BridgeContract {
         admin.require_auth();
         Self::assert_admin(&env, &admin)?;
 
+        Self::validate_fee_schedule(&FeeSchedule { base_fee_bps, min_fee, max_fee })?;
+
         let mut config: BridgeConfig = env.storage().instance().get(&DataKey::Config).unwrap();
 
         config.base_fee_bps = base_fee_bps;
@@ -330,18 +439,145 @@ impl BridgeContract {
         Ok(())
     }
 
+    /// Set the token fees are paid in; `None` charges fees in the bridged asset (admin only)
+    pub fn set_fee_token(env: Env, admin: Address, fee_token: Option<Address>) -> Result<(), Error> {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin)?;
+
+        let mut config: BridgeConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        config.fee_token = fee_token;
+        env.storage().instance().set(&DataKey::Config, &config);
+
+        Ok(())
+    }
+
+    /// Override the default fees for transfers to one destination chain (admin only)
+    pub fn set_chain_fee_schedule(env: Env, admin: Address, dest_chain: u32, schedule: FeeSchedule) -> Result<(), Error> {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin)?;
+
+        if dest_chain == 0 || dest_chain > MAX_CHAIN_ID {
+            return Err(Error::InvalidChainId);
+        }
+        Self::validate_fee_schedule(&schedule)?;
+
+        env.storage().instance().set(&DataKey::ChainFeeSchedule(dest_chain), &schedule);
+
+        Ok(())
+    }
+
+    /// Drop a destination chain override, falling back to the default fees (admin only)
+    pub fn remove_chain_fee_schedule(env: Env, admin: Address, dest_chain: u32) -> Result<(), Error> {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin)?;
+
+        env.storage().instance().remove(&DataKey::ChainFeeSchedule(dest_chain));
+
+        Ok(())
+    }
+
+    /// Withdraw accumulated fees (fee collector only)
+    pub fn withdraw_fees(env: Env, collector: Address, fee_token: Address, amount: i128) -> Result<(), Error> {
+        collector.require_auth();
+
+        let config: BridgeConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        if config.fee_collector != collector {
+            return Err(Error::Unauthorized);
+        }
+
+        if amount <= 0 {
+            return Err(Error::InvalidAssetAmount);
+        }
+
+        let balance: i128 = env.storage().instance().get(&DataKey::FeeBalance(fee_token.clone())).unwrap_or(0);
+        if amount > balance {
+            return Err(Error::InsufficientBalance);
+        }
+
+        env.storage().instance().set(&DataKey::FeeBalance(fee_token.clone()), &(balance - amount));
+
+        let token_client = token::Client::new(&env, &fee_token);
+        token_client.transfer(&env.current_contract_address(), &collector, &amount);
+
+        env.events().publish(
+            (symbol_short!("B_FEEW"), fee_token),
+            (collector, amount),
+        );
+
+        Ok(())
+    }
+
     /// Emergency pause/unpause (admin only)
     pub fn set_paused(env: Env, admin: Address, paused: bool) -> Result<(), Error> {
         admin.require_auth();
         Self::assert_admin(&env, &admin)?;
 
+        Self::write_paused(&env, paused);
+
+        Ok(())
+    }
+
+    /// Configure the single-transfer cap, the delay queue and the rate limit window (admin only)
+    pub fn set_transfer_limits(
+        env: Env,
+        admin: Address,
+        max_transfer_amount: i128,
+        delay_threshold: i128,
+        transfer_delay: u64,
+        limit_window: u64,
+    ) -> Result<(), Error> {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin)?;
+
+        if max_transfer_amount < 0 || delay_threshold < 0 || limit_window == 0 {
+            return Err(Error::InvalidAssetAmount);
+        }
+
         let mut config: BridgeConfig = env.storage().instance().get(&DataKey::Config).unwrap();
-        config.paused = paused;
+        config.max_transfer_amount = max_transfer_amount;
+        config.delay_threshold = delay_threshold;
+        config.transfer_delay = transfer_delay;
+        config.limit_window = limit_window;
         env.storage().instance().set(&DataKey::Config, &config);
 
+        Ok(())
+    }
+
+    /// Cap total outflow of an asset per rolling window (admin only, 0 removes the limit)
+    pub fn set_asset_limit(env: Env, admin: Address, asset: Address, limit: i128) -> Result<(), Error> {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin)?;
+
+        Self::write_limit(&env, DataKey::AssetLimit(asset), limit)
+    }
+
+    /// Cap outflow of an asset released by messages from one source chain (admin only, 0 removes the limit)
+    pub fn set_chain_limit(env: Env, admin: Address, source_chain: u32, asset: Address, limit: i128) -> Result<(), Error> {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin)?;
+
+        if source_chain > MAX_CHAIN_ID {
+            return Err(Error::InvalidChainId);
+        }
+
+        Self::write_limit(&env, DataKey::ChainLimit(source_chain, asset), limit)
+    }
+
+    /// Drop a queued transfer before it is released (admin only)
+    pub fn cancel_delayed(env: Env, admin: Address, message_id: BytesN<32>) -> Result<(), Error> {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin)?;
+
+        let delayed: DelayedTransfer = env.storage().instance()
+            .get(&DataKey::DelayedTransfers(message_id.clone()))
+            .ok_or(Error::TransferNotDelayed)?;
+
+        env.storage().instance().remove(&DataKey::DelayedTransfers(message_id.clone()));
+        Self::set_message_status(&env, &message_id, BridgeStatus::Cancelled);
+
         env.events().publish(
-            (symbol_short!("B_PAUSE"), paused),
-            env.ledger().timestamp(),
+            (symbol_short!("B_DCANCEL"), message_id),
+            delayed.message.asset_amount,
         );
 
         Ok(())
@@ -376,12 +612,28 @@ impl BridgeContract {
 
         let config: BridgeConfig = env.storage().instance().get(&DataKey::Config).unwrap();
 
+        // Calculate bridge fee
+        let fee = Self::quote(&env, &config, &asset_address, asset_type, amount, dest_chain)?;
+        let fee_amount = fee.as_ref().map(|q| q.amount).unwrap_or(0);
+
         // Check sender balance
         match asset_type {
             AssetType::Token => {
+                if config.max_transfer_amount > 0 && amount > config.max_transfer_amount {
+                    return Err(Error::TransferTooLarge);
+                }
+
+                // A fee paid in the asset itself comes on top of the bridged amount
+                let mut required = amount;
+                if let Some(quote) = &fee {
+                    if quote.fee_token == asset_address {
+                        required += quote.amount;
+                    }
+                }
+
                 let token_client = token::Client::new(&env, &asset_address);
                 let balance = token_client.balance(&sender);
-                if balance < amount {
+                if balance < required {
                     return Err(Error::InsufficientBalance);
                 }
             }
@@ -391,11 +643,8 @@ impl BridgeContract {
             }
         }
 
-        // Calculate bridge fee
-        let fee_amount = Self::calculate_fee(&env, amount, &config)?;
-
         // Generate unique message ID
-        let message_id = Self::generate_message_id(&env, &sender, asset_type.clone(), amount, dest_chain);
+        let message_id = Self::generate_message_id(&env, &sender, asset_type, amount, dest_chain);
 
         // Check for replay attack
         let processed: Option<BridgeStatus> = env.storage().instance().get(&DataKey::ProcessedMessages)
@@ -412,9 +661,9 @@ impl BridgeContract {
                 token_client.transfer(&sender, &env.current_contract_address(), &amount);
             }
             AssetType::NFT => {
-                // For NFTs, we need to handle the transfer
-                // This would typically involve calling the NFT contract
-                // For now, we'll store the lock information
+                // For NFTs, amount represents token_id
+                Self::transfer_nft(&env, &asset_address, &sender, &env.current_contract_address(), amount)?;
+                env.storage().instance().set(&DataKey::BridgedNFTs(asset_address.clone(), amount), &true);
             }
         }
 
@@ -422,7 +671,7 @@ impl BridgeContract {
         let locked_asset = LockedAsset {
             owner: sender.clone(),
             asset_address: asset_address.clone(),
-            asset_type: asset_type.clone(),
+            asset_type,
             amount,
             locked_at: env.ledger().timestamp(),
             message_id: message_id.clone(),
@@ -444,15 +693,18 @@ impl BridgeContract {
             sender,
             recipient,
             fee_amount,
-            fee_token: None, // Using same token as asset
+            fee_token: config.fee_token.clone(),
             timestamp: env.ledger().timestamp(),
             nonce: Self::get_next_bridge_nonce(&env),
         };
 
-        // Collect fee
-        if fee_amount > 0 {
-            // For now, fees are collected in the asset token
-            // In production, you might want separate fee tokens
+        // Collect fee; it stays with the bridge until the fee collector withdraws it
+        if let Some(quote) = fee {
+            let token_client = token::Client::new(&env, &quote.fee_token);
+            token_client.transfer(&message.sender, &env.current_contract_address(), &quote.amount);
+
+            let balance: i128 = env.storage().instance().get(&DataKey::FeeBalance(quote.fee_token.clone())).unwrap_or(0);
+            env.storage().instance().set(&DataKey::FeeBalance(quote.fee_token), &(balance + quote.amount));
         }
 
         // Initialize message status
@@ -462,6 +714,9 @@ impl BridgeContract {
         processed_messages.set(message_id.clone(), BridgeStatus::Pending);
         env.storage().instance().set(&DataKey::ProcessedMessages, &processed_messages);
 
+        // Keep the full message so relayers can pick it up for the destination chain
+        env.storage().instance().set(&DataKey::OutboundMessages(message_id.clone()), &message);
+
         // Emit bridge initiation event
         env.events().publish(
             (symbol_short!("B_INIT"), message_id.clone()),
@@ -472,12 +727,16 @@ impl BridgeContract {
     }
 
     /// Complete cross-chain transfer (unlock assets) - validator only
+    ///
+    /// Returns `Completed` when the assets were released, `Delayed` when the
+    /// transfer was queued, or `Pending` when it tripped an outflow limit and
+    /// paused the bridge.
     pub fn complete_bridge(
         env: Env,
         validator: Address,
         message: BridgeMessage,
         signatures: Vec<ValidatorSignature>,
-    ) -> Result<(), Error> {
+    ) -> Result<BridgeStatus, Error> {
         validator.require_auth();
         Self::assert_not_paused(&env)?;
 
@@ -488,50 +747,148 @@ impl BridgeContract {
         }
 
         let config: BridgeConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        Self::check_inbound(&env, &config, &message)?;
 
-        // Verify message hasn't been processed
-        let processed: Option<BridgeStatus> = env.storage().instance().get(&DataKey::ProcessedMessages)
-            .and_then(|m: Map<BytesN<32>, BridgeStatus>| m.get(message.message_id.clone()));
+        // Verify signatures
+        let digest = Self::signing_digest(&env, Self::message_to_bytes(&env, &message));
+        Self::verify_signatures(&env, &digest, &signatures, &validators, config.required_signatures)?;
 
-        if let Some(status) = processed {
-            if status != BridgeStatus::Pending {
-                return Err(Error::MessageAlreadyProcessed);
-            }
+        let status = Self::finalize_inbound(&env, &config, &message)?;
+
+        if status != BridgeStatus::Pending {
+            // Store signatures for audit
+            env.storage().instance().set(&DataKey::MessageSignatures(message.message_id.clone()), &signatures);
         }
 
-        // Verify signatures
-        Self::verify_signatures(&env, &message, &signatures, &validators, config.required_signatures)?;
+        Ok(status)
+    }
 
-        // Process the bridge action
-        match message.action {
-            BridgeAction::Unlock => {
-                Self::process_unlock(&env, &message)?;
-            }
-            BridgeAction::Lock => {
-                // Lock actions are initiated from source, not completed here
-                return Err(Error::InvalidMessage);
-            }
+    /// Attest the Merkle root over a batch of messages from `source_chain` - validator only
+    ///
+    /// Validators sign `batch_digest(source_chain, epoch, root, message_count)`;
+    /// each message in the batch can then be completed with an inclusion proof.
+    pub fn attest_batch_root(
+        env: Env,
+        validator: Address,
+        source_chain: u32,
+        epoch: u64,
+        root: BytesN<32>,
+        message_count: u32,
+        signatures: Vec<ValidatorSignature>,
+    ) -> Result<(), Error> {
+        validator.require_auth();
+        Self::assert_not_paused(&env)?;
+
+        let validators: Vec<Address> = env.storage().instance().get(&DataKey::Validators).unwrap_or(Vec::new(&env));
+        if !validators.contains(&validator) {
+            return Err(Error::Unauthorized);
         }
 
-        // Update message status
-        let mut processed_messages: Map<BytesN<32>, BridgeStatus> = env.storage().instance()
-            .get(&DataKey::ProcessedMessages)
-            .unwrap_or(Map::new(&env));
-        processed_messages.set(message.message_id.clone(), BridgeStatus::Completed);
-        env.storage().instance().set(&DataKey::ProcessedMessages, &processed_messages);
+        if source_chain > MAX_CHAIN_ID {
+            return Err(Error::InvalidChainId);
+        }
+        if message_count == 0 {
+            return Err(Error::InvalidMessage);
+        }
+
+        let key = DataKey::BatchRoots(source_chain, epoch);
+        if env.storage().instance().has(&key) {
+            return Err(Error::BatchAlreadyAttested);
+        }
 
-        // Store signatures for audit
-        env.storage().instance().set(&DataKey::MessageSignatures(message.message_id.clone()), &signatures);
+        let config: BridgeConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        let digest = Self::signing_digest(&env, Self::batch_to_bytes(&env, source_chain, epoch, &root, message_count));
+        Self::verify_signatures(&env, &digest, &signatures, &validators, config.required_signatures)?;
+
+        let batch = BatchRoot {
+            source_chain,
+            epoch,
+            root: root.clone(),
+            message_count,
+            executed: 0,
+            attested_at: env.ledger().timestamp(),
+        };
+        env.storage().instance().set(&key, &batch);
 
-        // Emit completion event
         env.events().publish(
-            (symbol_short!("B_COMP"), message.message_id),
-            (message.action, message.asset_amount),
+            (symbol_short!("B_ROOT"), source_chain, epoch),
+            (root, message_count),
         );
 
         Ok(())
     }
 
+    /// Complete a transfer included in an attested batch (anyone can relay)
+    ///
+    /// `proof` holds the sibling hashes from the message leaf up to the root;
+    /// pairs are hashed in sorted order. At most `message_count` messages can be
+    /// completed per batch. Returns the same statuses as `complete_bridge`.
+    pub fn complete_bridge_with_proof(
+        env: Env,
+        relayer: Address,
+        message: BridgeMessage,
+        epoch: u64,
+        proof: Vec<BytesN<32>>,
+    ) -> Result<BridgeStatus, Error> {
+        relayer.require_auth();
+        Self::assert_not_paused(&env)?;
+
+        let config: BridgeConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        Self::check_inbound(&env, &config, &message)?;
+
+        let batch_key = DataKey::BatchRoots(message.source_chain, epoch);
+        let mut batch: BatchRoot = env.storage().instance()
+            .get(&batch_key)
+            .ok_or(Error::BatchNotAttested)?;
+
+        let leaf: BytesN<32> = env.crypto().sha256(&Self::message_to_bytes(&env, &message)).into();
+        if Self::compute_merkle_root(&env, leaf, proof) != batch.root {
+            return Err(Error::InvalidMerkleProof);
+        }
+        if batch.executed >= batch.message_count {
+            return Err(Error::BatchExhausted);
+        }
+
+        // A message left pending by a tripped limit is resubmitted later, so it
+        // only takes one of the batch's slots once it actually goes through
+        let status = Self::finalize_inbound(&env, &config, &message)?;
+        if status != BridgeStatus::Pending {
+            batch.executed += 1;
+            env.storage().instance().set(&batch_key, &batch);
+        }
+
+        Ok(status)
+    }
+
+    /// Release a queued transfer once its delay has elapsed (anyone can call)
+    pub fn execute_delayed(env: Env, message_id: BytesN<32>) -> Result<BridgeStatus, Error> {
+        Self::assert_not_paused(&env)?;
+
+        let delayed: DelayedTransfer = env.storage().instance()
+            .get(&DataKey::DelayedTransfers(message_id.clone()))
+            .ok_or(Error::TransferNotDelayed)?;
+
+        if env.ledger().timestamp() < delayed.release_at {
+            return Err(Error::DelayNotElapsed);
+        }
+
+        let config: BridgeConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        if !Self::execute_unlock(&env, &config, &delayed.message)? {
+            // Still queued; the bridge is now paused
+            return Ok(BridgeStatus::Delayed);
+        }
+
+        env.storage().instance().remove(&DataKey::DelayedTransfers(message_id.clone()));
+        Self::set_message_status(&env, &message_id, BridgeStatus::Completed);
+
+        env.events().publish(
+            (symbol_short!("B_COMP"), message_id),
+            (delayed.message.action, delayed.message.asset_amount),
+        );
+
+        Ok(BridgeStatus::Completed)
+    }
+
     /// Cancel a pending bridge operation (user or admin)
     pub fn cancel_bridge(
         env: Env,
@@ -568,7 +925,8 @@ impl BridgeContract {
                 token_client.transfer(&env.current_contract_address(), &locked_asset.owner, &locked_asset.amount);
             }
             AssetType::NFT => {
-                // Handle NFT refund
+                Self::transfer_nft(&env, &locked_asset.asset_address, &env.current_contract_address(), &locked_asset.owner, locked_asset.amount)?;
+                env.storage().instance().remove(&DataKey::BridgedNFTs(locked_asset.asset_address.clone(), locked_asset.amount));
             }
         }
 
@@ -625,7 +983,7 @@ impl BridgeContract {
         // Emit wrap event
         env.events().publish(
             (symbol_short!("NFT_WRAP"), wrapped_token_id),
-            (nft_contract.clone(), token_id, dest_chain),
+            (nft_contract.clone(), token_id, dest_chain, recipient),
         );
 
         Ok(wrapped_token_id)
@@ -675,6 +1033,85 @@ impl BridgeContract {
         env.storage().instance().get(&DataKey::Validators).unwrap_or(Vec::new(&env))
     }
 
+    pub fn get_validator_key(env: Env, validator: Address) -> Option<BytesN<32>> {
+        env.storage().instance().get(&DataKey::ValidatorKeys)
+            .and_then(|m: Map<Address, BytesN<32>>| m.get(validator))
+    }
+
+    pub fn get_validator_set_version(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::ValidatorSetVersion).unwrap_or(1)
+    }
+
+    /// Digest validators sign for a message under the current validator set
+    pub fn message_digest(env: Env, message: BridgeMessage) -> BytesN<32> {
+        let payload = Self::message_to_bytes(&env, &message);
+        Self::signing_digest(&env, payload).try_into().unwrap()
+    }
+
+    /// Merkle leaf for a message in a batch (not bound to the validator set version)
+    pub fn message_leaf(env: Env, message: BridgeMessage) -> BytesN<32> {
+        env.crypto().sha256(&Self::message_to_bytes(&env, &message)).into()
+    }
+
+    /// Digest validators sign to attest a batch root under the current validator set
+    pub fn batch_digest(env: Env, source_chain: u32, epoch: u64, root: BytesN<32>, message_count: u32) -> BytesN<32> {
+        let payload = Self::batch_to_bytes(&env, source_chain, epoch, &root, message_count);
+        Self::signing_digest(&env, payload).try_into().unwrap()
+    }
+
+    pub fn get_batch_root(env: Env, source_chain: u32, epoch: u64) -> Option<BatchRoot> {
+        env.storage().instance().get(&DataKey::BatchRoots(source_chain, epoch))
+    }
+
+    pub fn get_delayed_transfer(env: Env, message_id: BytesN<32>) -> Option<DelayedTransfer> {
+        env.storage().instance().get(&DataKey::DelayedTransfers(message_id))
+    }
+
+    pub fn get_asset_limit(env: Env, asset: Address) -> Option<i128> {
+        env.storage().instance().get(&DataKey::AssetLimit(asset))
+    }
+
+    pub fn get_chain_limit(env: Env, source_chain: u32, asset: Address) -> Option<i128> {
+        env.storage().instance().get(&DataKey::ChainLimit(source_chain, asset))
+    }
+
+    /// Amount of an asset released within the current rolling window
+    pub fn get_asset_outflow(env: Env, asset: Address) -> i128 {
+        let config: BridgeConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        Self::window_total(&Self::recent_outflows(&env, &DataKey::AssetOutflow(asset), config.limit_window))
+    }
+
+    /// Amount of an asset released for one source chain within the current rolling window
+    pub fn get_chain_outflow(env: Env, source_chain: u32, asset: Address) -> i128 {
+        let config: BridgeConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        Self::window_total(&Self::recent_outflows(&env, &DataKey::ChainOutflow(source_chain, asset), config.limit_window))
+    }
+
+    /// Fee charged by `bridge_assets` for the same asset, type, amount and destination
+    pub fn quote_fee(env: Env, asset: Address, asset_type: AssetType, amount: i128, dest_chain: u32) -> Result<FeeQuote, Error> {
+        if amount <= 0 {
+            return Err(Error::InvalidAssetAmount);
+        }
+        if dest_chain == 0 || dest_chain > MAX_CHAIN_ID {
+            return Err(Error::InvalidChainId);
+        }
+
+        let config: BridgeConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        let quote = Self::quote(&env, &config, &asset, asset_type, amount, dest_chain)?;
+        let fee_token = config.fee_token.unwrap_or(asset);
+        Ok(quote.unwrap_or(FeeQuote { fee_token, amount: 0 }))
+    }
+
+    /// Fees in effect for a destination chain, including the default when no override is set
+    pub fn get_fee_schedule(env: Env, dest_chain: u32) -> FeeSchedule {
+        let config: BridgeConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        Self::fee_schedule(&env, &config, dest_chain)
+    }
+
+    pub fn get_fee_balance(env: Env, fee_token: Address) -> i128 {
+        env.storage().instance().get(&DataKey::FeeBalance(fee_token)).unwrap_or(0)
+    }
+
     pub fn get_message_status(env: Env, message_id: BytesN<32>) -> Option<BridgeStatus> {
         env.storage().instance().get(&DataKey::ProcessedMessages)
             .and_then(|m: Map<BytesN<32>, BridgeStatus>| m.get(message_id))
@@ -688,6 +1125,15 @@ impl BridgeContract {
         env.storage().instance().get(&DataKey::WrappedNFTs(wrapped_token_id))
     }
 
+    pub fn get_nft_metadata(env: Env, wrapped_token_id: i128) -> Option<NFTMetadata> {
+        env.storage().instance().get(&DataKey::NFTMetadata(wrapped_token_id))
+    }
+
+    /// Full message for a transfer initiated on this chain, for relaying
+    pub fn get_outbound_message(env: Env, message_id: BytesN<32>) -> Option<BridgeMessage> {
+        env.storage().instance().get(&DataKey::OutboundMessages(message_id))
+    }
+
     // ───────────── INTERNAL HELPERS ─────────────
 
     fn generate_message_id(
@@ -698,9 +1144,9 @@ impl BridgeContract {
         dest_chain: u32,
     ) -> BytesN<32> {
         let mut data = Bytes::new(env);
+        data.extend_from_slice(&Self::get_chain_id(env).to_be_bytes());
         data.extend_from_slice(&env.ledger().timestamp().to_be_bytes());
-        // Placeholder for sender address in hash
-        data.extend_from_slice(&[0u8; 32]);
+        data.append(&sender.clone().to_xdr(env));
         data.extend_from_slice(&(asset_type as u32).to_be_bytes());
         data.extend_from_slice(&amount.to_be_bytes());
         data.extend_from_slice(&dest_chain.to_be_bytes());
@@ -718,8 +1164,7 @@ impl BridgeContract {
         dest_chain: u32,
     ) -> i128 {
         let mut data = Bytes::new(env);
-        // Placeholder for NFT contract address
-        data.extend_from_slice(&[0u8; 32]);
+        data.append(&nft_contract.to_xdr(env));
         data.extend_from_slice(&token_id.to_be_bytes());
         data.extend_from_slice(&dest_chain.to_be_bytes());
         data.extend_from_slice(&env.ledger().timestamp().to_be_bytes());
@@ -746,52 +1191,105 @@ impl BridgeContract {
         next
     }
 
-    fn calculate_fee(env: &Env, amount: i128, config: &BridgeConfig) -> Result<i128, Error> {
-        let fee = (amount * config.base_fee_bps as i128) / BASIS_POINTS as i128;
-        let final_fee = fee.max(config.min_fee).min(config.max_fee);
+    fn calculate_fee(amount: i128, schedule: &FeeSchedule) -> Result<i128, Error> {
+        let fee = (amount * schedule.base_fee_bps as i128) / BASIS_POINTS as i128;
+        let final_fee = fee.max(schedule.min_fee).min(schedule.max_fee);
 
-        if final_fee > config.max_fee {
+        if final_fee > schedule.max_fee {
             return Err(Error::FeeTooHigh);
         }
-        if final_fee < config.min_fee {
+        if final_fee < schedule.min_fee {
             return Err(Error::FeeTooLow);
         }
 
         Ok(final_fee)
     }
 
+    /// Work out the fee for a transfer, or `None` when nothing is owed.
+    ///
+    /// Fees paid in the bridged asset use the percentage schedule. Fees paid in
+    /// a separate fee token, and NFT fees, are the flat `min_fee` since the amount
+    /// isn't denominated in the fee token. NFTs are free when no fee token is set.
+    fn quote(env: &Env, config: &BridgeConfig, asset: &Address, asset_type: AssetType, amount: i128, dest_chain: u32) -> Result<Option<FeeQuote>, Error> {
+        let schedule = Self::fee_schedule(env, config, dest_chain);
+
+        let (fee_token, fee) = match (&config.fee_token, asset_type) {
+            (Some(fee_token), _) if fee_token != asset => (fee_token.clone(), schedule.min_fee),
+            (_, AssetType::Token) => (asset.clone(), Self::calculate_fee(amount, &schedule)?),
+            (_, AssetType::NFT) => return Ok(None),
+        };
+
+        if fee <= 0 {
+            return Ok(None);
+        }
+        Ok(Some(FeeQuote { fee_token, amount: fee }))
+    }
+
+    fn fee_schedule(env: &Env, config: &BridgeConfig, dest_chain: u32) -> FeeSchedule {
+        env.storage().instance().get(&DataKey::ChainFeeSchedule(dest_chain)).unwrap_or(FeeSchedule {
+            base_fee_bps: config.base_fee_bps,
+            min_fee: config.min_fee,
+            max_fee: config.max_fee,
+        })
+    }
+
+    fn validate_fee_schedule(schedule: &FeeSchedule) -> Result<(), Error> {
+        if schedule.base_fee_bps > BASIS_POINTS || schedule.max_fee < 0 {
+            return Err(Error::FeeTooHigh);
+        }
+        if schedule.min_fee < 0 || schedule.min_fee > schedule.max_fee {
+            return Err(Error::FeeTooLow);
+        }
+        Ok(())
+    }
+
     fn verify_signatures(
         env: &Env,
-        message: &BridgeMessage,
+        digest: &Bytes,
         signatures: &Vec<ValidatorSignature>,
         validators: &Vec<Address>,
         required: u32,
     ) -> Result<(), Error> {
-        if (signatures.len() as u32) < required {
+        if signatures.len() < required {
             return Err(Error::InsufficientSignatures);
         }
 
-        let message_bytes = Self::message_to_bytes(env, message);
-        let message_hash = env.crypto().sha256(&message_bytes);
-
+        let keys: Map<Address, BytesN<32>> = env.storage().instance().get(&DataKey::ValidatorKeys).unwrap_or(Map::new(env));
+        let mut counted: Vec<Address> = Vec::new(env);
         let mut valid_signatures = 0u32;
 
         for sig in signatures.iter() {
-            if validators.contains(&sig.validator) {
-                // TODO: Implement actual signature verification
-                // For now, we'll assume signatures are valid in tests
-                // In production, this would verify Ed25519 signatures
-                valid_signatures += 1;
+            if !validators.contains(&sig.validator) || counted.contains(&sig.validator) {
+                continue;
             }
+            let public_key = match keys.get(sig.validator.clone()) {
+                Some(key) => key,
+                None => continue,
+            };
+
+            // A bad signature traps the host and aborts the whole call,
+            // so it never surfaces as a contract error.
+            env.crypto().ed25519_verify(&public_key, digest, &sig.signature);
+
+            counted.push_back(sig.validator.clone());
+            valid_signatures += 1;
         }
 
         if valid_signatures < required {
-            return Err(Error::InvalidSignature);
+            return Err(Error::InsufficientSignatures);
         }
 
         Ok(())
     }
 
+    /// Signatures are bound to the validator set version, so any rotation
+    /// invalidates signatures collected under the previous set.
+    fn signing_digest(env: &Env, mut payload: Bytes) -> Bytes {
+        let version: u32 = env.storage().instance().get(&DataKey::ValidatorSetVersion).unwrap_or(1);
+        payload.extend_from_slice(&version.to_be_bytes());
+        env.crypto().sha256(&payload).into()
+    }
+
     fn message_to_bytes(env: &Env, message: &BridgeMessage) -> Bytes {
         let mut data = Bytes::new(env);
         data.extend_from_slice(&message.message_id.to_array());
@@ -799,43 +1297,395 @@ impl BridgeContract {
         data.extend_from_slice(&message.dest_chain.to_be_bytes());
         data.extend_from_slice(&(message.action as u32).to_be_bytes());
         data.extend_from_slice(&(message.asset_type as u32).to_be_bytes());
-        // For hashing, we'll use a simple representation
-        // In production, proper address serialization would be needed
-        data.extend_from_slice(&[0u8; 32]); // Placeholder for address bytes
+        data.append(&message.asset_address.clone().to_xdr(env));
         data.extend_from_slice(&message.asset_amount.to_be_bytes());
-        data.extend_from_slice(&[0u8; 32]); // Placeholder for sender address
-        data.extend_from_slice(message.recipient.to_buffer::<1024>().as_slice());
+        data.append(&message.sender.clone().to_xdr(env));
+        data.append(&message.recipient);
         data.extend_from_slice(&message.fee_amount.to_be_bytes());
         data.extend_from_slice(&message.timestamp.to_be_bytes());
         data.extend_from_slice(&message.nonce.to_be_bytes());
         data
     }
 
-    fn process_unlock(env: &Env, message: &BridgeMessage) -> Result<(), Error> {
-        // For unlock operations, we need to release assets to the recipient
-        // This would typically involve checking if the assets are locked
-        // and then transferring them to the recipient
+    fn batch_to_bytes(env: &Env, source_chain: u32, epoch: u64, root: &BytesN<32>, message_count: u32) -> Bytes {
+        let mut data = Bytes::new(env);
+        data.extend_from_slice(b"batch");
+        data.extend_from_slice(&Self::get_chain_id(env).to_be_bytes());
+        data.extend_from_slice(&source_chain.to_be_bytes());
+        data.extend_from_slice(&epoch.to_be_bytes());
+        data.extend_from_slice(&root.to_array());
+        data.extend_from_slice(&message_count.to_be_bytes());
+        data
+    }
+
+    fn compute_merkle_root(env: &Env, leaf: BytesN<32>, proof: Vec<BytesN<32>>) -> BytesN<32> {
+        let mut current_hash = leaf;
+
+        for proof_element in proof.iter() {
+            let current_bytes = current_hash.to_array();
+            let proof_bytes = proof_element.to_array();
+
+            // Sorted pairs, so proofs don't need to carry left/right positions
+            let mut combined = Bytes::new(env);
+            if current_bytes < proof_bytes {
+                combined.extend_from_slice(&current_bytes);
+                combined.extend_from_slice(&proof_bytes);
+            } else {
+                combined.extend_from_slice(&proof_bytes);
+                combined.extend_from_slice(&current_bytes);
+            }
+
+            current_hash = env.crypto().sha256(&combined).into();
+        }
+
+        current_hash
+    }
+
+    /// Checks shared by every inbound completion path
+    fn check_inbound(env: &Env, config: &BridgeConfig, message: &BridgeMessage) -> Result<(), Error> {
+        if message.dest_chain != config.chain_id {
+            return Err(Error::InvalidChainId);
+        }
+
+        // Verify message hasn't been processed
+        let processed: Option<BridgeStatus> = env.storage().instance().get(&DataKey::ProcessedMessages)
+            .and_then(|m: Map<BytesN<32>, BridgeStatus>| m.get(message.message_id.clone()));
+
+        if let Some(status) = processed {
+            if status != BridgeStatus::Pending {
+                return Err(Error::MessageAlreadyProcessed);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Process a verified inbound message and record its resulting status
+    fn finalize_inbound(env: &Env, config: &BridgeConfig, message: &BridgeMessage) -> Result<BridgeStatus, Error> {
+        // Process the bridge action
+        let status = match message.action {
+            BridgeAction::Unlock => Self::route_unlock(env, config, message)?,
+            BridgeAction::Lock => {
+                // Lock actions are initiated from source, not completed here
+                return Err(Error::InvalidMessage);
+            }
+        };
+
+        // A tripped limit leaves the message pending so it can be resubmitted after review
+        if status == BridgeStatus::Pending {
+            return Ok(status);
+        }
+
+        // Update message status
+        Self::set_message_status(env, &message.message_id, status);
+
+        if status == BridgeStatus::Completed {
+            // Emit completion event
+            env.events().publish(
+                (symbol_short!("B_COMP"), message.message_id.clone()),
+                (message.action, message.asset_amount),
+            );
+        }
+
+        Ok(status)
+    }
+
+    /// Decide whether an inbound unlock is released now, queued, or trips the breaker
+    fn route_unlock(env: &Env, config: &BridgeConfig, message: &BridgeMessage) -> Result<BridgeStatus, Error> {
+        // Limits are denominated in token units; for NFTs the amount is a token id
+        if message.asset_type == AssetType::Token {
+            if config.max_transfer_amount > 0 && message.asset_amount > config.max_transfer_amount {
+                Self::trip_breaker(env, symbol_short!("max_size"), &message.message_id);
+                return Ok(BridgeStatus::Pending);
+            }
+
+            if config.delay_threshold > 0 && message.asset_amount >= config.delay_threshold {
+                let now = env.ledger().timestamp();
+                let delayed = DelayedTransfer {
+                    message: message.clone(),
+                    queued_at: now,
+                    release_at: now + config.transfer_delay,
+                };
+                env.storage().instance().set(&DataKey::DelayedTransfers(message.message_id.clone()), &delayed);
+
+                env.events().publish(
+                    (symbol_short!("B_DELAY"), message.message_id.clone()),
+                    (message.asset_amount, delayed.release_at),
+                );
+                return Ok(BridgeStatus::Delayed);
+            }
+        }
+
+        if Self::execute_unlock(env, config, message)? {
+            Ok(BridgeStatus::Completed)
+        } else {
+            Ok(BridgeStatus::Pending)
+        }
+    }
+
+    /// Check outflow limits and release the assets. Returns false if a limit was hit.
+    fn execute_unlock(env: &Env, config: &BridgeConfig, message: &BridgeMessage) -> Result<bool, Error> {
+        if message.asset_type == AssetType::Token {
+            let asset_key = DataKey::AssetOutflow(message.asset_address.clone());
+            let chain_key = DataKey::ChainOutflow(message.source_chain, message.asset_address.clone());
+
+            // Outflows are only tracked for assets and chains that have a limit
+            let asset_limit: Option<i128> = env.storage().instance().get(&DataKey::AssetLimit(message.asset_address.clone()));
+            let asset_outflows = asset_limit.map(|_| Self::recent_outflows(env, &asset_key, config.limit_window));
+            if let (Some(limit), Some(outflows)) = (asset_limit, &asset_outflows) {
+                if Self::window_total(outflows) + message.asset_amount > limit {
+                    Self::trip_breaker(env, symbol_short!("asset_lim"), &message.message_id);
+                    return Ok(false);
+                }
+            }
+
+            let chain_limit: Option<i128> = env.storage().instance().get(&DataKey::ChainLimit(message.source_chain, message.asset_address.clone()));
+            let chain_outflows = chain_limit.map(|_| Self::recent_outflows(env, &chain_key, config.limit_window));
+            if let (Some(limit), Some(outflows)) = (chain_limit, &chain_outflows) {
+                if Self::window_total(outflows) + message.asset_amount > limit {
+                    Self::trip_breaker(env, symbol_short!("chain_lim"), &message.message_id);
+                    return Ok(false);
+                }
+            }
+
+            if let Some(outflows) = asset_outflows {
+                Self::record_outflow(env, &asset_key, outflows, config.limit_window, message.asset_amount);
+            }
+            if let Some(outflows) = chain_outflows {
+                Self::record_outflow(env, &chain_key, outflows, config.limit_window, message.asset_amount);
+            }
+        }
+
+        Self::process_unlock(env, message)?;
+        Ok(true)
+    }
+
+    /// Outflow buckets that overlap the rolling window, oldest first.
+    /// A whole bucket counts until its end leaves the window, so limits err on the strict side.
+    fn recent_outflows(env: &Env, key: &DataKey, window: u64) -> Vec<(u64, i128)> {
+        let outflows: Vec<(u64, i128)> = env.storage().instance().get(key).unwrap_or(Vec::new(env));
+        let now = env.ledger().timestamp();
+        let width = Self::bucket_width(window);
+
+        let mut recent = Vec::new(env);
+        for (start, amount) in outflows.iter() {
+            if start + width + window > now {
+                recent.push_back((start, amount));
+            }
+        }
+        recent
+    }
+
+    /// Add an outflow to the current bucket, so at most `OUTFLOW_BUCKETS + 1` entries are kept
+    fn record_outflow(env: &Env, key: &DataKey, mut outflows: Vec<(u64, i128)>, window: u64, amount: i128) {
+        let now = env.ledger().timestamp();
+        let start = now - now % Self::bucket_width(window);
+
+        match outflows.last() {
+            Some((last_start, total)) if last_start == start => {
+                outflows.set(outflows.len() - 1, (start, total + amount));
+            }
+            _ => outflows.push_back((start, amount)),
+        }
+        env.storage().instance().set(key, &outflows);
+    }
+
+    fn bucket_width(window: u64) -> u64 {
+        (window / OUTFLOW_BUCKETS).max(1)
+    }
+
+    fn window_total(outflows: &Vec<(u64, i128)>) -> i128 {
+        outflows.iter().map(|(_, amount)| amount).sum()
+    }
+
+    fn write_limit(env: &Env, key: DataKey, limit: i128) -> Result<(), Error> {
+        if limit < 0 {
+            return Err(Error::InvalidAssetAmount);
+        }
+        if limit == 0 {
+            env.storage().instance().remove(&key);
+        } else {
+            env.storage().instance().set(&key, &limit);
+        }
+        Ok(())
+    }
+
+    /// Pause the bridge after a limit breach so the admin can review it
+    fn trip_breaker(env: &Env, reason: Symbol, message_id: &BytesN<32>) {
+        Self::write_paused(env, true);
+
+        env.events().publish(
+            (symbol_short!("B_TRIP"), reason),
+            message_id.clone(),
+        );
+    }
+
+    fn write_paused(env: &Env, paused: bool) {
+        let mut config: BridgeConfig = env.storage().instance().get(&DataKey::Config).unwrap();
+        config.paused = paused;
+        env.storage().instance().set(&DataKey::Config, &config);
+
+        env.events().publish(
+            (symbol_short!("B_PAUSE"), paused),
+            env.ledger().timestamp(),
+        );
+    }
+
+    fn set_message_status(env: &Env, message_id: &BytesN<32>, status: BridgeStatus) {
+        let mut processed_messages: Map<BytesN<32>, BridgeStatus> = env.storage().instance()
+            .get(&DataKey::ProcessedMessages)
+            .unwrap_or(Map::new(env));
+        processed_messages.set(message_id.clone(), status);
+        env.storage().instance().set(&DataKey::ProcessedMessages, &processed_messages);
+    }
+
+    fn process_unlock(env: &Env, message: &BridgeMessage) -> Result<(), Error> {
+        let recipient_addr = Self::bytes_to_address(env, &message.recipient)?;
 
         match message.asset_type {
             AssetType::Token => {
                 let token_client = token::Client::new(env, &message.asset_address);
-                let recipient_addr = Self::bytes_to_address(env, &message.recipient)?;
                 token_client.transfer(&env.current_contract_address(), &recipient_addr, &message.asset_amount);
             }
             AssetType::NFT => {
-                // Handle NFT unlock
-                // This would involve minting or transferring the NFT
+                let native_key = DataKey::BridgedNFTs(message.asset_address.clone(), message.asset_amount);
+                if env.storage().instance().has(&native_key) {
+                    // NFT returning home: release the original held by the bridge
+                    Self::transfer_nft(env, &message.asset_address, &env.current_contract_address(), &recipient_addr, message.asset_amount)?;
+                    env.storage().instance().remove(&native_key);
+
+                    env.events().publish(
+                        (symbol_short!("NFT_REL"), message.asset_address.clone()),
+                        (message.asset_amount, recipient_addr),
+                    );
+                } else {
+                    // Foreign NFT: mint a wrapped representation to the recipient
+                    let wrapped_token_id = Self::generate_wrapped_token_id(env, message.asset_address.clone(), message.asset_amount, message.source_chain);
+
+                    let wrapped_nft = WrappedNFT {
+                        original_token_id: message.asset_amount,
+                        original_chain: message.source_chain,
+                        original_contract: message.asset_address.clone(),
+                        wrapped_token_id,
+                        owner: recipient_addr.clone(),
+                        wrapped_at: env.ledger().timestamp(),
+                    };
+                    env.storage().instance().set(&DataKey::WrappedNFTs(wrapped_token_id), &wrapped_nft);
+
+                    let metadata_key = DataKey::NFTMetadata(wrapped_token_id);
+                    if !env.storage().instance().has(&metadata_key) {
+                        let metadata = NFTMetadata {
+                            token_id: message.asset_amount,
+                            name: Bytes::new(env),
+                            description: Bytes::new(env),
+                            image_uri: Bytes::new(env),
+                            attributes: Map::new(env),
+                            original_chain: message.source_chain,
+                            original_contract: message.asset_address.clone(),
+                        };
+                        env.storage().instance().set(&metadata_key, &metadata);
+                    }
+
+                    env.events().publish(
+                        (symbol_short!("NFT_MINT"), wrapped_token_id),
+                        (message.asset_address.clone(), message.asset_amount, recipient_addr),
+                    );
+                }
             }
         }
 
         Ok(())
     }
 
-    fn bytes_to_address(_env: &Env, _bytes: &Bytes) -> Result<Address, Error> {
-        // TODO: Implement proper address conversion from bytes
-        // This requires careful handling of different address formats
-        // For now, return an error to indicate this needs proper implementation
-        Err(Error::InvalidRecipient)
+    /// Decode a recipient encoded either as a strkey (`G...`/`C...`), as an
+    /// XDR `ScVal::Address`, or as a bare XDR `ScAddress`
+    fn bytes_to_address(env: &Env, bytes: &Bytes) -> Result<Address, Error> {
+        let bytes = if bytes.len() == STRKEY_LEN {
+            Self::strkey_to_sc_address(env, bytes)?
+        } else {
+            bytes.clone()
+        };
+
+        if bytes.len() < 4 {
+            return Err(Error::InvalidRecipient);
+        }
+        let mut tag = [0u8; 4];
+        bytes.slice(0..4).copy_into_slice(&mut tag);
+
+        let encoded = if tag == SCV_ADDRESS {
+            bytes
+        } else if (tag == SC_ADDRESS_TYPE_ACCOUNT && bytes.len() == 40) || (tag == SC_ADDRESS_TYPE_CONTRACT && bytes.len() == 36) {
+            let mut wrapped = Bytes::from_array(env, &SCV_ADDRESS);
+            wrapped.append(&bytes);
+            wrapped
+        } else {
+            return Err(Error::InvalidRecipient);
+        };
+
+        Address::from_xdr(env, &encoded).map_err(|_| Error::InvalidRecipient)
+    }
+
+    /// Decode a strkey into XDR `ScAddress` bytes, checking its version byte
+    /// and checksum. `Address::from_string_bytes` would trap on a bad
+    /// checksum instead of returning an error.
+    fn strkey_to_sc_address(env: &Env, strkey: &Bytes) -> Result<Bytes, Error> {
+        // 56 base32 characters carry exactly 35 bytes: version, key, checksum
+        let mut raw = [0u8; 35];
+        let mut acc: u32 = 0;
+        let mut bits = 0u32;
+        let mut i = 0usize;
+        for c in strkey.iter() {
+            let value = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'2'..=b'7' => c - b'2' + 26,
+                _ => return Err(Error::InvalidRecipient),
+            };
+            acc = (acc << 5) | value as u32;
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                raw[i] = (acc >> bits) as u8;
+                i += 1;
+            }
+        }
+
+        let checksum = u16::from_le_bytes([raw[33], raw[34]]);
+        if checksum != Self::crc16_xmodem(&raw[..33]) {
+            return Err(Error::InvalidRecipient);
+        }
+
+        let mut encoded = Bytes::new(env);
+        match raw[0] {
+            STRKEY_VERSION_ACCOUNT => {
+                encoded.extend_from_slice(&SC_ADDRESS_TYPE_ACCOUNT);
+                encoded.extend_from_slice(&PUBLIC_KEY_TYPE_ED25519);
+            }
+            STRKEY_VERSION_CONTRACT => encoded.extend_from_slice(&SC_ADDRESS_TYPE_CONTRACT),
+            _ => return Err(Error::InvalidRecipient),
+        }
+        encoded.extend_from_slice(&raw[1..33]);
+        Ok(encoded)
+    }
+
+    fn crc16_xmodem(data: &[u8]) -> u16 {
+        let mut crc: u16 = 0;
+        for byte in data {
+            crc ^= (*byte as u16) << 8;
+            for _ in 0..8 {
+                crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+            }
+        }
+        crc
+    }
+
+    fn transfer_nft(env: &Env, nft_contract: &Address, from: &Address, to: &Address, token_id: i128) -> Result<(), Error> {
+        let token_id: u32 = token_id.try_into().map_err(|_| Error::InvalidAssetAmount)?;
+        env.invoke_contract::<()>(
+            nft_contract,
+            &Symbol::new(env, "transfer"),
+            (from.clone(), to.clone(), token_id).into_val(env),
+        );
+        Ok(())
     }
 
     fn get_chain_id(env: &Env) -> u32 {
@@ -862,8 +1712,60 @@ impl BridgeContract {
 
 #[cfg(test)]
 mod test {
+    extern crate std;
+
     use super::*;
-    use soroban_sdk::{testutils::Address as _, Env};
+    use ed25519_dalek::{Signer, SigningKey};
+    use rand::rngs::OsRng;
+    use soroban_sdk::{testutils::{Address as _, Ledger}, Env};
+
+    fn setup_validators(env: &Env, client: &BridgeContractClient, admin: &Address, count: usize) -> std::vec::Vec<(Address, SigningKey)> {
+        let mut validators = std::vec::Vec::new();
+        for _ in 0..count {
+            let validator = Address::generate(env);
+            let key = SigningKey::generate(&mut OsRng);
+            client.add_validator(admin, &validator, &BytesN::from_array(env, &key.verifying_key().to_bytes()));
+            validators.push((validator, key));
+        }
+        validators
+    }
+
+    fn sign_message(env: &Env, client: &BridgeContractClient, message: &BridgeMessage, signers: &[(Address, SigningKey)]) -> Vec<ValidatorSignature> {
+        let digest = client.message_digest(message).to_array();
+        let mut signatures = Vec::new(env);
+        for (validator, key) in signers {
+            signatures.push_back(ValidatorSignature {
+                validator: validator.clone(),
+                signature: BytesN::from_array(env, &key.sign(&digest).to_bytes()),
+            });
+        }
+        signatures
+    }
+
+    fn strkey_bytes(env: &Env, address: &Address) -> Bytes {
+        let strkey = address.to_string();
+        let mut buf = [0u8; 56];
+        strkey.copy_into_slice(&mut buf);
+        Bytes::from_array(env, &buf)
+    }
+
+    fn inbound_nft_message(env: &Env, sender: &Address, nft_contract: &Address) -> BridgeMessage {
+        BridgeMessage {
+            message_id: BytesN::from_array(env, &[9u8; 32]),
+            source_chain: 2,
+            dest_chain: 1,
+            action: BridgeAction::Unlock,
+            asset_type: AssetType::NFT,
+            asset_address: nft_contract.clone(),
+            asset_amount: 42,
+            sender: sender.clone(),
+            recipient: strkey_bytes(env, &Address::generate(env)),
+            fee_amount: 0,
+            fee_token: None,
+            timestamp: 0,
+            nonce: 1,
+        }
+    }
 
     #[test]
     fn test_bridge_initialization() {
@@ -897,11 +1799,18 @@ mod test {
         let validator = Address::generate(&env);
 
         client.initialize(&admin, &2u32, &0u32, &fee_collector);
-        client.add_validator(&admin, &validator);
+        let public_key = BytesN::from_array(&env, &[7u8; 32]);
+        client.add_validator(&admin, &validator, &public_key);
 
         let validators = client.get_validators();
         assert_eq!(validators.len(), 1);
         assert_eq!(validators.get(0).unwrap(), validator);
+        assert_eq!(client.get_validator_key(&validator), Some(public_key.clone()));
+        assert_eq!(client.get_validator_set_version(), 2);
+
+        // The same key cannot back two validators
+        let other = Address::generate(&env);
+        assert_eq!(client.try_add_validator(&admin, &other, &public_key), Err(Ok(Error::DuplicateValidatorKey)));
     }
 
     #[test]
@@ -923,7 +1832,7 @@ mod test {
         // For testing, we'll use a mock token - actual minting would be done externally
         // In a real scenario, the user would already have tokens
 
-        token_admin_client.mint(&user, &1000);
+        token_admin_client.mint(&user, &2_000_000);
 
         // Bridge tokens
         let recipient = Bytes::from_array(&env, &[1u8; 32]);
@@ -940,4 +1849,524 @@ mod test {
         assert_eq!(locked_asset.amount, 500);
         assert_eq!(locked_asset.owner, user);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_signature_verification() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, BridgeContract);
+        let client = BridgeContractClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let fee_collector = Address::generate(&env);
+        client.initialize(&admin, &2u32, &1u32, &fee_collector);
+
+        let validators = setup_validators(&env, &client, &admin, 3);
+        let message = inbound_nft_message(&env, &Address::generate(&env), &Address::generate(&env));
+
+        // The same validator listed twice only counts once
+        let duplicated = sign_message(&env, &client, &message, &[validators[0].clone(), validators[0].clone()]);
+        let res = client.try_complete_bridge(&validators[0].0, &message, &duplicated);
+        assert_eq!(res, Err(Ok(Error::InsufficientSignatures)));
+
+        // A forged signature aborts verification
+        let mut forged = sign_message(&env, &client, &message, &validators[0..2]);
+        forged.set(1, ValidatorSignature {
+            validator: validators[1].0.clone(),
+            signature: BytesN::from_array(&env, &[0u8; 64]),
+        });
+        assert!(client.try_complete_bridge(&validators[0].0, &message, &forged).is_err());
+
+        let signatures = sign_message(&env, &client, &message, &validators[0..2]);
+        client.complete_bridge(&validators[0].0, &message, &signatures);
+        assert_eq!(client.get_message_status(&message.message_id), Some(BridgeStatus::Completed));
+    }
+
+    #[test]
+    fn test_signatures_from_rotated_set_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, BridgeContract);
+        let client = BridgeContractClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let fee_collector = Address::generate(&env);
+        client.initialize(&admin, &2u32, &1u32, &fee_collector);
+
+        let validators = setup_validators(&env, &client, &admin, 3);
+        let message = inbound_nft_message(&env, &Address::generate(&env), &Address::generate(&env));
+        let signatures = sign_message(&env, &client, &message, &validators[0..2]);
+
+        // Rotating the set bumps the version the signatures were bound to
+        client.remove_validator(&admin, &validators[2].0);
+        assert!(client.try_complete_bridge(&validators[0].0, &message, &signatures).is_err());
+
+        let fresh = sign_message(&env, &client, &message, &validators[0..2]);
+        client.complete_bridge(&validators[0].0, &message, &fresh);
+        assert_eq!(client.get_message_status(&message.message_id), Some(BridgeStatus::Completed));
+    }
+
+    #[test]
+    fn test_recipient_decoding() {
+        let env = Env::default();
+        let account = Address::generate(&env);
+        let contract = env.register_contract(None, BridgeContract);
+
+        for address in [account, contract] {
+            let strkey = strkey_bytes(&env, &address);
+            assert_eq!(BridgeContract::bytes_to_address(&env, &strkey), Ok(address.clone()));
+
+            let scval = address.clone().to_xdr(&env);
+            assert_eq!(BridgeContract::bytes_to_address(&env, &scval), Ok(address.clone()));
+
+            let sc_address = scval.slice(4..);
+            assert_eq!(BridgeContract::bytes_to_address(&env, &sc_address), Ok(address.clone()));
+        }
+
+        let garbage = Bytes::from_array(&env, &[1u8; 32]);
+        assert_eq!(BridgeContract::bytes_to_address(&env, &garbage), Err(Error::InvalidRecipient));
+    }
+
+    #[test]
+    fn test_recipient_strkey_with_bad_checksum_is_rejected() {
+        let env = Env::default();
+        let strkey = strkey_bytes(&env, &Address::generate(&env));
+
+        // Swap one character of the key so the checksum no longer matches
+        let mut corrupted = strkey.clone();
+        let c = corrupted.get(10).unwrap();
+        corrupted.set(10, if c == b'A' { b'B' } else { b'A' });
+        assert_eq!(BridgeContract::bytes_to_address(&env, &corrupted), Err(Error::InvalidRecipient));
+
+        let mut bad_char = strkey.clone();
+        bad_char.set(10, b'1');
+        assert_eq!(BridgeContract::bytes_to_address(&env, &bad_char), Err(Error::InvalidRecipient));
+
+        let mut wrong_version = strkey;
+        wrong_version.set(0, b'S');
+        assert_eq!(BridgeContract::bytes_to_address(&env, &wrong_version), Err(Error::InvalidRecipient));
+    }
+
+    fn setup_bridge<'a>(env: &'a Env, chain_id: u32) -> (BridgeContractClient<'a>, Address, std::vec::Vec<(Address, SigningKey)>) {
+        let contract_id = env.register_contract(None, BridgeContract);
+        let client = BridgeContractClient::new(env, &contract_id);
+        let admin = Address::generate(env);
+        client.initialize(&admin, &2u32, &chain_id, &Address::generate(env));
+        let validators = setup_validators(env, &client, &admin, 2);
+        (client, admin, validators)
+    }
+
+    /// What a relayer does: turn a lock on the source bridge into an unlock on the destination
+    fn relay(env: &Env, source: &BridgeContractClient, dest: &BridgeContractClient, validators: &[(Address, SigningKey)], message_id: &BytesN<32>) -> BridgeMessage {
+        let mut message = source.get_outbound_message(message_id).unwrap();
+        message.action = BridgeAction::Unlock;
+        let signatures = sign_message(env, dest, &message, validators);
+        dest.complete_bridge(&validators[0].0, &message, &signatures);
+        message
+    }
+
+    #[test]
+    fn test_token_round_trip_between_chains() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (stellar, _, stellar_validators) = setup_bridge(&env, 1);
+        let (remote, _, remote_validators) = setup_bridge(&env, 2);
+
+        let token_admin = Address::generate(&env);
+        let token = env.register_stellar_asset_contract_v2(token_admin).address();
+        let token_admin_client = token::StellarAssetClient::new(&env, &token);
+        let token_client = token::Client::new(&env, &token);
+
+        let user = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        token_admin_client.mint(&user, &5_000_000);
+        // Liquidity already held by the destination bridge
+        token_admin_client.mint(&remote.address, &5_000_000);
+
+        let message_id = stellar.bridge_assets(&user, &token, &AssetType::Token, &2_000_000, &2u32, &strkey_bytes(&env, &recipient));
+        // Locked amount plus the default minimum fee
+        assert_eq!(token_client.balance(&stellar.address), 3_000_000);
+        assert_eq!(stellar.get_fee_balance(&token), 1_000_000);
+
+        let message = relay(&env, &stellar, &remote, &remote_validators, &message_id);
+        assert_eq!(token_client.balance(&recipient), 2_000_000);
+        assert_eq!(remote.get_message_status(&message_id), Some(BridgeStatus::Completed));
+
+        // Replays are rejected
+        let signatures = sign_message(&env, &remote, &message, &remote_validators);
+        assert_eq!(remote.try_complete_bridge(&remote_validators[0].0, &message, &signatures), Err(Ok(Error::MessageAlreadyProcessed)));
+
+        // A message for another chain cannot be completed here
+        let signatures = sign_message(&env, &stellar, &message, &stellar_validators);
+        assert_eq!(stellar.try_complete_bridge(&stellar_validators[0].0, &message, &signatures), Err(Ok(Error::InvalidChainId)));
+    }
+
+    fn inbound_token_message(env: &Env, id: u8, token: &Address, amount: i128, recipient: &Address) -> BridgeMessage {
+        BridgeMessage {
+            message_id: BytesN::from_array(env, &[id; 32]),
+            source_chain: 2,
+            dest_chain: 1,
+            action: BridgeAction::Unlock,
+            asset_type: AssetType::Token,
+            asset_address: token.clone(),
+            asset_amount: amount,
+            sender: Address::generate(env),
+            recipient: strkey_bytes(env, recipient),
+            fee_amount: 0,
+            fee_token: None,
+            timestamp: 0,
+            nonce: id as u64,
+        }
+    }
+
+    #[test]
+    fn test_outflow_limit_trips_breaker() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, admin, validators) = setup_bridge(&env, 1);
+        let token = env.register_stellar_asset_contract_v2(Address::generate(&env)).address();
+        token::StellarAssetClient::new(&env, &token).mint(&client.address, &10_000_000);
+        let token_client = token::Client::new(&env, &token);
+        let recipient = Address::generate(&env);
+
+        client.set_asset_limit(&admin, &token, &3_000_000);
+        client.set_chain_limit(&admin, &2u32, &token, &5_000_000);
+
+        let first = inbound_token_message(&env, 1, &token, 2_000_000, &recipient);
+        let signatures = sign_message(&env, &client, &first, &validators);
+        assert_eq!(client.complete_bridge(&validators[0].0, &first, &signatures), BridgeStatus::Completed);
+        assert_eq!(client.get_asset_outflow(&token), 2_000_000);
+        assert_eq!(client.get_chain_outflow(&2u32, &token), 2_000_000);
+
+        // Going over the asset limit pauses the bridge and leaves the message pending
+        let second = inbound_token_message(&env, 2, &token, 2_000_000, &recipient);
+        let signatures = sign_message(&env, &client, &second, &validators);
+        assert_eq!(client.complete_bridge(&validators[0].0, &second, &signatures), BridgeStatus::Pending);
+        assert!(client.get_config().paused);
+        assert_eq!(client.get_message_status(&second.message_id), None);
+        assert_eq!(token_client.balance(&recipient), 2_000_000);
+        assert_eq!(client.try_complete_bridge(&validators[0].0, &second, &signatures), Err(Ok(Error::ContractPaused)));
+
+        // Once the window and its last bucket roll over and the admin resumes, the message goes through
+        client.set_paused(&admin, &false);
+        env.ledger().with_mut(|l| l.timestamp += DEFAULT_LIMIT_WINDOW + 1);
+        assert_eq!(client.get_asset_outflow(&token), 2_000_000);
+        env.ledger().with_mut(|l| l.timestamp += DEFAULT_LIMIT_WINDOW / OUTFLOW_BUCKETS);
+        assert_eq!(client.get_asset_outflow(&token), 0);
+        assert_eq!(client.complete_bridge(&validators[0].0, &second, &signatures), BridgeStatus::Completed);
+        assert_eq!(token_client.balance(&recipient), 4_000_000);
+    }
+
+    #[test]
+    fn test_outflows_tracked_in_buckets_only_when_limited() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, admin, validators) = setup_bridge(&env, 1);
+        let token = env.register_stellar_asset_contract_v2(Address::generate(&env)).address();
+        token::StellarAssetClient::new(&env, &token).mint(&client.address, &10_000_000);
+        let recipient = Address::generate(&env);
+
+        // No limit configured: nothing is recorded
+        let first = inbound_token_message(&env, 1, &token, 1_000_000, &recipient);
+        let signatures = sign_message(&env, &client, &first, &validators);
+        assert_eq!(client.complete_bridge(&validators[0].0, &first, &signatures), BridgeStatus::Completed);
+        assert_eq!(client.get_asset_outflow(&token), 0);
+
+        // Transfers within one bucket share a single entry
+        client.set_asset_limit(&admin, &token, &5_000_000);
+        for id in 2..5 {
+            let message = inbound_token_message(&env, id, &token, 1_000_000, &recipient);
+            let signatures = sign_message(&env, &client, &message, &validators);
+            assert_eq!(client.complete_bridge(&validators[0].0, &message, &signatures), BridgeStatus::Completed);
+            env.ledger().with_mut(|l| l.timestamp += 60);
+        }
+        assert_eq!(client.get_asset_outflow(&token), 3_000_000);
+
+        let buckets: Vec<(u64, i128)> = env.as_contract(&client.address, || {
+            env.storage().instance().get(&DataKey::AssetOutflow(token.clone())).unwrap()
+        });
+        assert_eq!(buckets.len(), 1);
+        assert!(env.as_contract(&client.address, || {
+            !env.storage().instance().has(&DataKey::ChainOutflow(2, token.clone()))
+        }));
+    }
+
+    #[test]
+    fn test_large_transfers_are_delayed() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, admin, validators) = setup_bridge(&env, 1);
+        let token = env.register_stellar_asset_contract_v2(Address::generate(&env)).address();
+        token::StellarAssetClient::new(&env, &token).mint(&client.address, &50_000_000);
+        let token_client = token::Client::new(&env, &token);
+        let recipient = Address::generate(&env);
+
+        client.set_transfer_limits(&admin, &10_000_000, &5_000_000, &3_600, &DEFAULT_LIMIT_WINDOW);
+
+        let queued = inbound_token_message(&env, 1, &token, 6_000_000, &recipient);
+        let signatures = sign_message(&env, &client, &queued, &validators);
+        assert_eq!(client.complete_bridge(&validators[0].0, &queued, &signatures), BridgeStatus::Delayed);
+        assert_eq!(client.get_message_status(&queued.message_id), Some(BridgeStatus::Delayed));
+        assert_eq!(client.try_complete_bridge(&validators[0].0, &queued, &signatures), Err(Ok(Error::MessageAlreadyProcessed)));
+        assert_eq!(client.try_execute_delayed(&queued.message_id), Err(Ok(Error::DelayNotElapsed)));
+
+        env.ledger().with_mut(|l| l.timestamp += 3_600);
+        assert_eq!(client.execute_delayed(&queued.message_id), BridgeStatus::Completed);
+        assert_eq!(token_client.balance(&recipient), 6_000_000);
+        assert!(client.get_delayed_transfer(&queued.message_id).is_none());
+
+        // The admin can drop a queued transfer
+        let cancelled = inbound_token_message(&env, 2, &token, 7_000_000, &recipient);
+        let signatures = sign_message(&env, &client, &cancelled, &validators);
+        client.complete_bridge(&validators[0].0, &cancelled, &signatures);
+        client.cancel_delayed(&admin, &cancelled.message_id);
+        assert_eq!(client.get_message_status(&cancelled.message_id), Some(BridgeStatus::Cancelled));
+        assert_eq!(client.try_execute_delayed(&cancelled.message_id), Err(Ok(Error::TransferNotDelayed)));
+
+        // Anything above the single-transfer cap trips the breaker
+        let oversized = inbound_token_message(&env, 3, &token, 20_000_000, &recipient);
+        let signatures = sign_message(&env, &client, &oversized, &validators);
+        assert_eq!(client.complete_bridge(&validators[0].0, &oversized, &signatures), BridgeStatus::Pending);
+        assert!(client.get_config().paused);
+        assert_eq!(token_client.balance(&recipient), 6_000_000);
+    }
+
+    fn hash_pair(env: &Env, a: &BytesN<32>, b: &BytesN<32>) -> BytesN<32> {
+        let (first, second) = if a.to_array() < b.to_array() { (a, b) } else { (b, a) };
+        let mut combined = Bytes::new(env);
+        combined.extend_from_slice(&first.to_array());
+        combined.extend_from_slice(&second.to_array());
+        env.crypto().sha256(&combined).into()
+    }
+
+    #[test]
+    fn test_batched_messages_with_merkle_proofs() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, validators) = setup_bridge(&env, 1);
+        let token = env.register_stellar_asset_contract_v2(Address::generate(&env)).address();
+        token::StellarAssetClient::new(&env, &token).mint(&client.address, &10_000_000);
+        let token_client = token::Client::new(&env, &token);
+
+        let recipients: std::vec::Vec<Address> = (0..4).map(|_| Address::generate(&env)).collect();
+        let messages: std::vec::Vec<BridgeMessage> = recipients.iter().enumerate()
+            .map(|(i, r)| inbound_token_message(&env, i as u8 + 1, &token, 1_000_000, r))
+            .collect();
+        let leaves: std::vec::Vec<BytesN<32>> = messages.iter().map(|m| client.message_leaf(m)).collect();
+        let left = hash_pair(&env, &leaves[0], &leaves[1]);
+        let right = hash_pair(&env, &leaves[2], &leaves[3]);
+        let root = hash_pair(&env, &left, &right);
+
+        // Validators sign the root once for the whole batch
+        let digest = client.batch_digest(&2u32, &7u64, &root, &4u32).to_array();
+        let mut signatures = Vec::new(&env);
+        for (validator, key) in &validators {
+            signatures.push_back(ValidatorSignature {
+                validator: validator.clone(),
+                signature: BytesN::from_array(&env, &key.sign(&digest).to_bytes()),
+            });
+        }
+        client.attest_batch_root(&validators[0].0, &2u32, &7u64, &root, &4u32, &signatures);
+        assert_eq!(client.get_batch_root(&2u32, &7u64).unwrap().root, root);
+        assert_eq!(client.try_attest_batch_root(&validators[0].0, &2u32, &7u64, &root, &4u32, &signatures), Err(Ok(Error::BatchAlreadyAttested)));
+
+        let relayer = Address::generate(&env);
+        let proof = Vec::from_array(&env, [leaves[3].clone(), left.clone()]);
+        assert_eq!(client.complete_bridge_with_proof(&relayer, &messages[2], &7u64, &proof), BridgeStatus::Completed);
+        assert_eq!(token_client.balance(&recipients[2]), 1_000_000);
+
+        // Replays are still caught by the processed-message map
+        assert_eq!(client.try_complete_bridge_with_proof(&relayer, &messages[2], &7u64, &proof), Err(Ok(Error::MessageAlreadyProcessed)));
+
+        // Wrong proof, or an epoch nobody attested
+        assert_eq!(client.try_complete_bridge_with_proof(&relayer, &messages[0], &7u64, &proof), Err(Ok(Error::InvalidMerkleProof)));
+        let proof = Vec::from_array(&env, [leaves[1].clone(), right.clone()]);
+        assert_eq!(client.try_complete_bridge_with_proof(&relayer, &messages[0], &8u64, &proof), Err(Ok(Error::BatchNotAttested)));
+        assert_eq!(client.complete_bridge_with_proof(&relayer, &messages[0], &7u64, &proof), BridgeStatus::Completed);
+        assert_eq!(token_client.balance(&recipients[0]), 1_000_000);
+        assert_eq!(client.get_batch_root(&2u32, &7u64).unwrap().executed, 2);
+
+        // A batch attested for fewer messages than its tree holds stops at its count
+        let digest = client.batch_digest(&2u32, &9u64, &root, &1u32).to_array();
+        let mut signatures = Vec::new(&env);
+        for (validator, key) in &validators {
+            signatures.push_back(ValidatorSignature {
+                validator: validator.clone(),
+                signature: BytesN::from_array(&env, &key.sign(&digest).to_bytes()),
+            });
+        }
+        client.attest_batch_root(&validators[0].0, &2u32, &9u64, &root, &1u32, &signatures);
+        let proof = Vec::from_array(&env, [leaves[0].clone(), right.clone()]);
+        assert_eq!(client.complete_bridge_with_proof(&relayer, &messages[1], &9u64, &proof), BridgeStatus::Completed);
+        let proof = Vec::from_array(&env, [leaves[2].clone(), left.clone()]);
+        assert_eq!(client.try_complete_bridge_with_proof(&relayer, &messages[3], &9u64, &proof), Err(Ok(Error::BatchExhausted)));
+        assert_eq!(token_client.balance(&recipients[3]), 0);
+    }
+
+    #[test]
+    fn test_pending_batch_message_does_not_use_up_the_batch() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, admin, validators) = setup_bridge(&env, 1);
+        let token = env.register_stellar_asset_contract_v2(Address::generate(&env)).address();
+        token::StellarAssetClient::new(&env, &token).mint(&client.address, &10_000_000);
+        let token_client = token::Client::new(&env, &token);
+
+        let recipients: std::vec::Vec<Address> = (0..2).map(|_| Address::generate(&env)).collect();
+        let messages: std::vec::Vec<BridgeMessage> = recipients.iter().enumerate()
+            .map(|(i, r)| inbound_token_message(&env, i as u8 + 1, &token, 1_000_000, r))
+            .collect();
+        let leaves: std::vec::Vec<BytesN<32>> = messages.iter().map(|m| client.message_leaf(m)).collect();
+        let root = hash_pair(&env, &leaves[0], &leaves[1]);
+
+        let digest = client.batch_digest(&2u32, &1u64, &root, &2u32).to_array();
+        let mut signatures = Vec::new(&env);
+        for (validator, key) in &validators {
+            signatures.push_back(ValidatorSignature {
+                validator: validator.clone(),
+                signature: BytesN::from_array(&env, &key.sign(&digest).to_bytes()),
+            });
+        }
+        client.attest_batch_root(&validators[0].0, &2u32, &1u64, &root, &2u32, &signatures);
+
+        // The first message trips the breaker and stays pending without taking a slot
+        client.set_asset_limit(&admin, &token, &500_000);
+        let relayer = Address::generate(&env);
+        let first_proof = Vec::from_array(&env, [leaves[1].clone()]);
+        assert_eq!(client.complete_bridge_with_proof(&relayer, &messages[0], &1u64, &first_proof), BridgeStatus::Pending);
+        assert!(client.get_config().paused);
+        assert_eq!(client.get_batch_root(&2u32, &1u64).unwrap().executed, 0);
+
+        // After the admin resets, the retry and the last leaf both fit in the batch
+        client.set_asset_limit(&admin, &token, &5_000_000);
+        client.set_paused(&admin, &false);
+        assert_eq!(client.complete_bridge_with_proof(&relayer, &messages[0], &1u64, &first_proof), BridgeStatus::Completed);
+        let last_proof = Vec::from_array(&env, [leaves[0].clone()]);
+        assert_eq!(client.complete_bridge_with_proof(&relayer, &messages[1], &1u64, &last_proof), BridgeStatus::Completed);
+        assert_eq!(client.get_batch_root(&2u32, &1u64).unwrap().executed, 2);
+        assert_eq!(token_client.balance(&recipients[0]), 1_000_000);
+        assert_eq!(token_client.balance(&recipients[1]), 1_000_000);
+    }
+
+    #[test]
+    fn test_fees_in_separate_token() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, BridgeContract);
+        let client = BridgeContractClient::new(&env, &contract_id);
+        let admin = Address::generate(&env);
+        let fee_collector = Address::generate(&env);
+        client.initialize(&admin, &1u32, &1u32, &fee_collector);
+
+        let asset = env.register_stellar_asset_contract_v2(Address::generate(&env)).address();
+        let fee_token = env.register_stellar_asset_contract_v2(Address::generate(&env)).address();
+        let user = Address::generate(&env);
+        token::StellarAssetClient::new(&env, &asset).mint(&user, &100_000_000);
+        token::StellarAssetClient::new(&env, &fee_token).mint(&user, &10_000);
+
+        // Default schedule charges in the asset, clamped to the minimum fee
+        assert_eq!(client.quote_fee(&asset, &AssetType::Token, &50_000_000, &2u32), FeeQuote { fee_token: asset.clone(), amount: 1_000_000 });
+
+        // Per-chain schedule, percentage applies when paid in the asset
+        client.set_chain_fee_schedule(&admin, &3u32, &FeeSchedule { base_fee_bps: 100, min_fee: 100, max_fee: 1_000_000 });
+        assert_eq!(client.quote_fee(&asset, &AssetType::Token, &50_000_000, &3u32), FeeQuote { fee_token: asset.clone(), amount: 500_000 });
+
+        // NFTs pay no percentage fee in the asset itself
+        let nft = Address::generate(&env);
+        assert_eq!(client.quote_fee(&nft, &AssetType::NFT, &7, &3u32), FeeQuote { fee_token: nft.clone(), amount: 0 });
+
+        // With a fee token set, the schedule's minimum is charged as a flat fee
+        client.set_fee_token(&admin, &Some(fee_token.clone()));
+        let quote = client.quote_fee(&asset, &AssetType::Token, &50_000_000, &3u32);
+        assert_eq!(quote, FeeQuote { fee_token: fee_token.clone(), amount: 100 });
+        assert_eq!(client.quote_fee(&nft, &AssetType::NFT, &7, &3u32), FeeQuote { fee_token: fee_token.clone(), amount: 100 });
+
+        let recipient = Bytes::from_array(&env, &[1u8; 32]);
+        let message_id = client.bridge_assets(&user, &asset, &AssetType::Token, &50_000_000, &3u32, &recipient);
+        assert_eq!(client.get_outbound_message(&message_id).unwrap().fee_token, Some(fee_token.clone()));
+        assert_eq!(token::Client::new(&env, &asset).balance(&user), 50_000_000);
+        assert_eq!(token::Client::new(&env, &fee_token).balance(&user), 9_900);
+        assert_eq!(client.get_fee_balance(&fee_token), 100);
+
+        // Only the fee collector can withdraw, and only what was collected
+        assert_eq!(client.try_withdraw_fees(&admin, &fee_token, &100), Err(Ok(Error::Unauthorized)));
+        assert_eq!(client.try_withdraw_fees(&fee_collector, &fee_token, &101), Err(Ok(Error::InsufficientBalance)));
+        client.withdraw_fees(&fee_collector, &fee_token, &100);
+        assert_eq!(token::Client::new(&env, &fee_token).balance(&fee_collector), 100);
+        assert_eq!(client.get_fee_balance(&fee_token), 0);
+    }
+
+    #[test]
+    fn test_nft_round_trip_between_chains() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (stellar, _, stellar_validators) = setup_bridge(&env, 1);
+        let (remote, _, remote_validators) = setup_bridge(&env, 2);
+
+        let nft_id = env.register_contract(None, MockNft);
+        let nft = MockNftClient::new(&env, &nft_id);
+        let user = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        nft.mint(&user, &7);
+
+        // Outbound: the native NFT is locked on the source bridge
+        let message_id = stellar.bridge_assets(&user, &nft_id, &AssetType::NFT, &7, &2u32, &strkey_bytes(&env, &recipient));
+        assert_eq!(nft.owner_of(&7), stellar.address);
+
+        // Inbound on the remote chain: a wrapped NFT is minted to the recipient
+        relay(&env, &stellar, &remote, &remote_validators, &message_id);
+        let wrapped_id = BridgeContract::generate_wrapped_token_id(&env, nft_id.clone(), 7, 1);
+        let wrapped = remote.get_wrapped_nft(&wrapped_id).unwrap();
+        assert_eq!(wrapped.owner, recipient);
+        assert_eq!(wrapped.original_chain, 1);
+        assert_eq!(wrapped.original_token_id, 7);
+        assert_eq!(remote.get_nft_metadata(&wrapped_id).unwrap().original_contract, nft_id);
+
+        // Coming home: the original is released from the source bridge
+        let mut back = inbound_nft_message(&env, &recipient, &nft_id);
+        back.source_chain = 2;
+        back.asset_amount = 7;
+        back.recipient = strkey_bytes(&env, &user);
+        let signatures = sign_message(&env, &stellar, &back, &stellar_validators);
+        stellar.complete_bridge(&stellar_validators[0].0, &back, &signatures);
+        assert_eq!(nft.owner_of(&7), user);
+    }
+
+    #[contracttype]
+    enum MockNftDataKey {
+        Owner(u32),
+    }
+
+    #[contract]
+    struct MockNft;
+
+    #[contractimpl]
+    impl MockNft {
+        pub fn mint(env: Env, to: Address, token_id: u32) {
+            env.storage().persistent().set(&MockNftDataKey::Owner(token_id), &to);
+        }
+
+        pub fn transfer(env: Env, from: Address, to: Address, token_id: u32) {
+            from.require_auth();
+            let owner: Address = env.storage().persistent().get(&MockNftDataKey::Owner(token_id)).unwrap();
+            if owner != from {
+                panic!("Not owner");
+            }
+            env.storage().persistent().set(&MockNftDataKey::Owner(token_id), &to);
+        }
+
+        pub fn owner_of(env: Env, token_id: u32) -> Address {
+            env.storage().persistent().get(&MockNftDataKey::Owner(token_id)).unwrap()
+        }
+    }
+}
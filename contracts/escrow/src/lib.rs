@@ -1,7 +1,10 @@
 #![no_std]
+// `ReleaseCondition` variants are part of the contract interface; the test
+// utilities `contracttype` generates for it trip this lint.
+#![allow(clippy::enum_variant_names)]
 use soroban_sdk::{
     contract, contracterror, contractimpl, contracttype, symbol_short, token, vec, Address, Env,
-    String, Symbol, Vec,
+    IntoVal, Map, String, Symbol, Vec,
 };
 
 #[contracttype]
@@ -9,6 +12,13 @@ use soroban_sdk::{
 pub enum DataKey {
     Escrow(u32),
     NextEscrowId,
+    Swap(u32),
+    NextSwapId,
+    ArbitrationConfig,
+    Arbitrators,
+    Arbitrator(Address),
+    Panel(u32),
+    PanelPool(u32),
 }
 
 #[contracttype]
@@ -19,6 +29,8 @@ pub enum EscrowState {
     Released,
     Refunded,
     Disputed,
+    /// Closed by an arbitration panel that split the funds
+    Resolved,
 }
 
 #[contracttype]
@@ -27,13 +39,6 @@ pub enum ReleaseCondition {
     AllPartiesApprove,
     MajorityApprove,
     ArbitratorApprove,
-pub enum EscrowStatus {
-    Created = 1,
-    Active = 2,
-    Disputed = 3,
-    Resolved = 4,
-    Refunded = 5,
-    Released = 6,
 }
 
 #[contracttype]
@@ -41,11 +46,90 @@ pub enum EscrowStatus {
 pub enum DisputeResolution {
     Release,
     Refund,
-pub struct ReleaseCondition {
-    pub condition_id: u64,
+}
+
+/// A slice of the escrowed funds paid to `recipient` once the milestone is
+/// fulfilled. Evidence is submitted by one party and accepted by another
+/// party or the arbitrator.
+///
+/// Takes over the struct form of `ReleaseCondition`, whose name is kept by
+/// the approval-policy enum above.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Milestone {
     pub description: String,
-    pub fulfilled: bool,
+    pub amount: i128,
+    pub recipient: Address,
     pub evidence: Option<String>,
+    pub submitted_by: Option<Address>,
+    pub fulfilled: bool,
+}
+
+/// A single asset committed to a swap: a token amount, or an NFT token id
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EscrowAsset {
+    Token(Address, i128),
+    Nft(Address, u32),
+}
+
+/// One party's side of a swap: the basket they commit and who receives it
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SwapLeg {
+    pub party: Address,
+    pub basket: Vec<EscrowAsset>,
+    pub recipient: Address,
+    pub deposited: bool,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SwapDeal {
+    pub id: u32,
+    pub creator: Address,
+    pub legs: Vec<SwapLeg>,
+    pub state: EscrowState,
+    pub timeout: u64,
+    pub created_at: u64,
+}
+
+/// A panelist's verdict on a dispute
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PanelVote {
+    Release,
+    Refund,
+    /// Share of the remaining funds released, in basis points; the rest is refunded
+    Split(u32),
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ArbitrationConfig {
+    pub admin: Address,
+    pub stake_token: Address,
+    pub min_stake: i128,
+    /// Fee paid to the majority out of the disputed funds
+    pub fee_bps: u32,
+    /// Share of stake taken from panelists who vote against the majority or don't vote
+    pub slash_bps: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ArbitratorInfo {
+    pub stake: i128,
+    pub active_cases: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DisputePanel {
+    pub escrow_id: u32,
+    pub members: Vec<Address>,
+    pub votes: Map<Address, PanelVote>,
+    pub deadline: u64,
 }
 
 #[contracttype]
@@ -60,6 +144,8 @@ pub struct EscrowData {
     pub approvals: Vec<bool>,
     pub state: EscrowState,
     pub conditions: Vec<ReleaseCondition>,
+    pub milestones: Vec<Milestone>,
+    pub released: i128,
     pub arbitrator: Option<Address>,
     pub timeout: u64,
     pub created_at: u64,
@@ -79,6 +165,20 @@ pub enum EscrowError {
     NoArbitrator = 8,
     TimeoutNotReached = 9,
     InsufficientFunds = 10,
+    InvalidBasket = 11,
+    AssetNotOwned = 12,
+    InvalidMilestone = 13,
+    MilestoneFulfilled = 14,
+    EvidenceMissing = 15,
+    NotConfigured = 16,
+    AlreadyConfigured = 17,
+    InsufficientStake = 18,
+    NotEnoughArbitrators = 19,
+    InvalidPanelSize = 20,
+    NotPanelist = 21,
+    AlreadyVoted = 22,
+    InvalidVote = 23,
+    VotingOpen = 24,
 }
 
 const ESCROW_CREATED: Symbol = symbol_short!("created");
@@ -91,47 +191,23 @@ const DISPUTE_INITIATED: Symbol = symbol_short!("dispute");
 const DISPUTE_RESOLVED: Symbol = symbol_short!("resolved");
 const AUTO_RELEASE: Symbol = symbol_short!("auto");
 const TIMEOUT_REFUND: Symbol = symbol_short!("timeout");
+const EVIDENCE_SUBMITTED: Symbol = symbol_short!("evidence");
+const PANEL_DRAWN: Symbol = symbol_short!("panel");
+const PANEL_VOTE: Symbol = symbol_short!("vote");
+const ARBITRATOR_SLASHED: Symbol = symbol_short!("slashed");
+const SWAP_CREATED: Symbol = symbol_short!("swap_new");
+const BASIS_POINTS: i128 = 10_000;
+const PANEL_VOTING_PERIOD: u64 = 3 * 24 * 60 * 60;
+const BASKET_DEPOSITED: Symbol = symbol_short!("basket");
+const SWAP_EXECUTED: Symbol = symbol_short!("swapped");
 
-pub struct EscrowAgreement {
-    pub escrow_id: u64,
-    pub creator: Address,
-    pub parties: Vec<Party>,
-    pub arbitrator: Address,
-    pub status: EscrowStatus,
-    pub total_deposit: i128,
-    pub release_conditions: Vec<ReleaseCondition>,
-    pub dispute_reason: Option<String>,
-    pub resolution: Option<String>,
-    pub created_at: u64,
-    pub timeout_at: u64,
-    pub last_activity_at: u64,
-}
-
-#[contracttype]
-pub enum DataKey {
-    Escrow(u64),
-    EscrowCount,
-    NextConditionId,
-}
-
-// 2. CONTRACT LOGIC
 #[contract]
 pub struct EscrowContract;
 
 #[contractimpl]
 impl EscrowContract {
-    #[allow(clippy::too_many_arguments)]
-    /// Initialize the contract
-    pub fn init(env: Env, _admin: Address) {
-        if !env.storage().instance().has(&DataKey::EscrowCount) {
-            env.storage().instance().set(&DataKey::EscrowCount, &0u64);
-        }
-        if !env.storage().instance().has(&DataKey::NextConditionId) {
-            env.storage().instance().set(&DataKey::NextConditionId, &1u64);
-        }
-    }
-
     /// Create a new escrow agreement
+    #[allow(clippy::too_many_arguments)]
     pub fn create_escrow(
         env: Env,
         creator: Address,
@@ -144,46 +220,182 @@ impl EscrowContract {
     ) -> Result<u32, EscrowError> {
         creator.require_auth();
 
-        if parties.is_empty() || amounts.is_empty() || parties.len() != amounts.len() {
-            return Err(EscrowError::InvalidParties);
-        }
+        Self::open_escrow(
+            &env,
+            creator,
+            parties,
+            token,
+            amounts,
+            conditions,
+            vec![&env],
+            arbitrator,
+            timeout,
+        )
+    }
 
-        let escrow_id = Self::get_next_escrow_id(&env);
-        let current_time = env.ledger().timestamp();
+    /// Create an escrow paid out milestone by milestone. The milestone amounts
+    /// must add up to the total the parties deposit.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_milestone_escrow(
+        env: Env,
+        creator: Address,
+        parties: Vec<Address>,
+        token: Address,
+        amounts: Vec<i128>,
+        milestones: Vec<Milestone>,
+        arbitrator: Option<Address>,
+        timeout: u64,
+    ) -> Result<u32, EscrowError> {
+        creator.require_auth();
 
-        let mut deposited = vec![&env];
-        let mut approvals = vec![&env];
+        if milestones.is_empty() {
+            return Err(EscrowError::InvalidMilestone);
+        }
 
-        for _ in 0..parties.len() {
-            deposited.push_back(0i128);
-            approvals.push_back(false);
+        let mut milestone_total: i128 = 0;
+        let mut fresh = vec![&env];
+        for milestone in milestones.iter() {
+            if milestone.amount <= 0 || !parties.contains(&milestone.recipient) {
+                return Err(EscrowError::InvalidMilestone);
+            }
+            milestone_total += milestone.amount;
+            fresh.push_back(Milestone {
+                evidence: None,
+                submitted_by: None,
+                fulfilled: false,
+                ..milestone
+            });
         }
 
-        let escrow = EscrowData {
-            id: escrow_id,
-            creator: creator.clone(),
-            parties: parties.clone(),
-            token: token.clone(),
-            amounts: amounts.clone(),
-            deposited,
-            approvals,
-            state: EscrowState::Created,
-            conditions,
+        if milestone_total != amounts.iter().sum::<i128>() {
+            return Err(EscrowError::AmountMismatch);
+        }
+
+        Self::open_escrow(
+            &env,
+            creator,
+            parties,
+            token,
+            amounts,
+            vec![&env],
+            fresh,
             arbitrator,
-            timeout: current_time + timeout,
-            created_at: current_time,
-        };
+            timeout,
+        )
+    }
+
+    /// Attach evidence that a milestone has been met
+    pub fn submit_evidence(
+        env: Env,
+        submitter: Address,
+        escrow_id: u32,
+        milestone_index: u32,
+        evidence: String,
+    ) -> Result<(), EscrowError> {
+        submitter.require_auth();
+
+        let mut escrow: EscrowData = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Escrow(escrow_id))
+            .ok_or(EscrowError::EscrowNotFound)?;
+
+        if escrow.state != EscrowState::Active {
+            return Err(EscrowError::InvalidState);
+        }
+
+        if !escrow.parties.contains(&submitter) {
+            return Err(EscrowError::NotParty);
+        }
+
+        let mut milestone = escrow
+            .milestones
+            .get(milestone_index)
+            .ok_or(EscrowError::InvalidMilestone)?;
+        if milestone.fulfilled {
+            return Err(EscrowError::MilestoneFulfilled);
+        }
+
+        milestone.evidence = Some(evidence.clone());
+        milestone.submitted_by = Some(submitter.clone());
+        escrow.milestones.set(milestone_index, milestone);
 
         env.storage()
             .persistent()
             .set(&DataKey::Escrow(escrow_id), &escrow);
-        env.storage()
+        env.events().publish(
+            (EVIDENCE_SUBMITTED,),
+            (escrow_id, milestone_index, submitter, evidence),
+        );
+        Ok(())
+    }
+
+    /// Accept a milestone and release its slice. The arbitrator can always
+    /// accept; otherwise a party other than the evidence submitter must.
+    pub fn fulfill_milestone(
+        env: Env,
+        approver: Address,
+        escrow_id: u32,
+        milestone_index: u32,
+    ) -> Result<(), EscrowError> {
+        approver.require_auth();
+
+        let mut escrow: EscrowData = env
+            .storage()
             .persistent()
-            .set(&DataKey::NextEscrowId, &(escrow_id + 1));
+            .get(&DataKey::Escrow(escrow_id))
+            .ok_or(EscrowError::EscrowNotFound)?;
 
-        env.events()
-            .publish((ESCROW_CREATED,), (escrow_id, creator, parties, amounts));
-        Ok(escrow_id)
+        if escrow.state != EscrowState::Active {
+            return Err(EscrowError::InvalidState);
+        }
+
+        let mut milestone = escrow
+            .milestones
+            .get(milestone_index)
+            .ok_or(EscrowError::InvalidMilestone)?;
+        if milestone.fulfilled {
+            return Err(EscrowError::MilestoneFulfilled);
+        }
+
+        if escrow.arbitrator != Some(approver.clone()) {
+            let submitter = milestone
+                .submitted_by
+                .clone()
+                .ok_or(EscrowError::EvidenceMissing)?;
+            if !escrow.parties.contains(&approver) {
+                return Err(EscrowError::NotParty);
+            }
+            if submitter == approver {
+                return Err(EscrowError::Unauthorized);
+            }
+        }
+
+        token::Client::new(&env, &escrow.token).transfer(
+            &env.current_contract_address(),
+            &milestone.recipient,
+            &milestone.amount,
+        );
+
+        milestone.fulfilled = true;
+        escrow.released += milestone.amount;
+        escrow.milestones.set(milestone_index, milestone.clone());
+
+        env.events().publish(
+            (PARTIAL_RELEASE,),
+            (escrow_id, milestone_index, milestone.amount),
+        );
+
+        if escrow.milestones.iter().all(|m| m.fulfilled) {
+            escrow.state = EscrowState::Released;
+            env.events()
+                .publish((ESCROW_RELEASED,), (escrow_id, escrow.released));
+        }
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Escrow(escrow_id), &escrow);
+        Ok(())
     }
 
     pub fn deposit(
@@ -226,7 +438,13 @@ impl EscrowContract {
 
         escrow.deposited.set(party_idx as u32, amount);
 
-        if escrow.deposited.iter().all(|d| d > 0) {
+        // Parties with nothing to deposit (e.g. the payee of a milestone escrow) don't block activation
+        if escrow
+            .amounts
+            .iter()
+            .zip(escrow.deposited.iter())
+            .all(|(a, d)| d >= a)
+        {
             escrow.state = EscrowState::Active;
             env.events().publish((ESCROW_ACTIVATED,), (escrow_id,));
         }
@@ -295,16 +513,25 @@ impl EscrowContract {
             return Err(EscrowError::Unauthorized);
         }
 
-        let total_amount: i128 = escrow.amounts.iter().sum();
-        let release_amount = partial_amount.unwrap_or(total_amount);
+        let remaining = Self::remaining_funds(&escrow);
+        let release_amount = partial_amount.unwrap_or(remaining);
 
-        if release_amount > total_amount {
+        if release_amount > remaining {
             return Err(EscrowError::AmountMismatch);
         }
 
-        Self::distribute_funds(&env, &escrow, release_amount)?;
+        if release_amount == remaining {
+            Self::release_remaining(&env, &mut escrow)?;
+        } else {
+            // Milestone escrows release slices through `fulfill_milestone`
+            if !escrow.milestones.is_empty() {
+                return Err(EscrowError::InvalidMilestone);
+            }
+            Self::distribute_funds(&env, &escrow, release_amount)?;
+            escrow.released += release_amount;
+        }
 
-        if release_amount == total_amount {
+        if release_amount == remaining {
             escrow.state = EscrowState::Released;
             env.events()
                 .publish((ESCROW_RELEASED,), (escrow_id, release_amount));
@@ -376,10 +603,14 @@ impl EscrowContract {
         if escrow.arbitrator != Some(arbitrator.clone()) {
             return Err(EscrowError::Unauthorized);
         }
+        // A dispute before a panel is settled by the panel alone
+        if env.storage().persistent().has(&DataKey::Panel(escrow_id)) {
+            return Err(EscrowError::InvalidState);
+        }
 
         match resolution {
             DisputeResolution::Release => {
-                Self::distribute_funds(&env, &escrow, escrow.amounts.iter().sum())?;
+                Self::release_remaining(&env, &mut escrow)?;
                 escrow.state = EscrowState::Released;
             }
             DisputeResolution::Refund => {
@@ -421,10 +652,685 @@ impl EscrowContract {
         Ok(())
     }
 
+    /// Set up the arbitrator registry. The caller becomes its admin.
+    pub fn init_arbitration(
+        env: Env,
+        admin: Address,
+        stake_token: Address,
+        min_stake: i128,
+        fee_bps: u32,
+        slash_bps: u32,
+    ) -> Result<(), EscrowError> {
+        admin.require_auth();
+
+        if env.storage().persistent().has(&DataKey::ArbitrationConfig) {
+            return Err(EscrowError::AlreadyConfigured);
+        }
+        if min_stake <= 0 || fee_bps as i128 > BASIS_POINTS || slash_bps as i128 > BASIS_POINTS {
+            return Err(EscrowError::InsufficientStake);
+        }
+
+        let config = ArbitrationConfig {
+            admin,
+            stake_token,
+            min_stake,
+            fee_bps,
+            slash_bps,
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::ArbitrationConfig, &config);
+        Ok(())
+    }
+
+    /// Join the registry or top up a bond. Arbitrators below the minimum
+    /// stake are not drawn for panels.
+    pub fn register_arbitrator(
+        env: Env,
+        arbitrator: Address,
+        stake: i128,
+    ) -> Result<(), EscrowError> {
+        arbitrator.require_auth();
+
+        let config = Self::arbitration_config(&env)?;
+        if stake <= 0 {
+            return Err(EscrowError::InsufficientStake);
+        }
+
+        let key = DataKey::Arbitrator(arbitrator.clone());
+        let mut info: ArbitratorInfo = env.storage().persistent().get(&key).unwrap_or(ArbitratorInfo {
+            stake: 0,
+            active_cases: 0,
+        });
+        if info.stake + stake < config.min_stake {
+            return Err(EscrowError::InsufficientStake);
+        }
+
+        token::Client::new(&env, &config.stake_token).transfer(
+            &arbitrator,
+            &env.current_contract_address(),
+            &stake,
+        );
+
+        if info.stake == 0 {
+            let mut registry: Vec<Address> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Arbitrators)
+                .unwrap_or(vec![&env]);
+            if !registry.contains(&arbitrator) {
+                registry.push_back(arbitrator.clone());
+                env.storage().persistent().set(&DataKey::Arbitrators, &registry);
+            }
+        }
+
+        info.stake += stake;
+        env.storage().persistent().set(&key, &info);
+        Ok(())
+    }
+
+    /// Leave the registry and withdraw the bond once no panel is pending
+    pub fn unregister_arbitrator(env: Env, arbitrator: Address) -> Result<i128, EscrowError> {
+        arbitrator.require_auth();
+
+        let config = Self::arbitration_config(&env)?;
+        let key = DataKey::Arbitrator(arbitrator.clone());
+        let info: ArbitratorInfo = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .ok_or(EscrowError::NotPanelist)?;
+
+        if info.active_cases > 0 {
+            return Err(EscrowError::InvalidState);
+        }
+
+        let registry: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Arbitrators)
+            .unwrap_or(vec![&env]);
+        let mut remaining = vec![&env];
+        for member in registry.iter() {
+            if member != arbitrator {
+                remaining.push_back(member);
+            }
+        }
+        env.storage().persistent().set(&DataKey::Arbitrators, &remaining);
+        env.storage().persistent().remove(&key);
+
+        if info.stake > 0 {
+            token::Client::new(&env, &config.stake_token).transfer(
+                &env.current_contract_address(),
+                &arbitrator,
+                &info.stake,
+            );
+        }
+        Ok(info.stake)
+    }
+
+    /// Dispute an escrow before a randomly drawn panel of 3 or 5 staked arbitrators.
+    ///
+    /// Only arbitrators registered before the escrow was created are drawn,
+    /// so nobody can stack the registry once the escrow exists.
+    pub fn dispute_with_panel(
+        env: Env,
+        disputer: Address,
+        escrow_id: u32,
+        reason: String,
+        panel_size: u32,
+    ) -> Result<(), EscrowError> {
+        disputer.require_auth();
+
+        let config = Self::arbitration_config(&env)?;
+        let mut escrow: EscrowData = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Escrow(escrow_id))
+            .ok_or(EscrowError::EscrowNotFound)?;
+
+        if escrow.state != EscrowState::Active {
+            return Err(EscrowError::InvalidState);
+        }
+        if !escrow.parties.contains(&disputer) {
+            return Err(EscrowError::NotParty);
+        }
+        if panel_size != 3 && panel_size != 5 {
+            return Err(EscrowError::InvalidPanelSize);
+        }
+
+        let pool: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::PanelPool(escrow_id))
+            .unwrap_or(vec![&env]);
+        let mut eligible = vec![&env];
+        for candidate in pool.iter() {
+            let info = Self::arbitrator_info(&env, &candidate);
+            if info.stake >= config.min_stake && !escrow.parties.contains(&candidate) {
+                eligible.push_back(candidate);
+            }
+        }
+        if eligible.len() < panel_size {
+            return Err(EscrowError::NotEnoughArbitrators);
+        }
+
+        let mut members = vec![&env];
+        for _ in 0..panel_size {
+            let pick = env.prng().gen_range::<u64>(0..eligible.len() as u64) as u32;
+            let member = eligible.get(pick).unwrap();
+            eligible.remove(pick);
+
+            let mut info = Self::arbitrator_info(&env, &member);
+            info.active_cases += 1;
+            env.storage()
+                .persistent()
+                .set(&DataKey::Arbitrator(member.clone()), &info);
+            members.push_back(member);
+        }
+
+        let panel = DisputePanel {
+            escrow_id,
+            members: members.clone(),
+            votes: Map::new(&env),
+            deadline: env.ledger().timestamp() + PANEL_VOTING_PERIOD,
+        };
+        env.storage().persistent().set(&DataKey::Panel(escrow_id), &panel);
+
+        escrow.state = EscrowState::Disputed;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Escrow(escrow_id), &escrow);
+
+        env.events()
+            .publish((DISPUTE_INITIATED,), (escrow_id, disputer, reason));
+        env.events().publish((PANEL_DRAWN,), (escrow_id, members));
+        Ok(())
+    }
+
+    /// Record a panelist's vote. The dispute settles once every panelist has voted.
+    pub fn cast_panel_vote(
+        env: Env,
+        arbitrator: Address,
+        escrow_id: u32,
+        vote: PanelVote,
+    ) -> Result<(), EscrowError> {
+        arbitrator.require_auth();
+
+        let mut panel: DisputePanel = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Panel(escrow_id))
+            .ok_or(EscrowError::InvalidState)?;
+
+        if !panel.members.contains(&arbitrator) {
+            return Err(EscrowError::NotPanelist);
+        }
+        if panel.votes.contains_key(arbitrator.clone()) {
+            return Err(EscrowError::AlreadyVoted);
+        }
+        if env.ledger().timestamp() > panel.deadline {
+            return Err(EscrowError::InvalidState);
+        }
+        if let PanelVote::Split(share) = vote {
+            if share as i128 > BASIS_POINTS {
+                return Err(EscrowError::InvalidVote);
+            }
+        }
+
+        panel.votes.set(arbitrator.clone(), vote.clone());
+        env.events()
+            .publish((PANEL_VOTE,), (escrow_id, arbitrator, vote));
+
+        if panel.votes.len() == panel.members.len() {
+            Self::settle_panel(&env, &panel)
+        } else {
+            env.storage().persistent().set(&DataKey::Panel(escrow_id), &panel);
+            Ok(())
+        }
+    }
+
+    /// Settle a panel after the voting period with the votes cast so far.
+    /// Panelists who didn't vote are slashed; if the votes cast don't form a
+    /// majority of the panel, the remaining funds are refunded.
+    pub fn finalize_panel(env: Env, escrow_id: u32) -> Result<(), EscrowError> {
+        let panel: DisputePanel = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Panel(escrow_id))
+            .ok_or(EscrowError::InvalidState)?;
+
+        if env.ledger().timestamp() <= panel.deadline {
+            return Err(EscrowError::VotingOpen);
+        }
+
+        Self::settle_panel(&env, &panel)
+    }
+
+    pub fn get_panel(env: Env, escrow_id: u32) -> Option<DisputePanel> {
+        env.storage().persistent().get(&DataKey::Panel(escrow_id))
+    }
+
+    pub fn get_arbitrator(env: Env, arbitrator: Address) -> Option<ArbitratorInfo> {
+        env.storage().persistent().get(&DataKey::Arbitrator(arbitrator))
+    }
+
     pub fn get_escrow(env: Env, escrow_id: u32) -> Option<EscrowData> {
         env.storage().persistent().get(&DataKey::Escrow(escrow_id))
     }
 
+    /// Create a multi-asset swap. Each leg's basket goes to its recipient once
+    /// every party has deposited; `deposited` on the input legs is ignored.
+    pub fn create_swap(
+        env: Env,
+        creator: Address,
+        legs: Vec<SwapLeg>,
+        timeout: u64,
+    ) -> Result<u32, EscrowError> {
+        creator.require_auth();
+
+        if legs.len() < 2 {
+            return Err(EscrowError::InvalidParties);
+        }
+
+        let mut parties: Vec<Address> = vec![&env];
+        for leg in legs.iter() {
+            if parties.contains(&leg.party) {
+                return Err(EscrowError::InvalidParties);
+            }
+            parties.push_back(leg.party.clone());
+        }
+
+        let mut swap_legs = vec![&env];
+        for leg in legs.iter() {
+            if leg.recipient == leg.party || !parties.contains(&leg.recipient) {
+                return Err(EscrowError::InvalidParties);
+            }
+            if leg.basket.is_empty() {
+                return Err(EscrowError::InvalidBasket);
+            }
+            for asset in leg.basket.iter() {
+                if let EscrowAsset::Token(_, amount) = asset {
+                    if amount <= 0 {
+                        return Err(EscrowError::InvalidBasket);
+                    }
+                }
+            }
+
+            swap_legs.push_back(SwapLeg {
+                deposited: false,
+                ..leg
+            });
+        }
+
+        let swap_id: u32 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::NextSwapId)
+            .unwrap_or(1);
+        let current_time = env.ledger().timestamp();
+
+        let swap = SwapDeal {
+            id: swap_id,
+            creator: creator.clone(),
+            legs: swap_legs,
+            state: EscrowState::Created,
+            timeout: current_time + timeout,
+            created_at: current_time,
+        };
+
+        env.storage().persistent().set(&DataKey::Swap(swap_id), &swap);
+        env.storage()
+            .persistent()
+            .set(&DataKey::NextSwapId, &(swap_id + 1));
+
+        env.events()
+            .publish((SWAP_CREATED,), (swap_id, creator, parties));
+        Ok(swap_id)
+    }
+
+    /// Deposit the caller's basket. The last deposit settles the swap, moving
+    /// every basket to its recipient in the same transaction.
+    pub fn deposit_basket(env: Env, party: Address, swap_id: u32) -> Result<(), EscrowError> {
+        party.require_auth();
+
+        let mut swap: SwapDeal = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Swap(swap_id))
+            .ok_or(EscrowError::EscrowNotFound)?;
+
+        if swap.state != EscrowState::Created {
+            return Err(EscrowError::InvalidState);
+        }
+
+        if env.ledger().timestamp() >= swap.timeout {
+            return Err(EscrowError::InvalidState);
+        }
+
+        let leg_idx = swap
+            .legs
+            .iter()
+            .position(|l| l.party == party)
+            .ok_or(EscrowError::NotParty)? as u32;
+
+        let mut leg = swap.legs.get(leg_idx).unwrap();
+        if leg.deposited {
+            return Err(EscrowError::AlreadyDeposited);
+        }
+
+        let contract = env.current_contract_address();
+        for asset in leg.basket.iter() {
+            if let EscrowAsset::Nft(nft_contract, token_id) = &asset {
+                Self::verify_nft_owner(&env, nft_contract, *token_id, &party)?;
+            }
+            Self::transfer_escrow_asset(&env, &asset, &party, &contract);
+        }
+
+        leg.deposited = true;
+        swap.legs.set(leg_idx, leg);
+        env.events()
+            .publish((BASKET_DEPOSITED,), (swap_id, party));
+
+        if swap.legs.iter().all(|l| l.deposited) {
+            for leg in swap.legs.iter() {
+                for asset in leg.basket.iter() {
+                    Self::transfer_escrow_asset(&env, &asset, &contract, &leg.recipient);
+                }
+            }
+            swap.state = EscrowState::Released;
+            env.events().publish((SWAP_EXECUTED,), (swap_id,));
+        }
+
+        env.storage().persistent().set(&DataKey::Swap(swap_id), &swap);
+        Ok(())
+    }
+
+    /// After the timeout, return each deposited basket to the party that committed it
+    pub fn refund_swap_timeout(env: Env, swap_id: u32) -> Result<(), EscrowError> {
+        let mut swap: SwapDeal = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Swap(swap_id))
+            .ok_or(EscrowError::EscrowNotFound)?;
+
+        if swap.state != EscrowState::Created {
+            return Err(EscrowError::InvalidState);
+        }
+
+        if env.ledger().timestamp() < swap.timeout {
+            return Err(EscrowError::TimeoutNotReached);
+        }
+
+        let contract = env.current_contract_address();
+        for leg in swap.legs.iter() {
+            if leg.deposited {
+                for asset in leg.basket.iter() {
+                    Self::transfer_escrow_asset(&env, &asset, &contract, &leg.party);
+                }
+            }
+        }
+        swap.state = EscrowState::Refunded;
+
+        env.storage().persistent().set(&DataKey::Swap(swap_id), &swap);
+        env.events().publish((TIMEOUT_REFUND,), (swap_id,));
+        Ok(())
+    }
+
+    pub fn get_swap(env: Env, swap_id: u32) -> Option<SwapDeal> {
+        env.storage().persistent().get(&DataKey::Swap(swap_id))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn open_escrow(
+        env: &Env,
+        creator: Address,
+        parties: Vec<Address>,
+        token: Address,
+        amounts: Vec<i128>,
+        conditions: Vec<ReleaseCondition>,
+        milestones: Vec<Milestone>,
+        arbitrator: Option<Address>,
+        timeout: u64,
+    ) -> Result<u32, EscrowError> {
+        if parties.is_empty() || amounts.is_empty() || parties.len() != amounts.len() {
+            return Err(EscrowError::InvalidParties);
+        }
+
+        let escrow_id = Self::get_next_escrow_id(env);
+        let current_time = env.ledger().timestamp();
+
+        let mut deposited = vec![env];
+        let mut approvals = vec![env];
+
+        for _ in 0..parties.len() {
+            deposited.push_back(0i128);
+            approvals.push_back(false);
+        }
+
+        let escrow = EscrowData {
+            id: escrow_id,
+            creator: creator.clone(),
+            parties: parties.clone(),
+            token: token.clone(),
+            amounts: amounts.clone(),
+            deposited,
+            approvals,
+            state: EscrowState::Created,
+            conditions,
+            milestones,
+            released: 0,
+            arbitrator,
+            timeout: current_time + timeout,
+            created_at: current_time,
+        };
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Escrow(escrow_id), &escrow);
+        env.storage()
+            .persistent()
+            .set(&DataKey::NextEscrowId, &(escrow_id + 1));
+
+        // Snapshot of the registry the panel is drawn from, so arbitrators
+        // registered after the escrow exists are never drawn for it
+        let pool: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Arbitrators)
+            .unwrap_or(vec![env]);
+        env.storage()
+            .persistent()
+            .set(&DataKey::PanelPool(escrow_id), &pool);
+
+        env.events()
+            .publish((ESCROW_CREATED,), (escrow_id, creator, parties, amounts));
+        Ok(escrow_id)
+    }
+
+    fn arbitration_config(env: &Env) -> Result<ArbitrationConfig, EscrowError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::ArbitrationConfig)
+            .ok_or(EscrowError::NotConfigured)
+    }
+
+    fn arbitrator_info(env: &Env, arbitrator: &Address) -> ArbitratorInfo {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Arbitrator(arbitrator.clone()))
+            .unwrap_or(ArbitratorInfo {
+                stake: 0,
+                active_cases: 0,
+            })
+    }
+
+    /// Share of the funds released for a vote, in basis points
+    fn vote_share(vote: &PanelVote) -> u32 {
+        match vote {
+            PanelVote::Release => BASIS_POINTS as u32,
+            PanelVote::Refund => 0,
+            PanelVote::Split(share) => *share,
+        }
+    }
+
+    fn same_kind(a: &PanelVote, b: &PanelVote) -> bool {
+        matches!(
+            (a, b),
+            (PanelVote::Release, PanelVote::Release)
+                | (PanelVote::Refund, PanelVote::Refund)
+                | (PanelVote::Split(_), PanelVote::Split(_))
+        )
+    }
+
+    /// Lower median of the shares of the given votes
+    fn median_share(env: &Env, votes: &Vec<PanelVote>) -> u32 {
+        let mut sorted: Vec<u32> = vec![env];
+        for vote in votes.iter() {
+            let share = Self::vote_share(&vote);
+            let pos = sorted.iter().position(|s| s > share).unwrap_or(sorted.len() as usize);
+            sorted.insert(pos as u32, share);
+        }
+        sorted.get((sorted.len() - 1) / 2).unwrap_or(0)
+    }
+
+    /// Apply the panel's decision: the majority kind of vote wins (a Split
+    /// majority uses the median of its percentages), the fee goes to the
+    /// majority and dissenting or absent panelists are slashed. A majority
+    /// needs more than half of the whole panel; without one the remaining
+    /// funds are refunded with no fee and only absentees are slashed.
+    fn settle_panel(env: &Env, panel: &DisputePanel) -> Result<(), EscrowError> {
+        let config = Self::arbitration_config(env)?;
+        let mut escrow: EscrowData = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Escrow(panel.escrow_id))
+            .ok_or(EscrowError::EscrowNotFound)?;
+        if escrow.state != EscrowState::Disputed {
+            return Err(EscrowError::InvalidState);
+        }
+
+        let cast: Vec<PanelVote> = panel.votes.values();
+        let mut majority: Option<PanelVote> = None;
+        for vote in cast.iter() {
+            let count = cast.iter().filter(|v| Self::same_kind(v, &vote)).count() as u32;
+            if count > panel.members.len() / 2 {
+                majority = Some(vote);
+                break;
+            }
+        }
+
+        let share = match &majority {
+            Some(winner) => {
+                let mut bloc = vec![env];
+                for vote in cast.iter() {
+                    if Self::same_kind(&vote, winner) {
+                        bloc.push_back(vote);
+                    }
+                }
+                Self::median_share(env, &bloc)
+            }
+            None => 0,
+        };
+
+        // The majority is paid from the fee; dissenters and absentees are slashed
+        let mut rewarded = vec![env];
+        let mut slashed = vec![env];
+        for member in panel.members.iter() {
+            match (panel.votes.get(member.clone()), &majority) {
+                (Some(vote), Some(winner)) if Self::same_kind(&vote, winner) => {
+                    rewarded.push_back(member)
+                }
+                (Some(_), None) => {}
+                _ => slashed.push_back(member),
+            }
+        }
+
+        let remaining = Self::remaining_funds(&escrow);
+        let fee = if rewarded.is_empty() {
+            0
+        } else {
+            remaining * config.fee_bps as i128 / BASIS_POINTS
+        };
+        let pool = remaining - fee;
+        let release_amount = pool * share as i128 / BASIS_POINTS;
+
+        if fee > 0 {
+            let token_client = token::Client::new(env, &escrow.token);
+            let per_member = fee / rewarded.len() as i128;
+            for (i, member) in rewarded.iter().enumerate() {
+                let cut = if i as u32 == rewarded.len() - 1 {
+                    fee - per_member * (rewarded.len() as i128 - 1)
+                } else {
+                    per_member
+                };
+                token_client.transfer(&env.current_contract_address(), &member, &cut);
+            }
+        }
+        if release_amount > 0 {
+            Self::release_amount(env, &mut escrow, release_amount)?;
+        }
+        Self::refund_pro_rata(env, &escrow, pool - release_amount)?;
+
+        // Slashed stake is shared among rewarded panelists, or sent to the admin if there are none
+        let mut slash_pool: i128 = 0;
+        for member in slashed.iter() {
+            let mut info = Self::arbitrator_info(env, &member);
+            let slash = info.stake * config.slash_bps as i128 / BASIS_POINTS;
+            info.stake -= slash;
+            slash_pool += slash;
+            env.storage()
+                .persistent()
+                .set(&DataKey::Arbitrator(member.clone()), &info);
+            env.events()
+                .publish((ARBITRATOR_SLASHED,), (panel.escrow_id, member, slash));
+        }
+        if slash_pool > 0 && rewarded.is_empty() {
+            token::Client::new(env, &config.stake_token).transfer(
+                &env.current_contract_address(),
+                &config.admin,
+                &slash_pool,
+            );
+            slash_pool = 0;
+        }
+
+        let per_member = if rewarded.is_empty() {
+            0
+        } else {
+            slash_pool / rewarded.len() as i128
+        };
+        for member in panel.members.iter() {
+            let mut info = Self::arbitrator_info(env, &member);
+            info.active_cases -= 1;
+            if let Some(pos) = rewarded.first_index_of(&member) {
+                info.stake += if pos == rewarded.len() - 1 {
+                    slash_pool - per_member * (rewarded.len() as i128 - 1)
+                } else {
+                    per_member
+                };
+            }
+            env.storage()
+                .persistent()
+                .set(&DataKey::Arbitrator(member.clone()), &info);
+        }
+
+        escrow.state = match share as i128 {
+            BASIS_POINTS => EscrowState::Released,
+            0 => EscrowState::Refunded,
+            _ => EscrowState::Resolved,
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::Escrow(panel.escrow_id), &escrow);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Panel(panel.escrow_id));
+
+        env.events()
+            .publish((DISPUTE_RESOLVED,), (panel.escrow_id, share, fee));
+        Ok(())
+    }
+
     fn get_next_escrow_id(env: &Env) -> u32 {
         env.storage()
             .persistent()
@@ -480,13 +1386,121 @@ impl EscrowContract {
         Ok(())
     }
 
+    fn verify_nft_owner(
+        env: &Env,
+        nft_contract: &Address,
+        token_id: u32,
+        owner: &Address,
+    ) -> Result<(), EscrowError> {
+        let current_owner: Address = env.invoke_contract(
+            nft_contract,
+            &Symbol::new(env, "owner_of"),
+            (token_id,).into_val(env),
+        );
+        if current_owner != *owner {
+            return Err(EscrowError::AssetNotOwned);
+        }
+        Ok(())
+    }
+
+    fn transfer_escrow_asset(env: &Env, asset: &EscrowAsset, from: &Address, to: &Address) {
+        match asset {
+            EscrowAsset::Token(token, amount) => {
+                token::Client::new(env, token).transfer(from, to, amount);
+            }
+            EscrowAsset::Nft(nft_contract, token_id) => {
+                env.invoke_contract::<()>(
+                    nft_contract,
+                    &Symbol::new(env, "transfer"),
+                    (from.clone(), to.clone(), *token_id).into_val(env),
+                );
+            }
+        }
+    }
+
+    /// Funds still held for an escrow after milestone and partial releases
+    fn remaining_funds(escrow: &EscrowData) -> i128 {
+        escrow.amounts.iter().sum::<i128>() - escrow.released
+    }
+
+    /// Pay out everything still held: outstanding milestones go to their
+    /// recipients, otherwise the remainder is split between the parties.
+    fn release_remaining(env: &Env, escrow: &mut EscrowData) -> Result<(), EscrowError> {
+        let remaining = Self::remaining_funds(escrow);
+        Self::release_amount(env, escrow, remaining)?;
+
+        for i in 0..escrow.milestones.len() {
+            let mut milestone = escrow.milestones.get(i).unwrap();
+            milestone.fulfilled = true;
+            escrow.milestones.set(i, milestone);
+        }
+        Ok(())
+    }
+
+    /// Release part of the remaining funds: split between outstanding
+    /// milestones in proportion to their amounts, or between the parties.
+    fn release_amount(env: &Env, escrow: &mut EscrowData, amount: i128) -> Result<(), EscrowError> {
+        if escrow.milestones.is_empty() {
+            Self::distribute_funds(env, escrow, amount)?;
+            escrow.released += amount;
+            return Ok(());
+        }
+
+        let outstanding: i128 = escrow
+            .milestones
+            .iter()
+            .filter(|m| !m.fulfilled)
+            .map(|m| m.amount)
+            .sum();
+        let token_client = token::Client::new(env, &escrow.token);
+        let mut paid: i128 = 0;
+        let mut last_recipient: Option<Address> = None;
+        for milestone in escrow.milestones.iter().filter(|m| !m.fulfilled) {
+            let cut = amount * milestone.amount / outstanding;
+            if cut > 0 {
+                token_client.transfer(&env.current_contract_address(), &milestone.recipient, &cut);
+            }
+            paid += cut;
+            last_recipient = Some(milestone.recipient);
+        }
+        if let Some(recipient) = last_recipient {
+            if amount > paid {
+                token_client.transfer(&env.current_contract_address(), &recipient, &(amount - paid));
+            }
+        }
+        escrow.released += amount;
+        Ok(())
+    }
+
+    /// Return the remaining funds to depositors, pro rata to what each put in
     fn refund_all(env: &Env, escrow: &EscrowData) -> Result<(), EscrowError> {
+        let total_deposited: i128 = escrow.deposited.iter().sum();
+        Self::refund_pro_rata(env, escrow, total_deposited - escrow.released)
+    }
+
+    fn refund_pro_rata(env: &Env, escrow: &EscrowData, amount: i128) -> Result<(), EscrowError> {
         let token_client = token::Client::new(env, &escrow.token);
+        let total_deposited: i128 = escrow.deposited.iter().sum();
+        if amount <= 0 || total_deposited == 0 {
+            return Ok(());
+        }
 
+        let mut paid: i128 = 0;
+        let mut last_depositor: Option<Address> = None;
         for (i, party) in escrow.parties.iter().enumerate() {
             let deposited = escrow.deposited.get(i as u32).unwrap();
             if deposited > 0 {
-                token_client.transfer(&env.current_contract_address(), &party, &deposited);
+                let refund = deposited * amount / total_deposited;
+                if refund > 0 {
+                    token_client.transfer(&env.current_contract_address(), &party, &refund);
+                }
+                paid += refund;
+                last_depositor = Some(party);
+            }
+        }
+        if let Some(depositor) = last_depositor {
+            if amount > paid {
+                token_client.transfer(&env.current_contract_address(), &depositor, &(amount - paid));
             }
         }
         Ok(())
@@ -1,8 +1,9 @@
 #![cfg(test)]
 use super::*;
 use soroban_sdk::{
+    contract, contractimpl, contracttype,
     testutils::{Address as _, Ledger},
-    Address, Env, String, Vec,
+    token, Address, Env, String, Vec,
 };
 
 #[test]
@@ -221,3 +222,557 @@ fn test_error_conditions() {
 
     assert_eq!(result, Err(Ok(EscrowError::InvalidParties)));
 }
+
+#[contracttype]
+enum MockNftDataKey {
+    Owner(u32),
+}
+
+#[contract]
+struct MockNft;
+
+#[contractimpl]
+impl MockNft {
+    pub fn mint(env: Env, to: Address, token_id: u32) {
+        env.storage()
+            .persistent()
+            .set(&MockNftDataKey::Owner(token_id), &to);
+    }
+
+    pub fn transfer(env: Env, from: Address, to: Address, token_id: u32) {
+        from.require_auth();
+        let owner: Address = env
+            .storage()
+            .persistent()
+            .get(&MockNftDataKey::Owner(token_id))
+            .unwrap();
+        if owner != from {
+            panic!("Not owner");
+        }
+        env.storage()
+            .persistent()
+            .set(&MockNftDataKey::Owner(token_id), &to);
+    }
+
+    pub fn owner_of(env: Env, token_id: u32) -> Address {
+        env.storage()
+            .persistent()
+            .get(&MockNftDataKey::Owner(token_id))
+            .unwrap()
+    }
+}
+
+fn setup_swap(
+    env: &Env,
+) -> (
+    EscrowContractClient<'_>,
+    Address,
+    Address,
+    Address,
+    Address,
+    MockNftClient<'_>,
+    u32,
+) {
+    let contract_id = env.register_contract(None, EscrowContract);
+    let client = EscrowContractClient::new(env, &contract_id);
+
+    let alice = Address::generate(env);
+    let bob = Address::generate(env);
+
+    let gold = env
+        .register_stellar_asset_contract_v2(Address::generate(env))
+        .address();
+    let gems = env
+        .register_stellar_asset_contract_v2(Address::generate(env))
+        .address();
+    token::StellarAssetClient::new(env, &gold).mint(&alice, &1_000);
+    token::StellarAssetClient::new(env, &gems).mint(&bob, &1_000);
+
+    let nft_id = env.register_contract(None, MockNft);
+    let nft = MockNftClient::new(env, &nft_id);
+    nft.mint(&alice, &1);
+    nft.mint(&bob, &2);
+
+    let legs = Vec::from_array(
+        env,
+        [
+            SwapLeg {
+                party: alice.clone(),
+                basket: Vec::from_array(
+                    env,
+                    [
+                        EscrowAsset::Token(gold.clone(), 500),
+                        EscrowAsset::Nft(nft_id.clone(), 1),
+                    ],
+                ),
+                recipient: bob.clone(),
+                deposited: false,
+            },
+            SwapLeg {
+                party: bob.clone(),
+                basket: Vec::from_array(
+                    env,
+                    [
+                        EscrowAsset::Token(gems.clone(), 300),
+                        EscrowAsset::Nft(nft_id.clone(), 2),
+                    ],
+                ),
+                recipient: alice.clone(),
+                deposited: false,
+            },
+        ],
+    );
+    let swap_id = client.create_swap(&alice, &legs, &3600u64);
+
+    (client, alice, bob, gold, gems, nft, swap_id)
+}
+
+#[test]
+fn test_multi_asset_swap() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, alice, bob, gold, gems, nft, swap_id) = setup_swap(&env);
+
+    client.deposit_basket(&alice, &swap_id);
+    assert_eq!(nft.owner_of(&1), client.address);
+    assert_eq!(
+        client.try_deposit_basket(&alice, &swap_id),
+        Err(Ok(EscrowError::AlreadyDeposited))
+    );
+
+    // Bob's deposit settles both sides at once
+    client.deposit_basket(&bob, &swap_id);
+    assert_eq!(client.get_swap(&swap_id).unwrap().state, EscrowState::Released);
+    assert_eq!(token::Client::new(&env, &gold).balance(&bob), 500);
+    assert_eq!(token::Client::new(&env, &gems).balance(&alice), 300);
+    assert_eq!(nft.owner_of(&1), bob);
+    assert_eq!(nft.owner_of(&2), alice);
+}
+
+#[test]
+fn test_swap_timeout_refunds_own_basket() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, alice, bob, gold, _, nft, swap_id) = setup_swap(&env);
+
+    client.deposit_basket(&alice, &swap_id);
+    assert_eq!(
+        client.try_refund_swap_timeout(&swap_id),
+        Err(Ok(EscrowError::TimeoutNotReached))
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 3600);
+    assert_eq!(
+        client.try_deposit_basket(&bob, &swap_id),
+        Err(Ok(EscrowError::InvalidState))
+    );
+
+    client.refund_swap_timeout(&swap_id);
+    assert_eq!(client.get_swap(&swap_id).unwrap().state, EscrowState::Refunded);
+    assert_eq!(token::Client::new(&env, &gold).balance(&alice), 1_000);
+    assert_eq!(nft.owner_of(&1), alice);
+    assert_eq!(nft.owner_of(&2), bob);
+}
+
+#[test]
+fn test_milestone_partial_release_and_dispute() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, EscrowContract);
+    let client = EscrowContractClient::new(&env, &contract_id);
+
+    let client_party = Address::generate(&env);
+    let freelancer = Address::generate(&env);
+    let arbitrator = Address::generate(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    token::StellarAssetClient::new(&env, &token).mint(&client_party, &1_000);
+    let token_client = token::Client::new(&env, &token);
+
+    let milestone = |description: &str, amount: i128| Milestone {
+        description: String::from_str(&env, description),
+        amount,
+        recipient: freelancer.clone(),
+        evidence: None,
+        submitted_by: None,
+        fulfilled: false,
+    };
+    let milestones = Vec::from_array(
+        &env,
+        [
+            milestone("Design", 300),
+            milestone("Build", 400),
+            milestone("Launch", 300),
+        ],
+    );
+
+    let parties = Vec::from_array(&env, [client_party.clone(), freelancer.clone()]);
+    let amounts = Vec::from_array(&env, [1_000i128, 0i128]);
+    let escrow_id = client.create_milestone_escrow(
+        &client_party,
+        &parties,
+        &token,
+        &amounts,
+        &milestones,
+        &Some(arbitrator.clone()),
+        &3600u64,
+    );
+
+    client.deposit(&client_party, &escrow_id, &1_000);
+    assert_eq!(client.get_escrow(&escrow_id).unwrap().state, EscrowState::Active);
+
+    // The submitter cannot approve their own milestone, and evidence comes first
+    assert_eq!(
+        client.try_fulfill_milestone(&client_party, &escrow_id, &0),
+        Err(Ok(EscrowError::EvidenceMissing))
+    );
+    client.submit_evidence(&freelancer, &escrow_id, &0, &String::from_str(&env, "ipfs://design"));
+    assert_eq!(
+        client.try_fulfill_milestone(&freelancer, &escrow_id, &0),
+        Err(Ok(EscrowError::Unauthorized))
+    );
+
+    client.fulfill_milestone(&client_party, &escrow_id, &0);
+    assert_eq!(token_client.balance(&freelancer), 300);
+    assert_eq!(
+        client.try_fulfill_milestone(&client_party, &escrow_id, &0),
+        Err(Ok(EscrowError::MilestoneFulfilled))
+    );
+
+    // The arbitrator can accept without evidence
+    client.fulfill_milestone(&arbitrator, &escrow_id, &1);
+    assert_eq!(token_client.balance(&freelancer), 700);
+    assert_eq!(client.get_escrow(&escrow_id).unwrap().released, 700);
+
+    // A dispute only puts the unreleased 300 at stake
+    client.dispute(&client_party, &escrow_id, &String::from_str(&env, "Launch missed"));
+    client.resolve_dispute(&arbitrator, &escrow_id, &DisputeResolution::Refund);
+    assert_eq!(token_client.balance(&client_party), 300);
+    assert_eq!(token_client.balance(&freelancer), 700);
+    assert_eq!(client.get_escrow(&escrow_id).unwrap().state, EscrowState::Refunded);
+}
+
+#[test]
+fn test_refund_rounding_dust_goes_to_last_depositor() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, EscrowContract);
+    let client = EscrowContractClient::new(&env, &contract_id);
+
+    let depositors = [
+        Address::generate(&env),
+        Address::generate(&env),
+        Address::generate(&env),
+    ];
+    let freelancer = Address::generate(&env);
+    let arbitrator = Address::generate(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    let token_client = token::Client::new(&env, &token);
+
+    let milestone = |amount: i128| Milestone {
+        description: String::from_str(&env, "Work"),
+        amount,
+        recipient: freelancer.clone(),
+        evidence: None,
+        submitted_by: None,
+        fulfilled: false,
+    };
+    let escrow_id = client.create_milestone_escrow(
+        &depositors[0],
+        &Vec::from_array(
+            &env,
+            [
+                depositors[0].clone(),
+                depositors[1].clone(),
+                depositors[2].clone(),
+                freelancer.clone(),
+            ],
+        ),
+        &token,
+        &Vec::from_array(&env, [1i128, 1i128, 1i128, 0i128]),
+        &Vec::from_array(&env, [milestone(1), milestone(2)]),
+        &Some(arbitrator.clone()),
+        &3600u64,
+    );
+    for depositor in &depositors {
+        token::StellarAssetClient::new(&env, &token).mint(depositor, &1);
+        client.deposit(depositor, &escrow_id, &1);
+    }
+
+    client.fulfill_milestone(&arbitrator, &escrow_id, &0);
+    assert_eq!(token_client.balance(&freelancer), 1);
+
+    // 2 split three ways rounds to 0 each; the remainder isn't left behind
+    client.dispute(&depositors[0], &escrow_id, &String::from_str(&env, "Stalled"));
+    client.resolve_dispute(&arbitrator, &escrow_id, &DisputeResolution::Refund);
+    assert_eq!(token_client.balance(&depositors[0]), 0);
+    assert_eq!(token_client.balance(&depositors[1]), 0);
+    assert_eq!(token_client.balance(&depositors[2]), 2);
+    assert_eq!(token_client.balance(&contract_id), 0);
+}
+
+fn setup_panel_dispute(
+    env: &Env,
+) -> (
+    EscrowContractClient<'_>,
+    Address,
+    Address,
+    Address,
+    Address,
+    u32,
+) {
+    let contract_id = env.register_contract(None, EscrowContract);
+    let client = EscrowContractClient::new(env, &contract_id);
+
+    let admin = Address::generate(env);
+    let stake_token = env
+        .register_stellar_asset_contract_v2(Address::generate(env))
+        .address();
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(env))
+        .address();
+    client.init_arbitration(&admin, &stake_token, &100, &500u32, &1_000u32);
+
+    for _ in 0..5 {
+        let arbitrator = Address::generate(env);
+        token::StellarAssetClient::new(env, &stake_token).mint(&arbitrator, &100);
+        client.register_arbitrator(&arbitrator, &100);
+    }
+
+    let buyer = Address::generate(env);
+    let seller = Address::generate(env);
+    token::StellarAssetClient::new(env, &token).mint(&buyer, &1_000);
+
+    let milestones = Vec::from_array(
+        env,
+        [Milestone {
+            description: String::from_str(env, "Delivery"),
+            amount: 1_000,
+            recipient: seller.clone(),
+            evidence: None,
+            submitted_by: None,
+            fulfilled: false,
+        }],
+    );
+    let escrow_id = client.create_milestone_escrow(
+        &buyer,
+        &Vec::from_array(env, [buyer.clone(), seller.clone()]),
+        &token,
+        &Vec::from_array(env, [1_000i128, 0i128]),
+        &milestones,
+        &None,
+        &3600u64,
+    );
+    client.deposit(&buyer, &escrow_id, &1_000);
+
+    (client, admin, token, buyer, seller, escrow_id)
+}
+
+#[test]
+fn test_panel_majority_split_and_slashing() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _, token, buyer, seller, escrow_id) = setup_panel_dispute(&env);
+    let token_client = token::Client::new(&env, &token);
+
+    assert_eq!(
+        client.try_dispute_with_panel(&buyer, &escrow_id, &String::from_str(&env, "Late"), &4),
+        Err(Ok(EscrowError::InvalidPanelSize))
+    );
+    client.dispute_with_panel(&buyer, &escrow_id, &String::from_str(&env, "Late"), &3);
+
+    let panel = client.get_panel(&escrow_id).unwrap();
+    assert_eq!(panel.members.len(), 3);
+    let first = panel.members.get(0).unwrap();
+    let second = panel.members.get(1).unwrap();
+    let dissenter = panel.members.get(2).unwrap();
+    assert_eq!(client.get_arbitrator(&first).unwrap().active_cases, 1);
+    assert_eq!(
+        client.try_unregister_arbitrator(&first),
+        Err(Ok(EscrowError::InvalidState))
+    );
+
+    client.cast_panel_vote(&first, &escrow_id, &PanelVote::Split(6_000));
+    assert_eq!(
+        client.try_cast_panel_vote(&first, &escrow_id, &PanelVote::Release),
+        Err(Ok(EscrowError::AlreadyVoted))
+    );
+    client.cast_panel_vote(&second, &escrow_id, &PanelVote::Split(6_000));
+    client.cast_panel_vote(&dissenter, &escrow_id, &PanelVote::Refund);
+
+    // 5% fee to the majority, then 60% of the rest released and 40% refunded
+    assert_eq!(token_client.balance(&first), 25);
+    assert_eq!(token_client.balance(&second), 25);
+    assert_eq!(token_client.balance(&seller), 570);
+    assert_eq!(token_client.balance(&buyer), 380);
+    assert_eq!(client.get_escrow(&escrow_id).unwrap().state, EscrowState::Resolved);
+
+    // The dissenter loses 10% of their bond to the majority
+    assert_eq!(client.get_arbitrator(&dissenter).unwrap().stake, 90);
+    assert_eq!(client.get_arbitrator(&first).unwrap().stake, 105);
+    assert_eq!(client.get_arbitrator(&second).unwrap().stake, 105);
+    assert_eq!(client.get_arbitrator(&first).unwrap().active_cases, 0);
+    assert!(client.get_panel(&escrow_id).is_none());
+}
+
+#[test]
+fn test_panel_ignores_arbitrators_registered_after_creation() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _, _, buyer, _, escrow_id) = setup_panel_dispute(&env);
+    let config: ArbitrationConfig = env.as_contract(&client.address, || {
+        env.storage()
+            .persistent()
+            .get(&DataKey::ArbitrationConfig)
+            .unwrap()
+    });
+
+    let mut late = Vec::new(&env);
+    for _ in 0..20 {
+        let arbitrator = Address::generate(&env);
+        token::StellarAssetClient::new(&env, &config.stake_token).mint(&arbitrator, &100);
+        client.register_arbitrator(&arbitrator, &100);
+        late.push_back(arbitrator);
+    }
+
+    client.dispute_with_panel(&buyer, &escrow_id, &String::from_str(&env, "Late"), &5);
+    let panel = client.get_panel(&escrow_id).unwrap();
+    assert_eq!(panel.members.len(), 5);
+    for member in panel.members.iter() {
+        assert!(!late.contains(&member));
+    }
+}
+
+#[test]
+fn test_panel_dispute_pays_out_once() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _, token, buyer, seller, _) = setup_panel_dispute(&env);
+    let token_client = token::Client::new(&env, &token);
+    let arbitrator = Address::generate(&env);
+    token::StellarAssetClient::new(&env, &token).mint(&buyer, &1_000);
+
+    let milestones = Vec::from_array(
+        &env,
+        [Milestone {
+            description: String::from_str(&env, "Delivery"),
+            amount: 1_000,
+            recipient: seller.clone(),
+            evidence: None,
+            submitted_by: None,
+            fulfilled: false,
+        }],
+    );
+    let escrow_id = client.create_milestone_escrow(
+        &buyer,
+        &Vec::from_array(&env, [buyer.clone(), seller.clone()]),
+        &token,
+        &Vec::from_array(&env, [1_000i128, 0i128]),
+        &milestones,
+        &Some(arbitrator.clone()),
+        &3600u64,
+    );
+    client.deposit(&buyer, &escrow_id, &1_000);
+
+    // The escrow's own arbitrator can't settle a dispute the panel is hearing
+    client.dispute_with_panel(&buyer, &escrow_id, &String::from_str(&env, "Late"), &3);
+    assert_eq!(
+        client.try_resolve_dispute(&arbitrator, &escrow_id, &DisputeResolution::Refund),
+        Err(Ok(EscrowError::InvalidState))
+    );
+
+    let panel = client.get_panel(&escrow_id).unwrap();
+    for member in panel.members.iter() {
+        client.cast_panel_vote(&member, &escrow_id, &PanelVote::Release);
+    }
+    assert_eq!(token_client.balance(&seller), 950);
+    assert_eq!(client.get_escrow(&escrow_id).unwrap().state, EscrowState::Released);
+
+    // Nor pay out again once the panel has settled
+    assert_eq!(
+        client.try_resolve_dispute(&arbitrator, &escrow_id, &DisputeResolution::Refund),
+        Err(Ok(EscrowError::InvalidState))
+    );
+    assert_eq!(
+        client.try_finalize_panel(&escrow_id),
+        Err(Ok(EscrowError::InvalidState))
+    );
+    assert_eq!(token_client.balance(&buyer), 0);
+    assert_eq!(token_client.balance(&seller), 950);
+}
+
+#[test]
+fn test_panel_finalized_after_deadline() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _, token, buyer, seller, escrow_id) = setup_panel_dispute(&env);
+    let token_client = token::Client::new(&env, &token);
+
+    client.dispute_with_panel(&seller, &escrow_id, &String::from_str(&env, "Unpaid"), &3);
+    let panel = client.get_panel(&escrow_id).unwrap();
+    let first = panel.members.get(0).unwrap();
+    let second = panel.members.get(1).unwrap();
+    let absent = panel.members.get(2).unwrap();
+
+    client.cast_panel_vote(&first, &escrow_id, &PanelVote::Release);
+    client.cast_panel_vote(&second, &escrow_id, &PanelVote::Release);
+    assert_eq!(
+        client.try_finalize_panel(&escrow_id),
+        Err(Ok(EscrowError::VotingOpen))
+    );
+
+    env.ledger().with_mut(|li| li.timestamp += 3 * 24 * 60 * 60 + 1);
+    client.finalize_panel(&escrow_id);
+
+    assert_eq!(token_client.balance(&first), 25);
+    assert_eq!(token_client.balance(&second), 25);
+    assert_eq!(token_client.balance(&seller), 950);
+    assert_eq!(token_client.balance(&buyer), 0);
+    assert_eq!(client.get_escrow(&escrow_id).unwrap().state, EscrowState::Released);
+
+    // The absent panelist was slashed in favour of the majority
+    assert_eq!(client.get_arbitrator(&absent).unwrap().stake, 90);
+    assert_eq!(client.get_arbitrator(&first).unwrap().stake, 105);
+    assert_eq!(client.get_arbitrator(&second).unwrap().stake, 105);
+}
+
+#[test]
+fn test_panel_without_quorum_refunds() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _, token, buyer, seller, escrow_id) = setup_panel_dispute(&env);
+    let token_client = token::Client::new(&env, &token);
+
+    client.dispute_with_panel(&seller, &escrow_id, &String::from_str(&env, "Unpaid"), &3);
+    let panel = client.get_panel(&escrow_id).unwrap();
+    let voter = panel.members.get(0).unwrap();
+    let absent = panel.members.get(1).unwrap();
+
+    // One vote out of three is not a majority of the panel
+    client.cast_panel_vote(&voter, &escrow_id, &PanelVote::Release);
+    env.ledger().with_mut(|li| li.timestamp += 3 * 24 * 60 * 60 + 1);
+    client.finalize_panel(&escrow_id);
+
+    // Everything goes back to the buyer with no fee taken
+    assert_eq!(token_client.balance(&buyer), 1_000);
+    assert_eq!(token_client.balance(&seller), 0);
+    assert_eq!(token_client.balance(&voter), 0);
+    assert_eq!(client.get_escrow(&escrow_id).unwrap().state, EscrowState::Refunded);
+
+    // Absentees are still slashed, but the lone voter doesn't collect their stake
+    assert_eq!(client.get_arbitrator(&absent).unwrap().stake, 90);
+    assert_eq!(client.get_arbitrator(&voter).unwrap().stake, 100);
+    assert_eq!(client.get_arbitrator(&voter).unwrap().active_cases, 0);
+}
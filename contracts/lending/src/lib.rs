@@ -5,6 +5,10 @@ use soroban_sdk::{
 };
 
 const BASIS_POINTS: i128 = 10_000;
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+/// Fixed-point scale of the pool borrow index.
+const INDEX_PRECISION: i128 = 1_000_000_000;
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
 
 #[contracttype]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -79,6 +83,8 @@ pub struct Loan {
     pub status: LoanStatus,
     pub terms: LoanTerms,
     pub extension_secs: u64,
+    /// Debt written off when a liquidation exhausted the collateral.
+    pub bad_debt: i128,
 }
 
 #[contracttype]
@@ -90,6 +96,108 @@ pub struct ExtensionRequest {
     pub requested_time: u64,
 }
 
+/// Collateral risk parameters shared by all loans, in basis points.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RiskParams {
+    /// Share of collateral value that counts towards the health factor.
+    pub liquidation_threshold_bps: u32,
+    /// Extra collateral paid to the liquidator on top of the debt repaid.
+    pub liquidation_bonus_bps: u32,
+    /// Maximum share of the debt that can be repaid in one liquidation.
+    pub close_factor_bps: u32,
+}
+
+/// Mirrors the oracle contract's `PriceData`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceData {
+    pub price: i128,
+    pub timestamp: u64,
+    pub round_id: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LoanHealth {
+    pub collateral_value: i128,
+    pub debt_value: i128,
+    pub ltv_bps: i128,
+    /// Scaled by `BASIS_POINTS`; below 10_000 the loan can be liquidated.
+    pub health_factor_bps: i128,
+}
+
+/// Utilization-based borrow rate curve. All rates are annual, in basis points.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RateModel {
+    pub base_rate_bps: u32,
+    /// Rate added linearly up to the optimal utilization.
+    pub slope1_bps: u32,
+    /// Steeper rate added between the optimal utilization and 100%.
+    pub slope2_bps: u32,
+    pub optimal_utilization_bps: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Pool {
+    pub asset: Address,
+    pub rate_model: RateModel,
+    /// Maximum debt value as a share of collateral value when borrowing.
+    pub max_ltv_bps: u32,
+    pub total_shares: i128,
+    /// Liquidity held by the contract and available to borrow or withdraw.
+    pub cash: i128,
+    /// Sum of borrower debts divided by the borrow index.
+    pub total_scaled_debt: i128,
+    pub borrow_index: i128,
+    pub last_update: u64,
+    /// Debt written off when liquidations exhausted a position's collateral.
+    pub bad_debt: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolPosition {
+    pub borrower: Address,
+    pub collateral_token: Address,
+    pub collateral_amount: i128,
+    /// Debt divided by the borrow index at the time it was taken.
+    pub scaled_debt: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RentalStatus {
+    Listed = 1,
+    Cancelled = 2,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RentalTerms {
+    pub payment_token: Address,
+    pub price_per_day: i128,
+    pub max_days: u32,
+    /// Optional creator receiving `royalty_bps` of every rental fee.
+    pub creator: Option<Address>,
+    pub royalty_bps: u32,
+}
+
+/// An NFT held by the contract whose use right can be rented out by the day.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RentalListing {
+    pub listing_id: u64,
+    pub owner: Address,
+    pub nft: Asset,
+    pub terms: RentalTerms,
+    pub status: RentalStatus,
+    pub renter: Option<Address>,
+    pub rented_until: u64,
+}
+
 #[contracttype]
 pub enum DataKey {
     Offer(u64),
@@ -97,6 +205,16 @@ pub enum DataKey {
     Loan(u64),
     LoanCount,
     ExtensionRequest(u64),
+    Admin,
+    Oracle,
+    RiskParams,
+    PriceFeed(Address),
+    Pool(Address),
+    PoolShares(Address, Address),
+    PoolPosition(Address, Address),
+    RentalListing(u64),
+    RentalCount,
+    RentalByNft(Address, u32),
 }
 
 #[contract]
@@ -104,6 +222,36 @@ pub struct LendingContract;
 
 #[contractimpl]
 impl LendingContract {
+    pub fn initialize(env: Env, admin: Address, oracle: Address, risk_params: RiskParams) {
+        if env.storage().instance().has(&DataKey::Admin) {
+            panic!("Already initialized");
+        }
+        admin.require_auth();
+        Self::validate_risk_params(&risk_params);
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::Oracle, &oracle);
+        env.storage()
+            .instance()
+            .set(&DataKey::RiskParams, &risk_params);
+    }
+
+    pub fn set_risk_params(env: Env, admin: Address, risk_params: RiskParams) {
+        Self::require_admin(&env, &admin);
+        Self::validate_risk_params(&risk_params);
+        env.storage()
+            .instance()
+            .set(&DataKey::RiskParams, &risk_params);
+    }
+
+    /// Maps a token contract to the symbol it is quoted under in the oracle.
+    pub fn set_price_feed(env: Env, admin: Address, token: Address, feed: Symbol) {
+        Self::require_admin(&env, &admin);
+        env.storage()
+            .persistent()
+            .set(&DataKey::PriceFeed(token), &feed);
+    }
+
     pub fn create_offer(
         env: Env,
         lender: Address,
@@ -235,6 +383,7 @@ impl LendingContract {
             status: LoanStatus::Active,
             terms: offer.terms.clone(),
             extension_secs: 0,
+            bad_debt: 0,
         };
 
         offer.status = OfferStatus::Accepted;
@@ -281,19 +430,7 @@ impl LendingContract {
         let token_client = token::Client::new(&env, &loan.repayment_token);
         token_client.transfer(&borrower, &loan.lender, &amount);
 
-        let mut remaining = amount;
-        if loan.accrued_interest > 0 {
-            let pay_interest = if remaining < loan.accrued_interest {
-                remaining
-            } else {
-                loan.accrued_interest
-            };
-            loan.accrued_interest -= pay_interest;
-            remaining -= pay_interest;
-        }
-        if remaining > 0 {
-            loan.outstanding_principal -= remaining;
-        }
+        Self::apply_repayment(&mut loan, amount);
 
         if loan.outstanding_principal == 0 && loan.accrued_interest == 0 {
             loan.status = LoanStatus::Repaid;
@@ -348,6 +485,639 @@ impl LendingContract {
             .remove(&DataKey::ExtensionRequest(loan_id));
     }
 
+    /// Liquidates an undercollateralized loan before it is due.
+    ///
+    /// The liquidator repays up to `close_factor_bps` of the debt to the
+    /// lender and receives collateral worth the repaid amount plus the
+    /// liquidation bonus. When the collateral can't cover that, the repayment
+    /// is scaled down to what it can cover and the remaining debt is recorded
+    /// in `bad_debt`. Returns the collateral amount seized.
+    pub fn liquidate_unhealthy(
+        env: Env,
+        liquidator: Address,
+        loan_id: u64,
+        repay_amount: i128,
+    ) -> i128 {
+        liquidator.require_auth();
+
+        if repay_amount <= 0 {
+            panic!("Repayment amount must be greater than zero");
+        }
+
+        let mut loan: Loan = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Loan(loan_id))
+            .unwrap();
+
+        if loan.status != LoanStatus::Active {
+            panic!("Loan is not active");
+        }
+        if loan.loan_asset.asset_type != AssetType::Token {
+            panic!("Only token loans can be liquidated early");
+        }
+
+        Self::accrue_interest(&env, &mut loan);
+
+        let health = Self::compute_health(&env, &loan);
+        if health.health_factor_bps >= BASIS_POINTS {
+            panic!("Loan is healthy");
+        }
+
+        let params = Self::risk_params(&env);
+        let total_due = loan.outstanding_principal + loan.accrued_interest;
+        let max_repay = total_due * params.close_factor_bps as i128 / BASIS_POINTS;
+        if repay_amount > max_repay {
+            panic!("Repayment exceeds close factor");
+        }
+
+        // As in `pool_liquidate`, an underwater loan only charges the liquidator
+        // for the collateral that is left and hands over all of it.
+        let max_repay = Self::repay_for_collateral(
+            &env,
+            &loan.repayment_token,
+            &loan.collateral_asset.contract,
+            loan.collateral_asset.amount,
+        );
+        let repaid = repay_amount.min(max_repay);
+        let seized = if repaid >= max_repay {
+            loan.collateral_asset.amount
+        } else {
+            Self::collateral_to_seize(
+                &env,
+                &loan.repayment_token,
+                &loan.collateral_asset.contract,
+                repaid,
+                loan.collateral_asset.amount,
+            )
+        };
+
+        if repaid > 0 {
+            let token_client = token::Client::new(&env, &loan.repayment_token);
+            token_client.transfer(&liquidator, &loan.lender, &repaid);
+        }
+        if seized > 0 {
+            let collateral_client = token::Client::new(&env, &loan.collateral_asset.contract);
+            collateral_client.transfer(&env.current_contract_address(), &liquidator, &seized);
+        }
+
+        Self::apply_repayment(&mut loan, repaid);
+        loan.collateral_asset.amount -= seized;
+
+        // Debt left without collateral is written off as the lender's loss.
+        if loan.collateral_asset.amount == 0 {
+            loan.bad_debt = loan.outstanding_principal + loan.accrued_interest;
+            loan.outstanding_principal = 0;
+            loan.accrued_interest = 0;
+        }
+
+        if loan.outstanding_principal == 0 && loan.accrued_interest == 0 {
+            loan.status = LoanStatus::Liquidated;
+            if loan.collateral_asset.amount > 0 {
+                Self::transfer_asset(
+                    &env,
+                    &loan.collateral_asset,
+                    &env.current_contract_address(),
+                    &loan.borrower,
+                );
+            }
+            env.storage()
+                .persistent()
+                .remove(&DataKey::ExtensionRequest(loan_id));
+        }
+
+        env.storage().persistent().set(&DataKey::Loan(loan_id), &loan);
+        seized
+    }
+
+    pub fn get_loan_health(env: Env, loan_id: u64) -> LoanHealth {
+        let mut loan: Loan = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Loan(loan_id))
+            .unwrap();
+
+        if loan.status != LoanStatus::Active {
+            panic!("Loan is not active");
+        }
+
+        Self::accrue_interest(&env, &mut loan);
+        Self::compute_health(&env, &loan)
+    }
+
+    pub fn get_risk_params(env: Env) -> RiskParams {
+        Self::risk_params(&env)
+    }
+
+    pub fn create_pool(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        rate_model: RateModel,
+        max_ltv_bps: u32,
+    ) {
+        Self::require_admin(&env, &admin);
+
+        if env
+            .storage()
+            .persistent()
+            .has(&DataKey::Pool(asset.clone()))
+        {
+            panic!("Pool already exists");
+        }
+        if rate_model.optimal_utilization_bps == 0
+            || rate_model.optimal_utilization_bps as i128 >= BASIS_POINTS
+        {
+            panic!("Invalid optimal utilization");
+        }
+        if max_ltv_bps == 0 || max_ltv_bps > Self::risk_params(&env).liquidation_threshold_bps {
+            panic!("Invalid max LTV");
+        }
+
+        let pool = Pool {
+            asset: asset.clone(),
+            rate_model,
+            max_ltv_bps,
+            total_shares: 0,
+            cash: 0,
+            total_scaled_debt: 0,
+            borrow_index: INDEX_PRECISION,
+            last_update: env.ledger().timestamp(),
+            bad_debt: 0,
+        };
+        env.storage().persistent().set(&DataKey::Pool(asset), &pool);
+    }
+
+    /// Deposits liquidity into a pool and returns the shares minted.
+    pub fn pool_deposit(env: Env, lender: Address, asset: Address, amount: i128) -> i128 {
+        lender.require_auth();
+
+        if amount <= 0 {
+            panic!("Deposit amount must be greater than zero");
+        }
+
+        let mut pool = Self::load_pool(&env, &asset);
+        Self::accrue_pool(&env, &mut pool);
+
+        let total_assets = Self::pool_total_assets(&pool);
+        let shares = if pool.total_shares == 0 || total_assets == 0 {
+            amount
+        } else {
+            amount * pool.total_shares / total_assets
+        };
+        if shares <= 0 {
+            panic!("Deposit too small");
+        }
+
+        let token_client = token::Client::new(&env, &asset);
+        token_client.transfer(&lender, &env.current_contract_address(), &amount);
+
+        pool.cash += amount;
+        pool.total_shares += shares;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Pool(asset.clone()), &pool);
+
+        let key = DataKey::PoolShares(asset, lender);
+        let balance: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        env.storage().persistent().set(&key, &(balance + shares));
+
+        shares
+    }
+
+    /// Burns pool shares and returns the underlying amount withdrawn.
+    pub fn pool_withdraw(env: Env, lender: Address, asset: Address, shares: i128) -> i128 {
+        lender.require_auth();
+
+        if shares <= 0 {
+            panic!("Shares must be greater than zero");
+        }
+
+        let key = DataKey::PoolShares(asset.clone(), lender.clone());
+        let balance: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        if shares > balance {
+            panic!("Insufficient shares");
+        }
+
+        let mut pool = Self::load_pool(&env, &asset);
+        Self::accrue_pool(&env, &mut pool);
+
+        let amount = shares * Self::pool_total_assets(&pool) / pool.total_shares;
+        if amount > pool.cash {
+            panic!("Insufficient pool liquidity");
+        }
+
+        pool.cash -= amount;
+        pool.total_shares -= shares;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Pool(asset.clone()), &pool);
+        env.storage().persistent().set(&key, &(balance - shares));
+
+        let token_client = token::Client::new(&env, &asset);
+        token_client.transfer(&env.current_contract_address(), &lender, &amount);
+
+        amount
+    }
+
+    /// Adds token collateral backing the borrower's position in a pool.
+    pub fn pool_add_collateral(
+        env: Env,
+        borrower: Address,
+        asset: Address,
+        collateral_token: Address,
+        amount: i128,
+    ) {
+        borrower.require_auth();
+
+        if amount <= 0 {
+            panic!("Collateral amount must be greater than zero");
+        }
+        Self::load_pool(&env, &asset);
+
+        let key = DataKey::PoolPosition(asset, borrower.clone());
+        let mut position: PoolPosition =
+            env.storage()
+                .persistent()
+                .get(&key)
+                .unwrap_or(PoolPosition {
+                    borrower: borrower.clone(),
+                    collateral_token: collateral_token.clone(),
+                    collateral_amount: 0,
+                    scaled_debt: 0,
+                });
+        if position.collateral_token != collateral_token {
+            panic!("Collateral token mismatch");
+        }
+
+        let token_client = token::Client::new(&env, &collateral_token);
+        token_client.transfer(&borrower, &env.current_contract_address(), &amount);
+
+        position.collateral_amount += amount;
+        env.storage().persistent().set(&key, &position);
+    }
+
+    pub fn pool_remove_collateral(env: Env, borrower: Address, asset: Address, amount: i128) {
+        borrower.require_auth();
+
+        if amount <= 0 {
+            panic!("Collateral amount must be greater than zero");
+        }
+
+        let mut pool = Self::load_pool(&env, &asset);
+        Self::accrue_pool(&env, &mut pool);
+
+        let key = DataKey::PoolPosition(asset.clone(), borrower.clone());
+        let mut position: PoolPosition = env.storage().persistent().get(&key).unwrap();
+        if amount > position.collateral_amount {
+            panic!("Insufficient collateral");
+        }
+
+        position.collateral_amount -= amount;
+        Self::check_borrow_limit(&env, &pool, &position);
+
+        env.storage().persistent().set(&DataKey::Pool(asset), &pool);
+        env.storage().persistent().set(&key, &position);
+
+        let token_client = token::Client::new(&env, &position.collateral_token);
+        token_client.transfer(&env.current_contract_address(), &borrower, &amount);
+    }
+
+    pub fn pool_borrow(env: Env, borrower: Address, asset: Address, amount: i128) {
+        borrower.require_auth();
+
+        if amount <= 0 {
+            panic!("Borrow amount must be greater than zero");
+        }
+
+        let mut pool = Self::load_pool(&env, &asset);
+        Self::accrue_pool(&env, &mut pool);
+
+        if amount > pool.cash {
+            panic!("Insufficient pool liquidity");
+        }
+
+        let key = DataKey::PoolPosition(asset.clone(), borrower.clone());
+        let mut position: PoolPosition = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .expect("No collateral posted");
+
+        // Round the scaled amount up so the pool never loses dust.
+        let scaled = (amount * INDEX_PRECISION + pool.borrow_index - 1) / pool.borrow_index;
+        position.scaled_debt += scaled;
+        pool.total_scaled_debt += scaled;
+        pool.cash -= amount;
+        Self::check_borrow_limit(&env, &pool, &position);
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Pool(asset.clone()), &pool);
+        env.storage().persistent().set(&key, &position);
+
+        let token_client = token::Client::new(&env, &asset);
+        token_client.transfer(&env.current_contract_address(), &borrower, &amount);
+    }
+
+    /// Repays pool debt and returns the amount actually applied.
+    pub fn pool_repay(env: Env, borrower: Address, asset: Address, amount: i128) -> i128 {
+        borrower.require_auth();
+
+        if amount <= 0 {
+            panic!("Repayment amount must be greater than zero");
+        }
+
+        let mut pool = Self::load_pool(&env, &asset);
+        Self::accrue_pool(&env, &mut pool);
+
+        let key = DataKey::PoolPosition(asset.clone(), borrower.clone());
+        let mut position: PoolPosition = env.storage().persistent().get(&key).unwrap();
+
+        let repaid = Self::reduce_pool_debt(&mut pool, &mut position, amount);
+
+        let token_client = token::Client::new(&env, &asset);
+        token_client.transfer(&borrower, &env.current_contract_address(), &repaid);
+
+        env.storage().persistent().set(&DataKey::Pool(asset), &pool);
+        env.storage().persistent().set(&key, &position);
+
+        repaid
+    }
+
+    /// Liquidates an unhealthy pool position, mirroring `liquidate_unhealthy`.
+    pub fn pool_liquidate(
+        env: Env,
+        liquidator: Address,
+        asset: Address,
+        borrower: Address,
+        repay_amount: i128,
+    ) -> i128 {
+        liquidator.require_auth();
+
+        if repay_amount <= 0 {
+            panic!("Repayment amount must be greater than zero");
+        }
+
+        let mut pool = Self::load_pool(&env, &asset);
+        Self::accrue_pool(&env, &mut pool);
+
+        let key = DataKey::PoolPosition(asset.clone(), borrower);
+        let mut position: PoolPosition = env.storage().persistent().get(&key).unwrap();
+
+        let debt = Self::pool_debt_of(&pool, &position);
+        let health = Self::health_for(
+            &env,
+            &position.collateral_token,
+            position.collateral_amount,
+            &asset,
+            debt,
+        );
+        if health.health_factor_bps >= BASIS_POINTS {
+            panic!("Position is healthy");
+        }
+
+        let params = Self::risk_params(&env);
+        if repay_amount > debt * params.close_factor_bps as i128 / BASIS_POINTS {
+            panic!("Repayment exceeds close factor");
+        }
+
+        // An underwater position can't cover the repayment plus bonus: charge
+        // the liquidator only for the collateral that is left, and seize all of it.
+        let max_repay = Self::repay_for_collateral(
+            &env,
+            &asset,
+            &position.collateral_token,
+            position.collateral_amount,
+        );
+        let repaid = Self::reduce_pool_debt(&mut pool, &mut position, repay_amount.min(max_repay));
+        let seized = if repaid >= max_repay {
+            position.collateral_amount
+        } else {
+            Self::collateral_to_seize(
+                &env,
+                &asset,
+                &position.collateral_token,
+                repaid,
+                position.collateral_amount,
+            )
+        };
+        position.collateral_amount -= seized;
+
+        // Debt left without collateral is written off against the pool.
+        if position.collateral_amount == 0 && position.scaled_debt > 0 {
+            let shortfall = Self::pool_debt_of(&pool, &position);
+            pool.total_scaled_debt -= position.scaled_debt;
+            position.scaled_debt = 0;
+            pool.bad_debt += shortfall;
+        }
+
+        if repaid > 0 {
+            let token_client = token::Client::new(&env, &asset);
+            token_client.transfer(&liquidator, &env.current_contract_address(), &repaid);
+        }
+        if seized > 0 {
+            let collateral_client = token::Client::new(&env, &position.collateral_token);
+            collateral_client.transfer(&env.current_contract_address(), &liquidator, &seized);
+        }
+
+        env.storage().persistent().set(&DataKey::Pool(asset), &pool);
+        env.storage().persistent().set(&key, &position);
+
+        seized
+    }
+
+    /// Pool state with interest accrued up to the current ledger time.
+    pub fn get_pool(env: Env, asset: Address) -> Pool {
+        let mut pool = Self::load_pool(&env, &asset);
+        Self::accrue_pool(&env, &mut pool);
+        pool
+    }
+
+    pub fn get_pool_shares(env: Env, asset: Address, lender: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::PoolShares(asset, lender))
+            .unwrap_or(0)
+    }
+
+    pub fn get_pool_position(env: Env, asset: Address, borrower: Address) -> Option<PoolPosition> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::PoolPosition(asset, borrower))
+    }
+
+    pub fn get_pool_debt(env: Env, asset: Address, borrower: Address) -> i128 {
+        let mut pool = Self::load_pool(&env, &asset);
+        Self::accrue_pool(&env, &mut pool);
+        let position: Option<PoolPosition> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::PoolPosition(asset, borrower));
+        match position {
+            Some(position) => Self::pool_debt_of(&pool, &position),
+            None => 0,
+        }
+    }
+
+    /// Current annual borrow rate of a pool, in basis points.
+    pub fn get_borrow_rate(env: Env, asset: Address) -> u32 {
+        let mut pool = Self::load_pool(&env, &asset);
+        Self::accrue_pool(&env, &mut pool);
+        Self::borrow_rate_bps(&pool)
+    }
+
+    /// Escrows an NFT and lists its use right for rent.
+    pub fn list_for_rent(env: Env, owner: Address, nft: Asset, terms: RentalTerms) -> u64 {
+        owner.require_auth();
+
+        if nft.asset_type != AssetType::NFT {
+            panic!("Only NFTs can be rented");
+        }
+        Self::validate_asset(&nft);
+
+        if terms.price_per_day <= 0 {
+            panic!("Price must be greater than zero");
+        }
+        if terms.max_days == 0 {
+            panic!("Max days must be greater than zero");
+        }
+        if terms.royalty_bps as i128 > BASIS_POINTS {
+            panic!("Royalty exceeds 100%");
+        }
+        if terms.creator.is_none() && terms.royalty_bps > 0 {
+            panic!("Royalty requires a creator");
+        }
+
+        Self::transfer_asset(&env, &nft, &owner, &env.current_contract_address());
+
+        let listing_id = Self::next_rental_id(&env);
+        let listing = RentalListing {
+            listing_id,
+            owner,
+            nft,
+            terms,
+            status: RentalStatus::Listed,
+            renter: None,
+            rented_until: 0,
+        };
+
+        env.storage().persistent().set(
+            &DataKey::RentalByNft(listing.nft.contract.clone(), listing.nft.nft_id),
+            &listing_id,
+        );
+        env.storage()
+            .persistent()
+            .set(&DataKey::RentalListing(listing_id), &listing);
+        listing_id
+    }
+
+    /// Rents a listed NFT for `days`, splitting the fee between the owner and
+    /// the creator royalty. Returns the time the use right expires.
+    pub fn rent(env: Env, renter: Address, listing_id: u64, days: u32) -> u64 {
+        renter.require_auth();
+
+        let mut listing: RentalListing = env
+            .storage()
+            .persistent()
+            .get(&DataKey::RentalListing(listing_id))
+            .unwrap();
+
+        if listing.status != RentalStatus::Listed {
+            panic!("Listing is not active");
+        }
+        if days == 0 || days > listing.terms.max_days {
+            panic!("Invalid rental duration");
+        }
+
+        let now = env.ledger().timestamp();
+        if listing.rented_until > now {
+            panic!("NFT is already rented");
+        }
+
+        let total = listing.terms.price_per_day * days as i128;
+        let royalty = total * listing.terms.royalty_bps as i128 / BASIS_POINTS;
+
+        let token_client = token::Client::new(&env, &listing.terms.payment_token);
+        if let Some(creator) = &listing.terms.creator {
+            if royalty > 0 {
+                token_client.transfer(&renter, creator, &royalty);
+            }
+        }
+        token_client.transfer(&renter, &listing.owner, &(total - royalty));
+
+        listing.renter = Some(renter);
+        listing.rented_until = now + days as u64 * SECONDS_PER_DAY;
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::RentalListing(listing_id), &listing);
+        listing.rented_until
+    }
+
+    /// Withdraws an NFT from rental once no rental is running.
+    pub fn cancel_rental_listing(env: Env, owner: Address, listing_id: u64) {
+        owner.require_auth();
+
+        let mut listing: RentalListing = env
+            .storage()
+            .persistent()
+            .get(&DataKey::RentalListing(listing_id))
+            .unwrap();
+
+        if listing.owner != owner {
+            panic!("Only owner can cancel");
+        }
+        if listing.status != RentalStatus::Listed {
+            panic!("Listing is not active");
+        }
+        if listing.rented_until > env.ledger().timestamp() {
+            panic!("NFT is currently rented");
+        }
+
+        listing.status = RentalStatus::Cancelled;
+        listing.renter = None;
+        env.storage()
+            .persistent()
+            .set(&DataKey::RentalListing(listing_id), &listing);
+        env.storage().persistent().remove(&DataKey::RentalByNft(
+            listing.nft.contract.clone(),
+            listing.nft.nft_id,
+        ));
+
+        Self::transfer_asset(
+            &env,
+            &listing.nft,
+            &env.current_contract_address(),
+            &listing.owner,
+        );
+    }
+
+    /// Current user of a rented NFT, or `None` once the rental has expired.
+    pub fn user_of(env: Env, nft_contract: Address, nft_id: u32) -> Option<Address> {
+        let listing_id: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::RentalByNft(nft_contract, nft_id))?;
+        let listing: RentalListing = env
+            .storage()
+            .persistent()
+            .get(&DataKey::RentalListing(listing_id))?;
+
+        if listing.status != RentalStatus::Listed
+            || listing.rented_until <= env.ledger().timestamp()
+        {
+            return None;
+        }
+        listing.renter
+    }
+
+    pub fn get_rental_listing(env: Env, listing_id: u64) -> Option<RentalListing> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::RentalListing(listing_id))
+    }
+
     pub fn request_extension(env: Env, borrower: Address, loan_id: u64, extra_secs: u64) {
         borrower.require_auth();
 
@@ -458,6 +1228,17 @@ impl LendingContract {
         id
     }
 
+    fn next_rental_id(env: &Env) -> u64 {
+        let mut id: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::RentalCount)
+            .unwrap_or(0);
+        id += 1;
+        env.storage().persistent().set(&DataKey::RentalCount, &id);
+        id
+    }
+
     fn validate_asset(asset: &Asset) {
         match asset.asset_type {
             AssetType::Token => {
@@ -493,6 +1274,261 @@ impl LendingContract {
         }
     }
 
+    fn apply_repayment(loan: &mut Loan, amount: i128) {
+        let mut remaining = amount;
+        if loan.accrued_interest > 0 {
+            let pay_interest = if remaining < loan.accrued_interest {
+                remaining
+            } else {
+                loan.accrued_interest
+            };
+            loan.accrued_interest -= pay_interest;
+            remaining -= pay_interest;
+        }
+        if remaining > 0 {
+            loan.outstanding_principal -= remaining;
+        }
+    }
+
+    fn require_admin(env: &Env, admin: &Address) {
+        admin.require_auth();
+        let stored: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .expect("Not initialized");
+        if stored != *admin {
+            panic!("Only admin");
+        }
+    }
+
+    fn validate_risk_params(params: &RiskParams) {
+        if params.liquidation_threshold_bps == 0
+            || params.liquidation_threshold_bps as i128 > BASIS_POINTS
+        {
+            panic!("Invalid liquidation threshold");
+        }
+        if params.close_factor_bps == 0 || params.close_factor_bps as i128 > BASIS_POINTS {
+            panic!("Invalid close factor");
+        }
+    }
+
+    fn risk_params(env: &Env) -> RiskParams {
+        env.storage()
+            .instance()
+            .get(&DataKey::RiskParams)
+            .expect("Not initialized")
+    }
+
+    /// Reads the current oracle price for a token contract.
+    fn asset_price(env: &Env, token: &Address) -> i128 {
+        let oracle: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Oracle)
+            .expect("Not initialized");
+        let feed: Symbol = env
+            .storage()
+            .persistent()
+            .get(&DataKey::PriceFeed(token.clone()))
+            .expect("No price feed for asset");
+
+        let data: PriceData = env.invoke_contract(
+            &oracle,
+            &Symbol::new(env, "get_price"),
+            (feed,).into_val(env),
+        );
+        if data.price <= 0 {
+            panic!("Invalid oracle price");
+        }
+        data.price
+    }
+
+    fn compute_health(env: &Env, loan: &Loan) -> LoanHealth {
+        if loan.collateral_asset.asset_type != AssetType::Token {
+            panic!("Health factor requires token collateral");
+        }
+
+        Self::health_for(
+            env,
+            &loan.collateral_asset.contract,
+            loan.collateral_asset.amount,
+            &loan.repayment_token,
+            loan.outstanding_principal + loan.accrued_interest,
+        )
+    }
+
+    fn health_for(
+        env: &Env,
+        collateral_token: &Address,
+        collateral_amount: i128,
+        debt_token: &Address,
+        debt_amount: i128,
+    ) -> LoanHealth {
+        let params = Self::risk_params(env);
+        let collateral_value = collateral_amount * Self::asset_price(env, collateral_token);
+        let debt_value = debt_amount * Self::asset_price(env, debt_token);
+
+        if debt_value == 0 {
+            return LoanHealth {
+                collateral_value,
+                debt_value,
+                ltv_bps: 0,
+                health_factor_bps: i128::MAX,
+            };
+        }
+
+        let ltv_bps = if collateral_value == 0 {
+            i128::MAX
+        } else {
+            debt_value * BASIS_POINTS / collateral_value
+        };
+        let health_factor_bps =
+            collateral_value * params.liquidation_threshold_bps as i128 / debt_value;
+
+        LoanHealth {
+            collateral_value,
+            debt_value,
+            ltv_bps,
+            health_factor_bps,
+        }
+    }
+
+    /// Collateral owed to a liquidator repaying `repay_amount` of debt,
+    /// including the liquidation bonus and capped at `available`.
+    fn collateral_to_seize(
+        env: &Env,
+        debt_token: &Address,
+        collateral_token: &Address,
+        repay_amount: i128,
+        available: i128,
+    ) -> i128 {
+        let params = Self::risk_params(env);
+        let debt_price = Self::asset_price(env, debt_token);
+        let collateral_price = Self::asset_price(env, collateral_token);
+        let seized =
+            repay_amount * debt_price * (BASIS_POINTS + params.liquidation_bonus_bps as i128)
+                / BASIS_POINTS
+                / collateral_price;
+        if seized > available {
+            available
+        } else {
+            seized
+        }
+    }
+
+    /// Largest repayment whose collateral reward, bonus included, fits in `available`.
+    fn repay_for_collateral(
+        env: &Env,
+        debt_token: &Address,
+        collateral_token: &Address,
+        available: i128,
+    ) -> i128 {
+        let params = Self::risk_params(env);
+        let debt_price = Self::asset_price(env, debt_token);
+        let collateral_price = Self::asset_price(env, collateral_token);
+        available * collateral_price * BASIS_POINTS
+            / (debt_price * (BASIS_POINTS + params.liquidation_bonus_bps as i128))
+    }
+
+    fn load_pool(env: &Env, asset: &Address) -> Pool {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Pool(asset.clone()))
+            .expect("Pool not found")
+    }
+
+    fn pool_total_debt(pool: &Pool) -> i128 {
+        pool.total_scaled_debt * pool.borrow_index / INDEX_PRECISION
+    }
+
+    fn pool_total_assets(pool: &Pool) -> i128 {
+        pool.cash + Self::pool_total_debt(pool)
+    }
+
+    fn pool_debt_of(pool: &Pool, position: &PoolPosition) -> i128 {
+        // Round up so dust never escapes repayment.
+        (position.scaled_debt * pool.borrow_index + INDEX_PRECISION - 1) / INDEX_PRECISION
+    }
+
+    fn utilization_bps(pool: &Pool) -> i128 {
+        let debt = Self::pool_total_debt(pool);
+        let total = pool.cash + debt;
+        if total == 0 {
+            return 0;
+        }
+        debt * BASIS_POINTS / total
+    }
+
+    fn borrow_rate_bps(pool: &Pool) -> u32 {
+        let model = &pool.rate_model;
+        let utilization = Self::utilization_bps(pool);
+        let optimal = model.optimal_utilization_bps as i128;
+
+        let rate = if utilization <= optimal {
+            model.base_rate_bps as i128 + model.slope1_bps as i128 * utilization / optimal
+        } else {
+            model.base_rate_bps as i128
+                + model.slope1_bps as i128
+                + model.slope2_bps as i128 * (utilization - optimal) / (BASIS_POINTS - optimal)
+        };
+        rate as u32
+    }
+
+    /// Grows the borrow index by the interest accrued since the last update.
+    fn accrue_pool(env: &Env, pool: &mut Pool) {
+        let now = env.ledger().timestamp();
+        if now <= pool.last_update {
+            return;
+        }
+        if pool.total_scaled_debt > 0 {
+            let rate_bps = Self::borrow_rate_bps(pool);
+            pool.borrow_index += Self::interest_for(
+                pool.borrow_index,
+                rate_bps,
+                now - pool.last_update,
+                SECONDS_PER_YEAR,
+            );
+        }
+        pool.last_update = now;
+    }
+
+    fn reduce_pool_debt(pool: &mut Pool, position: &mut PoolPosition, amount: i128) -> i128 {
+        let debt = Self::pool_debt_of(pool, position);
+        if debt == 0 {
+            panic!("No outstanding debt");
+        }
+
+        let (repaid, scaled) = if amount >= debt {
+            (debt, position.scaled_debt)
+        } else {
+            (amount, amount * INDEX_PRECISION / pool.borrow_index)
+        };
+
+        position.scaled_debt -= scaled;
+        pool.total_scaled_debt -= scaled;
+        pool.cash += repaid;
+        repaid
+    }
+
+    fn check_borrow_limit(env: &Env, pool: &Pool, position: &PoolPosition) {
+        let debt = Self::pool_debt_of(pool, position);
+        if debt == 0 {
+            return;
+        }
+        let collateral_value =
+            position.collateral_amount * Self::asset_price(env, &position.collateral_token);
+        let debt_value = debt * Self::asset_price(env, &pool.asset);
+        if debt_value * BASIS_POINTS > collateral_value * pool.max_ltv_bps as i128 {
+            panic!("Borrow limit exceeded");
+        }
+    }
+
+    /// Interest on `amount` at `rate_bps` for `elapsed` out of `period` seconds.
+    fn interest_for(amount: i128, rate_bps: u32, elapsed: u64, period: u64) -> i128 {
+        (amount * rate_bps as i128 * elapsed as i128) / period as i128 / BASIS_POINTS
+    }
+
     fn accrue_interest(env: &Env, loan: &mut Loan) {
         let now = env.ledger().timestamp();
         let accrual_end = if now < loan.due_time {
@@ -509,11 +1545,12 @@ impl LendingContract {
             return;
         }
         let delta = accrual_end - loan.last_accrual_time;
-        let interest = (loan.outstanding_principal
-            * loan.terms.interest_bps as i128
-            * delta as i128)
-            / duration as i128
-            / BASIS_POINTS;
+        let interest = Self::interest_for(
+            loan.outstanding_principal,
+            loan.terms.interest_bps,
+            delta,
+            duration,
+        );
         loan.accrued_interest += interest;
         loan.last_accrual_time = accrual_end;
     }
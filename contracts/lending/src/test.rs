@@ -4,7 +4,7 @@ use super::*;
 use soroban_sdk::{
     contract, contractimpl, contracttype,
     testutils::{Address as _, Ledger},
-    token, Address, Env,
+    token, Address, Env, Symbol,
 };
 
 fn setup_token<'a>(
@@ -257,6 +257,428 @@ fn test_nft_loan_repayment_flow() {
     assert_eq!(repayment_client.balance(&lender), 330);
 }
 
+fn setup_risk<'a>(
+    env: &'a Env,
+    client: &LendingContractClient<'a>,
+    loan_token: &Address,
+    collateral_token: &Address,
+) -> (MockOracleClient<'a>, Address) {
+    let admin = Address::generate(env);
+    let oracle_id = env.register_contract(None, MockOracle);
+    let oracle = MockOracleClient::new(env, &oracle_id);
+
+    client.initialize(
+        &admin,
+        &oracle_id,
+        &RiskParams {
+            liquidation_threshold_bps: 8_000,
+            liquidation_bonus_bps: 500,
+            close_factor_bps: 5_000,
+        },
+    );
+    client.set_price_feed(&admin, loan_token, &Symbol::new(env, "USDC"));
+    client.set_price_feed(&admin, collateral_token, &Symbol::new(env, "XLM"));
+
+    oracle.set_price(&Symbol::new(env, "USDC"), &100);
+    oracle.set_price(&Symbol::new(env, "XLM"), &100);
+    (oracle, admin)
+}
+
+#[test]
+fn test_health_factor_and_partial_liquidation() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let lender = Address::generate(&env);
+    let borrower = Address::generate(&env);
+    let liquidator = Address::generate(&env);
+    let loan_admin = Address::generate(&env);
+    let collateral_admin = Address::generate(&env);
+
+    let (loan_token, loan_client, loan_admin_client) = setup_token(&env, &loan_admin);
+    let (collateral_token, collateral_client, collateral_admin_client) =
+        setup_token(&env, &collateral_admin);
+
+    loan_admin_client.mint(&lender, &1_000);
+    loan_admin_client.mint(&liquidator, &1_000);
+    collateral_admin_client.mint(&borrower, &1_500);
+
+    let contract_id = env.register_contract(None, LendingContract);
+    let client = LendingContractClient::new(&env, &contract_id);
+    let (oracle, _admin) = setup_risk(&env, &client, &loan_token, &collateral_token);
+
+    let offer_id = client.create_offer(
+        &lender,
+        &Asset {
+            asset_type: AssetType::Token,
+            contract: loan_token.clone(),
+            amount: 1_000,
+            nft_id: 0,
+        },
+        &Asset {
+            asset_type: AssetType::Token,
+            contract: collateral_token.clone(),
+            amount: 1_500,
+            nft_id: 0,
+        },
+        &loan_token,
+        &1_000,
+        &LoanTerms {
+            duration_secs: 1_000,
+            interest_bps: 0,
+            max_extension_secs: 0,
+        },
+    );
+    let loan_id = client.accept_offer(&borrower, &offer_id);
+
+    let health = client.get_loan_health(&loan_id);
+    assert_eq!(health.ltv_bps, 6_666);
+    assert_eq!(health.health_factor_bps, 12_000);
+    assert!(client
+        .try_liquidate_unhealthy(&liquidator, &loan_id, &100)
+        .is_err());
+
+    // Collateral drops to 0.75: 1_125 * 0.8 = 900 < 1_000
+    oracle.set_price(&Symbol::new(&env, "XLM"), &75);
+    let health = client.get_loan_health(&loan_id);
+    assert_eq!(health.health_factor_bps, 9_000);
+
+    // More than the 50% close factor is refused
+    assert!(client
+        .try_liquidate_unhealthy(&liquidator, &loan_id, &600)
+        .is_err());
+
+    let seized = client.liquidate_unhealthy(&liquidator, &loan_id, &300);
+    // 300 * 100 * 1.05 / 75
+    assert_eq!(seized, 420);
+    assert_eq!(collateral_client.balance(&liquidator), 420);
+    assert_eq!(loan_client.balance(&lender), 300);
+
+    let loan = client.get_loan(&loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::Active);
+    assert_eq!(loan.outstanding_principal, 700);
+    assert_eq!(loan.collateral_asset.amount, 1_080);
+}
+
+#[test]
+fn test_underwater_liquidation_records_bad_debt() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let lender = Address::generate(&env);
+    let borrower = Address::generate(&env);
+    let liquidator = Address::generate(&env);
+    let loan_admin = Address::generate(&env);
+    let collateral_admin = Address::generate(&env);
+
+    let (loan_token, loan_client, loan_admin_client) = setup_token(&env, &loan_admin);
+    let (collateral_token, collateral_client, collateral_admin_client) =
+        setup_token(&env, &collateral_admin);
+
+    loan_admin_client.mint(&lender, &1_000);
+    loan_admin_client.mint(&liquidator, &1_000);
+    collateral_admin_client.mint(&borrower, &1_500);
+
+    let contract_id = env.register_contract(None, LendingContract);
+    let client = LendingContractClient::new(&env, &contract_id);
+    let (oracle, _admin) = setup_risk(&env, &client, &loan_token, &collateral_token);
+
+    let offer_id = client.create_offer(
+        &lender,
+        &Asset {
+            asset_type: AssetType::Token,
+            contract: loan_token.clone(),
+            amount: 1_000,
+            nft_id: 0,
+        },
+        &Asset {
+            asset_type: AssetType::Token,
+            contract: collateral_token.clone(),
+            amount: 1_500,
+            nft_id: 0,
+        },
+        &loan_token,
+        &1_000,
+        &LoanTerms {
+            duration_secs: 1_000,
+            interest_bps: 0,
+            max_extension_secs: 0,
+        },
+    );
+    let loan_id = client.accept_offer(&borrower, &offer_id);
+
+    // Collateral is now worth 450 against 1_000 of debt.
+    oracle.set_price(&Symbol::new(&env, "XLM"), &30);
+    let seized = client.liquidate_unhealthy(&liquidator, &loan_id, &500);
+    assert_eq!(seized, 1_500);
+
+    // Charged only 1_500 * 30 / (100 * 1.05), not the requested 500.
+    assert_eq!(loan_client.balance(&liquidator), 1_000 - 428);
+    assert_eq!(loan_client.balance(&lender), 428);
+    assert_eq!(collateral_client.balance(&liquidator), 1_500);
+
+    let loan = client.get_loan(&loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::Liquidated);
+    assert_eq!(loan.collateral_asset.amount, 0);
+    assert_eq!(loan.outstanding_principal, 0);
+    assert_eq!(loan.bad_debt, 1_000 - 428);
+}
+
+fn setup_pool<'a>(
+    env: &'a Env,
+) -> (
+    LendingContractClient<'a>,
+    MockOracleClient<'a>,
+    Address,
+    token::StellarAssetClient<'a>,
+    token::StellarAssetClient<'a>,
+) {
+    let token_admin = Address::generate(env);
+    let (asset, _, _) = setup_token(env, &token_admin);
+    let (collateral, _, _) = setup_token(env, &token_admin);
+    let asset_admin_client = token::StellarAssetClient::new(env, &asset);
+    let collateral_admin_client = token::StellarAssetClient::new(env, &collateral);
+
+    let contract_id = env.register_contract(None, LendingContract);
+    let client = LendingContractClient::new(env, &contract_id);
+    let (oracle, admin) = setup_risk(env, &client, &asset, &collateral);
+
+    (
+        client,
+        oracle,
+        admin,
+        asset_admin_client,
+        collateral_admin_client,
+    )
+}
+
+#[test]
+fn test_pool_deposit_borrow_and_interest() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let lender = Address::generate(&env);
+    let borrower = Address::generate(&env);
+    let (client, _oracle, admin, asset_admin, collateral_admin) = setup_pool(&env);
+    let asset = asset_admin.address.clone();
+    let collateral = collateral_admin.address.clone();
+    let asset_client = token::Client::new(&env, &asset);
+
+    client.create_pool(
+        &admin,
+        &asset,
+        &RateModel {
+            base_rate_bps: 200,
+            slope1_bps: 1_000,
+            slope2_bps: 5_000,
+            optimal_utilization_bps: 8_000,
+        },
+        &7_500,
+    );
+
+    asset_admin.mint(&lender, &10_000);
+    collateral_admin.mint(&borrower, &10_000);
+
+    assert_eq!(client.pool_deposit(&lender, &asset, &10_000), 10_000);
+    client.pool_add_collateral(&borrower, &asset, &collateral, &10_000);
+
+    // 8_000 exceeds the 75% max LTV on 10_000 of equally priced collateral
+    assert!(client.try_pool_borrow(&borrower, &asset, &8_000).is_err());
+
+    client.pool_borrow(&borrower, &asset, &5_000);
+    assert_eq!(asset_client.balance(&borrower), 5_000);
+    // 50% utilization: 200 + 1_000 * 5_000 / 8_000
+    assert_eq!(client.get_borrow_rate(&asset), 825);
+
+    env.ledger().set_timestamp(SECONDS_PER_YEAR);
+    assert_eq!(client.get_pool_debt(&asset, &borrower), 5_413);
+
+    asset_admin.mint(&borrower, &413);
+    assert_eq!(client.pool_repay(&borrower, &asset, &6_000), 5_413);
+    assert_eq!(client.get_pool_debt(&asset, &borrower), 0);
+
+    assert_eq!(client.pool_withdraw(&lender, &asset, &10_000), 10_413);
+    assert_eq!(asset_client.balance(&lender), 10_413);
+
+    client.pool_remove_collateral(&borrower, &asset, &10_000);
+    assert_eq!(
+        token::Client::new(&env, &collateral).balance(&borrower),
+        10_000
+    );
+}
+
+#[test]
+fn test_pool_liquidation() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let lender = Address::generate(&env);
+    let borrower = Address::generate(&env);
+    let liquidator = Address::generate(&env);
+    let (client, oracle, admin, asset_admin, collateral_admin) = setup_pool(&env);
+    let asset = asset_admin.address.clone();
+    let collateral = collateral_admin.address.clone();
+
+    client.create_pool(
+        &admin,
+        &asset,
+        &RateModel {
+            base_rate_bps: 0,
+            slope1_bps: 0,
+            slope2_bps: 0,
+            optimal_utilization_bps: 8_000,
+        },
+        &7_500,
+    );
+
+    asset_admin.mint(&lender, &10_000);
+    asset_admin.mint(&liquidator, &5_000);
+    collateral_admin.mint(&borrower, &10_000);
+
+    client.pool_deposit(&lender, &asset, &10_000);
+    client.pool_add_collateral(&borrower, &asset, &collateral, &10_000);
+    client.pool_borrow(&borrower, &asset, &5_000);
+
+    assert!(client
+        .try_pool_liquidate(&liquidator, &asset, &borrower, &1_000)
+        .is_err());
+
+    oracle.set_price(&Symbol::new(&env, "XLM"), &50);
+    let seized = client.pool_liquidate(&liquidator, &asset, &borrower, &2_000);
+    // 2_000 * 100 * 1.05 / 50
+    assert_eq!(seized, 4_200);
+
+    let position = client.get_pool_position(&asset, &borrower).unwrap();
+    assert_eq!(position.collateral_amount, 5_800);
+    assert_eq!(client.get_pool_debt(&asset, &borrower), 3_000);
+    assert_eq!(client.get_pool(&asset).cash, 7_000);
+}
+
+#[test]
+fn test_pool_liquidation_underwater_records_bad_debt() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let lender = Address::generate(&env);
+    let borrower = Address::generate(&env);
+    let liquidator = Address::generate(&env);
+    let (client, oracle, admin, asset_admin, collateral_admin) = setup_pool(&env);
+    let asset = asset_admin.address.clone();
+    let collateral = collateral_admin.address.clone();
+
+    client.create_pool(
+        &admin,
+        &asset,
+        &RateModel {
+            base_rate_bps: 0,
+            slope1_bps: 0,
+            slope2_bps: 0,
+            optimal_utilization_bps: 8_000,
+        },
+        &7_500,
+    );
+
+    asset_admin.mint(&lender, &10_000);
+    asset_admin.mint(&liquidator, &5_000);
+    collateral_admin.mint(&borrower, &10_000);
+
+    client.pool_deposit(&lender, &asset, &10_000);
+    client.pool_add_collateral(&borrower, &asset, &collateral, &10_000);
+    client.pool_borrow(&borrower, &asset, &5_000);
+
+    // Collateral is now worth 2_000 against 5_000 of debt.
+    oracle.set_price(&Symbol::new(&env, "XLM"), &20);
+    let seized = client.pool_liquidate(&liquidator, &asset, &borrower, &2_500);
+    assert_eq!(seized, 10_000);
+
+    // Charged only 10_000 * 20 / (100 * 1.05), not the requested 2_500.
+    let asset_client = token::Client::new(&env, &asset);
+    assert_eq!(asset_client.balance(&liquidator), 5_000 - 1_904);
+
+    let position = client.get_pool_position(&asset, &borrower).unwrap();
+    assert_eq!(position.collateral_amount, 0);
+    assert_eq!(client.get_pool_debt(&asset, &borrower), 0);
+
+    let pool = client.get_pool(&asset);
+    assert_eq!(pool.bad_debt, 5_000 - 1_904);
+    assert_eq!(pool.total_scaled_debt, 0);
+    assert_eq!(pool.cash, 5_000 + 1_904);
+}
+
+#[test]
+fn test_nft_rental_flow() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let owner = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let renter = Address::generate(&env);
+    let second_renter = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+
+    let (payment_token, payment_client, payment_admin_client) = setup_token(&env, &token_admin);
+    payment_admin_client.mint(&renter, &1_000);
+    payment_admin_client.mint(&second_renter, &1_000);
+
+    let nft_contract_id = env.register_contract(None, MockNft);
+    let nft_client = MockNftClient::new(&env, &nft_contract_id);
+    let nft_id: u32 = 7;
+    nft_client.mint(&owner, &nft_id);
+
+    let contract_id = env.register_contract(None, LendingContract);
+    let client = LendingContractClient::new(&env, &contract_id);
+
+    let listing_id = client.list_for_rent(
+        &owner,
+        &Asset {
+            asset_type: AssetType::NFT,
+            contract: nft_contract_id.clone(),
+            amount: 1,
+            nft_id,
+        },
+        &RentalTerms {
+            payment_token: payment_token.clone(),
+            price_per_day: 100,
+            max_days: 7,
+            creator: Some(creator.clone()),
+            royalty_bps: 1_000,
+        },
+    );
+    assert_eq!(nft_client.owner_of(&nft_id), contract_id);
+    assert_eq!(client.user_of(&nft_contract_id, &nft_id), None);
+
+    assert!(client.try_rent(&renter, &listing_id, &8).is_err());
+
+    let expires = client.rent(&renter, &listing_id, &3);
+    assert_eq!(expires, 3 * 86_400);
+    assert_eq!(payment_client.balance(&owner), 270);
+    assert_eq!(payment_client.balance(&creator), 30);
+    assert_eq!(
+        client.user_of(&nft_contract_id, &nft_id),
+        Some(renter.clone())
+    );
+
+    // No double renting or withdrawing while the rental runs
+    assert!(client.try_rent(&second_renter, &listing_id, &1).is_err());
+    assert!(client
+        .try_cancel_rental_listing(&owner, &listing_id)
+        .is_err());
+
+    env.ledger().set_timestamp(expires);
+    assert_eq!(client.user_of(&nft_contract_id, &nft_id), None);
+
+    client.rent(&second_renter, &listing_id, &1);
+    assert_eq!(
+        client.user_of(&nft_contract_id, &nft_id),
+        Some(second_renter)
+    );
+
+    env.ledger().set_timestamp(expires + 86_400);
+    client.cancel_rental_listing(&owner, &listing_id);
+    assert_eq!(nft_client.owner_of(&nft_id), owner);
+    assert_eq!(client.user_of(&nft_contract_id, &nft_id), None);
+}
+
 #[contracttype]
 enum MockNftDataKey {
     Owner(u32),
@@ -295,3 +717,22 @@ impl MockNft {
             .unwrap()
     }
 }
+
+#[contract]
+struct MockOracle;
+
+#[contractimpl]
+impl MockOracle {
+    pub fn set_price(env: Env, asset: Symbol, price: i128) {
+        let data = PriceData {
+            price,
+            timestamp: env.ledger().timestamp(),
+            round_id: 1,
+        };
+        env.storage().persistent().set(&asset, &data);
+    }
+
+    pub fn get_price(env: Env, asset: Symbol) -> PriceData {
+        env.storage().persistent().get(&asset).unwrap()
+    }
+}
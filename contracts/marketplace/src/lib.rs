@@ -42,10 +42,18 @@ pub struct Listing {
     pub price: i128,
     pub status: ListingStatus,
     pub created_time: u64,
-    pub creator: Option<Address>, // For royalty payments
+    pub creator: Option<Address>, // Royalty recipient when the collection defines none
     pub royalty_bps: u32, // Royalty in basis points (10000 = 100%)
 }
 
+/// Admin-registered royalty for a collection without `royalty_info`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RoyaltySource {
+    Fixed(Address, u32),    // recipient, royalty in basis points
+    PuzzleFactory(Address), // puzzle_factory holding per-puzzle royalty percentages
+}
+
 #[contracttype]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum OfferStatus {
@@ -68,6 +76,29 @@ pub struct Offer {
     pub expiration_time: Option<u64>,
 }
 
+/// What a standing offer will buy
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum OfferTarget {
+    Collection(Address),  // any token from the collection
+    Puzzle(Address, u32), // any achievement token for the puzzle
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CollectionOffer {
+    pub offer_id: u64,
+    pub buyer: Address,
+    pub target: OfferTarget,
+    pub payment_token: Address,
+    pub price: i128,      // Paid per token
+    pub quantity: u32,
+    pub filled: u32,
+    pub status: OfferStatus,
+    pub created_time: u64,
+    pub expiration_time: u64,
+}
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct CounterOffer {
@@ -104,6 +135,11 @@ pub enum DataKey {
     ListingsByAsset(Address, u32),   // Vec<u64> - listing IDs by asset
     ActiveListings,                  // Vec<u64> - all active listings
     PriceHistory(Address, u32),      // Vec<i128> - price history for an asset
+    CollectionRoyalty(Address),      // RoyaltySource - fallback royalty for a collection
+    RoyaltySplitter(Address),        // Address - royalty_splitter for a collection
+    CollectionOffer(u64),            // CollectionOffer
+    CollectionOfferCount,            // u64
+    CollectionOffersByCollection(Address), // Vec<u64> - standing offer IDs for a collection
 }
 
 #[contracterror]
@@ -204,6 +240,50 @@ impl MarketplaceContract {
         env.storage().instance().set(&DataKey::Config, &new_config);
     }
 
+    /// Register the royalty for a collection whose contract doesn't expose
+    /// `royalty_info` (admin only)
+    pub fn set_collection_royalty(env: Env, collection: Address, source: RoyaltySource) {
+        let config = Self::get_config(env.clone());
+        config.admin.require_auth();
+
+        if let RoyaltySource::Fixed(_, royalty_bps) = source {
+            if royalty_bps > 10000 {
+                panic!("Royalty cannot exceed 100%");
+            }
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::CollectionRoyalty(collection), &source);
+    }
+
+    /// Remove a collection's registered royalty (admin only)
+    pub fn remove_collection_royalty(env: Env, collection: Address) {
+        let config = Self::get_config(env.clone());
+        config.admin.require_auth();
+
+        env.storage()
+            .instance()
+            .remove(&DataKey::CollectionRoyalty(collection));
+    }
+
+    /// Route a collection's royalties through a royalty_splitter contract (admin only)
+    pub fn set_royalty_splitter(env: Env, collection: Address, splitter: Option<Address>) {
+        let config = Self::get_config(env.clone());
+        config.admin.require_auth();
+
+        match splitter {
+            Some(splitter) => env
+                .storage()
+                .instance()
+                .set(&DataKey::RoyaltySplitter(collection), &splitter),
+            None => env
+                .storage()
+                .instance()
+                .remove(&DataKey::RoyaltySplitter(collection)),
+        }
+    }
+
     /// Create a new listing for an NFT or item
     pub fn create_listing(
         env: Env,
@@ -258,19 +338,19 @@ impl MarketplaceContract {
             .set(&DataKey::Listing(listing_id), &listing);
 
         // Update indexes
-        let mut seller_listings = Self::get_listings_by_seller(&env, &seller);
+        let mut seller_listings = Self::get_listings_by_seller(env.clone(), seller.clone());
         seller_listings.push_back(listing_id);
         env.storage()
             .instance()
             .set(&DataKey::ListingsBySeller(seller.clone()), &seller_listings);
 
-        let mut asset_listings = Self::get_listings_by_asset(&env, &asset.contract, &asset.token_id);
+        let mut asset_listings = Self::get_listings_by_asset(env.clone(), asset.contract.clone(), asset.token_id);
         asset_listings.push_back(listing_id);
         env.storage()
             .instance()
             .set(&DataKey::ListingsByAsset(asset.contract.clone(), asset.token_id), &asset_listings);
 
-        let mut active_listings = Self::get_active_listings(&env);
+        let mut active_listings = Self::get_active_listings(env.clone());
         active_listings.push_back(listing_id);
         env.storage()
             .instance()
@@ -303,33 +383,12 @@ impl MarketplaceContract {
             .get(&DataKey::Config)
             .expect("Not initialized");
 
-        // Calculate fees and royalties
-        let (seller_amount, fee_amount, royalty_amount) = Self::calculate_payouts(
-            &env,
-            listing.price,
-            config.fee_bps,
-            listing.royalty_bps,
-        );
-
         // Transfer payment from buyer to contract
         let token_client = token::Client::new(&env, &listing.payment_token);
         token_client.transfer(&buyer, &env.current_contract_address(), &listing.price);
 
         // Distribute payments
-        // 1. Pay seller (after fees and royalties)
-        token_client.transfer(&env.current_contract_address(), &listing.seller, &seller_amount);
-
-        // 2. Pay marketplace fee
-        if fee_amount > 0 {
-            token_client.transfer(&env.current_contract_address(), &config.fee_recipient, &fee_amount);
-        }
-
-        // 3. Pay royalty to creator
-        if royalty_amount > 0 {
-            if let Some(creator) = listing.creator.clone() {
-                token_client.transfer(&env.current_contract_address(), &creator, &royalty_amount);
-            }
-        }
+        Self::distribute_sale(&env, &config, &listing, &listing.seller, listing.price);
 
         // Transfer asset from contract to buyer
         Self::transfer_asset_from_contract(&env, &buyer, &listing.asset);
@@ -408,7 +467,7 @@ impl MarketplaceContract {
             .set(&DataKey::Offer(offer_id), &offer);
 
         // Update indexes
-        let mut listing_offers = Self::get_offers_by_listing(&env, listing_id);
+        let mut listing_offers = Self::get_offers_by_listing(env.clone(), listing_id);
         listing_offers.push_back(offer_id);
         env.storage()
             .instance()
@@ -462,31 +521,8 @@ impl MarketplaceContract {
             .get(&DataKey::Config)
             .expect("Not initialized");
 
-        // Calculate fees and royalties
-        let (seller_amount, fee_amount, royalty_amount) = Self::calculate_payouts(
-            &env,
-            offer.price,
-            config.fee_bps,
-            listing.royalty_bps,
-        );
-
-        let token_client = token::Client::new(&env, &listing.payment_token);
-
         // Distribute payments
-        // 1. Pay seller (after fees and royalties)
-        token_client.transfer(&env.current_contract_address(), &seller, &seller_amount);
-
-        // 2. Pay marketplace fee
-        if fee_amount > 0 {
-            token_client.transfer(&env.current_contract_address(), &config.fee_recipient, &fee_amount);
-        }
-
-        // 3. Pay royalty to creator
-        if royalty_amount > 0 {
-            if let Some(creator) = listing.creator.clone() {
-                token_client.transfer(&env.current_contract_address(), &creator, &royalty_amount);
-            }
-        }
+        Self::distribute_sale(&env, &config, &listing, &seller, offer.price);
 
         // Transfer asset from contract to buyer
         Self::transfer_asset_from_contract(&env, &offer.buyer, &listing.asset);
@@ -615,7 +651,7 @@ impl MarketplaceContract {
             .set(&DataKey::CounterOffer(counter_offer_id), &counter_offer);
 
         // Update indexes
-        let mut offer_counters = Self::get_counter_offers_by_offer(&env, offer_id);
+        let mut offer_counters = Self::get_counter_offers_by_offer(env.clone(), offer_id);
         offer_counters.push_back(counter_offer_id);
         env.storage()
             .instance()
@@ -672,35 +708,16 @@ impl MarketplaceContract {
 
         let token_client = token::Client::new(&env, &listing.payment_token);
 
-        // Refund original offer amount
-        token_client.transfer(&env.current_contract_address(), &buyer, &offer.price);
-
-        // Take new payment amount
+        // The original offer stays escrowed; settle the difference
         let price_difference = counter_offer.price - offer.price;
         if price_difference > 0 {
             token_client.transfer(&buyer, &env.current_contract_address(), &price_difference);
+        } else if price_difference < 0 {
+            token_client.transfer(&env.current_contract_address(), &buyer, &-price_difference);
         }
 
-        // Calculate fees and royalties
-        let (seller_amount, fee_amount, royalty_amount) = Self::calculate_payouts(
-            &env,
-            counter_offer.price,
-            config.fee_bps,
-            listing.royalty_bps,
-        );
-
         // Distribute payments
-        token_client.transfer(&env.current_contract_address(), &counter_offer.seller, &seller_amount);
-
-        if fee_amount > 0 {
-            token_client.transfer(&env.current_contract_address(), &config.fee_recipient, &fee_amount);
-        }
-
-        if royalty_amount > 0 {
-            if let Some(creator) = listing.creator.clone() {
-                token_client.transfer(&env.current_contract_address(), &creator, &royalty_amount);
-            }
-        }
+        Self::distribute_sale(&env, &config, &listing, &counter_offer.seller, counter_offer.price);
 
         // Transfer asset from contract to buyer
         Self::transfer_asset_from_contract(&env, &buyer, &listing.asset);
@@ -798,6 +815,177 @@ impl MarketplaceContract {
             .set(&DataKey::Offer(offer_id), &offer);
     }
 
+    /// Create a standing offer for up to `quantity` tokens matching `target`,
+    /// escrowing `price * quantity` up front
+    pub fn create_collection_offer(
+        env: Env,
+        buyer: Address,
+        target: OfferTarget,
+        payment_token: Address,
+        price: i128,
+        quantity: u32,
+        expiration_time: u64,
+    ) -> u64 {
+        buyer.require_auth();
+
+        if price <= 0 {
+            panic!("Price must be positive");
+        }
+
+        if quantity == 0 {
+            panic!("Quantity must be positive");
+        }
+
+        if expiration_time <= env.ledger().timestamp() {
+            panic!("Expiration time must be in the future");
+        }
+
+        // Generate offer ID
+        let mut offer_id: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::CollectionOfferCount)
+            .unwrap_or(0);
+        offer_id += 1;
+        env.storage().instance().set(&DataKey::CollectionOfferCount, &offer_id);
+
+        let collection = Self::target_collection(&target);
+        let offer = CollectionOffer {
+            offer_id,
+            buyer: buyer.clone(),
+            target,
+            payment_token: payment_token.clone(),
+            price,
+            quantity,
+            filled: 0,
+            status: OfferStatus::Open,
+            created_time: env.ledger().timestamp(),
+            expiration_time,
+        };
+
+        env.storage()
+            .instance()
+            .set(&DataKey::CollectionOffer(offer_id), &offer);
+
+        // Update indexes
+        let mut collection_offers = Self::get_collection_offers(env.clone(), collection.clone());
+        collection_offers.push_back(offer_id);
+        env.storage()
+            .instance()
+            .set(&DataKey::CollectionOffersByCollection(collection), &collection_offers);
+
+        // Transfer payment to contract (escrow)
+        let token_client = token::Client::new(&env, &payment_token);
+        token_client.transfer(&buyer, &env.current_contract_address(), &(price * quantity as i128));
+
+        offer_id
+    }
+
+    /// Sell a matching token into a standing offer without listing it first
+    pub fn fill_collection_offer(env: Env, seller: Address, offer_id: u64, token_id: u32) {
+        seller.require_auth();
+
+        let mut offer: CollectionOffer = env
+            .storage()
+            .instance()
+            .get(&DataKey::CollectionOffer(offer_id))
+            .expect("Offer not found");
+
+        if offer.status != OfferStatus::Open {
+            panic!("Offer is not open");
+        }
+
+        if env.ledger().timestamp() > offer.expiration_time {
+            panic!("Offer has expired");
+        }
+
+        if offer.buyer == seller {
+            panic!("Cannot fill your own offer");
+        }
+
+        let asset = Asset {
+            asset_type: AssetType::NFT,
+            contract: Self::target_collection(&offer.target),
+            token_id,
+        };
+
+        if let OfferTarget::Puzzle(_, puzzle_id) = offer.target {
+            let puzzle_of_args = (token_id,).into_val(&env);
+            let token_puzzle: u32 = env.invoke_contract(
+                &asset.contract,
+                &Symbol::new(&env, "puzzle_of"),
+                puzzle_of_args,
+            );
+            if token_puzzle != puzzle_id {
+                panic!("Token does not match offer");
+            }
+        }
+
+        Self::verify_asset_ownership(&env, &seller, &asset);
+
+        let config: MarketplaceConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Config)
+            .expect("Not initialized");
+
+        // Transfer asset straight from seller to buyer
+        let transfer_args = (seller.clone(), offer.buyer.clone(), token_id).into_val(&env);
+        env.invoke_contract::<()>(
+            &asset.contract,
+            &Symbol::new(&env, "transfer"),
+            transfer_args,
+        );
+
+        // Distribute payments from escrow
+        Self::pay_out_sale(&env, &config, &offer.payment_token, &asset, &seller, offer.price, None);
+
+        offer.filled += 1;
+        if offer.filled == offer.quantity {
+            offer.status = OfferStatus::Accepted;
+            Self::remove_from_collection_offers(&env, &offer);
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::CollectionOffer(offer_id), &offer);
+
+        // Record price in history
+        Self::record_price_history(&env, &asset.contract, &asset.token_id, offer.price);
+    }
+
+    /// Cancel a standing offer, refunding the escrow for unfilled tokens.
+    /// Also how buyers reclaim funds from expired offers.
+    pub fn cancel_collection_offer(env: Env, buyer: Address, offer_id: u64) {
+        buyer.require_auth();
+
+        let mut offer: CollectionOffer = env
+            .storage()
+            .instance()
+            .get(&DataKey::CollectionOffer(offer_id))
+            .expect("Offer not found");
+
+        if offer.buyer != buyer {
+            panic!("Not the offer buyer");
+        }
+
+        if offer.status != OfferStatus::Open {
+            panic!("Offer is not open");
+        }
+
+        // Refund unfilled quantity
+        let refund = offer.price * (offer.quantity - offer.filled) as i128;
+        let token_client = token::Client::new(&env, &offer.payment_token);
+        token_client.transfer(&env.current_contract_address(), &buyer, &refund);
+
+        // Update offer status
+        offer.status = OfferStatus::Cancelled;
+        env.storage()
+            .instance()
+            .set(&DataKey::CollectionOffer(offer_id), &offer);
+
+        Self::remove_from_collection_offers(&env, &offer);
+    }
+
     // ──────────────────────────────────────────────────────────
     // HELPER FUNCTIONS
     // ──────────────────────────────────────────────────────────
@@ -843,15 +1031,154 @@ impl MarketplaceContract {
         _env: &Env,
         price: i128,
         fee_bps: u32,
-        royalty_bps: u32,
+        royalty_amount: i128,
     ) -> (i128, i128, i128) {
         let fee_amount = (price * fee_bps as i128) / 10000;
-        let royalty_amount = (price * royalty_bps as i128) / 10000;
+        let royalty_amount = royalty_amount.clamp(0, price - fee_amount);
         let seller_amount = price - fee_amount - royalty_amount;
 
         (seller_amount, fee_amount, royalty_amount)
     }
 
+    /// Pay out a listing sale. The listing's own creator and royalty only
+    /// apply when the collection defines no royalty.
+    fn distribute_sale(
+        env: &Env,
+        config: &MarketplaceConfig,
+        listing: &Listing,
+        seller: &Address,
+        price: i128,
+    ) {
+        let listed_royalty = listing.creator.clone().map(|creator| (creator, listing.royalty_bps));
+        Self::pay_out_sale(env, config, &listing.payment_token, &listing.asset, seller, price, listed_royalty);
+    }
+
+    /// Pay the seller, marketplace fee and royalty out of a sale price the
+    /// contract already holds
+    fn pay_out_sale(
+        env: &Env,
+        config: &MarketplaceConfig,
+        payment_token: &Address,
+        asset: &Asset,
+        seller: &Address,
+        price: i128,
+        listed_royalty: Option<(Address, u32)>,
+    ) {
+        let royalty = Self::resolve_royalty(env, asset, price).or_else(|| {
+            listed_royalty.map(|(creator, royalty_bps)| (creator, (price * royalty_bps as i128) / 10000))
+        });
+        let royalty_amount = royalty.as_ref().map(|(_, amount)| *amount).unwrap_or(0);
+
+        let (seller_amount, fee_amount, royalty_amount) =
+            Self::calculate_payouts(env, price, config.fee_bps, royalty_amount);
+
+        let token_client = token::Client::new(env, payment_token);
+
+        // 1. Pay seller (after fees and royalties)
+        token_client.transfer(&env.current_contract_address(), seller, &seller_amount);
+
+        // 2. Pay marketplace fee
+        if fee_amount > 0 {
+            token_client.transfer(&env.current_contract_address(), &config.fee_recipient, &fee_amount);
+        }
+
+        // 3. Pay royalty to the recipient, or its collection's splitter
+        if let Some((recipient, _)) = royalty {
+            if royalty_amount > 0 {
+                Self::pay_royalty(env, &token_client, &asset.contract, &recipient, royalty_amount);
+            }
+        }
+    }
+
+    /// Look up the royalty on a sale: the asset contract's own
+    /// `royalty_info(token_id, price)` first, then the admin registry
+    fn resolve_royalty(env: &Env, asset: &Asset, price: i128) -> Option<(Address, i128)> {
+        let royalty_info_args = (asset.token_id, price).into_val(env);
+        if let Ok(Ok(royalty)) = env.try_invoke_contract::<(Address, i128), soroban_sdk::Error>(
+            &asset.contract,
+            &Symbol::new(env, "royalty_info"),
+            royalty_info_args,
+        ) {
+            return Some(royalty);
+        }
+
+        let source: RoyaltySource = env
+            .storage()
+            .instance()
+            .get(&DataKey::CollectionRoyalty(asset.contract.clone()))?;
+
+        match source {
+            RoyaltySource::Fixed(recipient, royalty_bps) => {
+                Some((recipient, (price * royalty_bps as i128) / 10000))
+            }
+            RoyaltySource::PuzzleFactory(factory) => {
+                // Achievement NFTs carry the puzzle the factory prices royalties for
+                let puzzle_of_args = (asset.token_id,).into_val(env);
+                let puzzle_id: u32 = env.invoke_contract(
+                    &asset.contract,
+                    &Symbol::new(env, "puzzle_of"),
+                    puzzle_of_args,
+                );
+                let royalty_info_args = (puzzle_id, price).into_val(env);
+                Some(env.invoke_contract(
+                    &factory,
+                    &Symbol::new(env, "royalty_info"),
+                    royalty_info_args,
+                ))
+            }
+        }
+    }
+
+    /// Pay a royalty to the collection's royalty_splitter when one is set,
+    /// otherwise straight to the recipient. The splitter is paid first and
+    /// then told to split the amount; if it refuses (e.g. below its minimum),
+    /// the tokens stay with it and are split with a later receipt.
+    fn pay_royalty(
+        env: &Env,
+        token_client: &token::Client,
+        collection: &Address,
+        recipient: &Address,
+        amount: i128,
+    ) {
+        let splitter: Option<Address> = env
+            .storage()
+            .instance()
+            .get(&DataKey::RoyaltySplitter(collection.clone()));
+
+        match splitter {
+            Some(splitter) => {
+                token_client.transfer(&env.current_contract_address(), &splitter, &amount);
+                let receive_args = (token_client.address.clone(), amount).into_val(env);
+                let _ = env.try_invoke_contract::<(), soroban_sdk::Error>(
+                    &splitter,
+                    &Symbol::new(env, "receive"),
+                    receive_args,
+                );
+            }
+            None => token_client.transfer(&env.current_contract_address(), recipient, &amount),
+        }
+    }
+
+    /// Remove a standing offer from its collection's open offers
+    fn remove_from_collection_offers(env: &Env, offer: &CollectionOffer) {
+        let collection = Self::target_collection(&offer.target);
+        let mut collection_offers = Self::get_collection_offers(env.clone(), collection.clone());
+        if let Some(index) = collection_offers.first_index_of(offer.offer_id) {
+            collection_offers.remove(index);
+            env.storage()
+                .instance()
+                .set(&DataKey::CollectionOffersByCollection(collection), &collection_offers);
+        }
+    }
+
+    /// Collection a standing offer buys from
+    fn target_collection(target: &OfferTarget) -> Address {
+        match target {
+            OfferTarget::Collection(collection) => collection.clone(),
+            OfferTarget::Puzzle(collection, _) => collection.clone(),
+        }
+    }
+
     /// Record price in history for price discovery
     fn record_price_history(env: &Env, contract: &Address, token_id: &u32, price: i128) {
         let mut history: Vec<i128> = env
@@ -868,7 +1195,7 @@ impl MarketplaceContract {
             let mut new_history = Vec::new(env);
             let start_index = history.len() - 100;
             for i in start_index..history.len() {
-                new_history.push_back(*history.get(i).unwrap());
+                new_history.push_back(history.get(i).unwrap());
             }
             history = new_history;
         }
@@ -880,7 +1207,7 @@ impl MarketplaceContract {
 
     /// Remove listing from active listings
     fn remove_from_active_listings(env: &Env, listing_id: u64) {
-        let mut active_listings = Self::get_active_listings(env);
+        let mut active_listings = Self::get_active_listings(env.clone());
         if let Some(index) = active_listings.first_index_of(listing_id) {
             active_listings.remove(index);
             env.storage()
@@ -891,7 +1218,7 @@ impl MarketplaceContract {
 
     /// Refund all offers on a listing
     fn refund_all_offers(env: &Env, listing_id: u64) {
-        let offers = Self::get_offers_by_listing(env, listing_id);
+        let offers = Self::get_offers_by_listing(env.clone(), listing_id);
         let listing: Listing = env
             .storage()
             .instance()
@@ -915,7 +1242,7 @@ impl MarketplaceContract {
 
     /// Refund other offers (except the accepted one)
     fn refund_other_offers(env: &Env, listing_id: u64, accepted_offer_id: u64) {
-        let offers = Self::get_offers_by_listing(env, listing_id);
+        let offers = Self::get_offers_by_listing(env.clone(), listing_id);
         let listing: Listing = env
             .storage()
             .instance()
@@ -959,43 +1286,43 @@ impl MarketplaceContract {
     }
 
     /// Get all listings by seller
-    pub fn get_listings_by_seller(env: &Env, seller: &Address) -> Vec<u64> {
+    pub fn get_listings_by_seller(env: Env, seller: Address) -> Vec<u64> {
         env.storage()
             .instance()
             .get(&DataKey::ListingsBySeller(seller.clone()))
-            .unwrap_or(Vec::new(env))
+            .unwrap_or(Vec::new(&env))
     }
 
     /// Get all listings for an asset
-    pub fn get_listings_by_asset(env: &Env, contract: &Address, token_id: &u32) -> Vec<u64> {
+    pub fn get_listings_by_asset(env: Env, contract: Address, token_id: u32) -> Vec<u64> {
         env.storage()
             .instance()
-            .get(&DataKey::ListingsByAsset(contract.clone(), *token_id))
-            .unwrap_or(Vec::new(env))
+            .get(&DataKey::ListingsByAsset(contract.clone(), token_id))
+            .unwrap_or(Vec::new(&env))
     }
 
     /// Get all active listings
-    pub fn get_active_listings(env: &Env) -> Vec<u64> {
+    pub fn get_active_listings(env: Env) -> Vec<u64> {
         env.storage()
             .instance()
             .get(&DataKey::ActiveListings)
-            .unwrap_or(Vec::new(env))
+            .unwrap_or(Vec::new(&env))
     }
 
     /// Get all offers for a listing
-    pub fn get_offers_by_listing(env: &Env, listing_id: u64) -> Vec<u64> {
+    pub fn get_offers_by_listing(env: Env, listing_id: u64) -> Vec<u64> {
         env.storage()
             .instance()
             .get(&DataKey::OffersByListing(listing_id))
-            .unwrap_or(Vec::new(env))
+            .unwrap_or(Vec::new(&env))
     }
 
     /// Get all counter offers for an offer
-    pub fn get_counter_offers_by_offer(env: &Env, offer_id: u64) -> Vec<u64> {
+    pub fn get_counter_offers_by_offer(env: Env, offer_id: u64) -> Vec<u64> {
         env.storage()
             .instance()
             .get(&DataKey::CounterOffersByOffer(offer_id))
-            .unwrap_or(Vec::new(env))
+            .unwrap_or(Vec::new(&env))
     }
 
     /// Get price history for an asset
@@ -1013,7 +1340,7 @@ impl MarketplaceContract {
             return None;
         }
 
-        let sum: i128 = history.iter().fold(0i128, |acc, &price| acc + price);
+        let sum: i128 = history.iter().sum();
         Some(sum / history.len() as i128)
     }
 
@@ -1030,7 +1357,7 @@ impl MarketplaceContract {
                 min = price;
             }
         }
-        Some(*min)
+        Some(min)
     }
 
     /// Get maximum price from history
@@ -1046,7 +1373,39 @@ impl MarketplaceContract {
                 max = price;
             }
         }
-        Some(*max)
+        Some(max)
+    }
+
+    /// Get standing offer details
+    pub fn get_collection_offer(env: Env, offer_id: u64) -> Option<CollectionOffer> {
+        env.storage().instance().get(&DataKey::CollectionOffer(offer_id))
+    }
+
+    /// Get standing offers for a collection that haven't been cancelled
+    pub fn get_collection_offers(env: Env, collection: Address) -> Vec<u64> {
+        env.storage()
+            .instance()
+            .get(&DataKey::CollectionOffersByCollection(collection))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Get the royalty registered for a collection
+    pub fn get_collection_royalty(env: Env, collection: Address) -> Option<RoyaltySource> {
+        env.storage()
+            .instance()
+            .get(&DataKey::CollectionRoyalty(collection))
+    }
+
+    /// Get the royalty_splitter a collection's royalties are routed to
+    pub fn get_royalty_splitter(env: Env, collection: Address) -> Option<Address> {
+        env.storage()
+            .instance()
+            .get(&DataKey::RoyaltySplitter(collection))
+    }
+
+    /// Royalty recipient and amount a sale of `asset` at `price` would pay
+    pub fn get_royalty(env: Env, asset: Asset, price: i128) -> Option<(Address, i128)> {
+        Self::resolve_royalty(&env, &asset, price)
     }
 
     /// Get marketplace configuration
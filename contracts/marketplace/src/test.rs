@@ -2,19 +2,20 @@
 
 use super::*;
 use soroban_sdk::{
+    contract, contractimpl, contracttype,
     testutils::{Address as _, Ledger},
-    token, Address, Env, symbol_short,
+    token, Address, Env, Map,
 };
 
-// Helper function to create a mock NFT contract
-fn create_mock_nft(env: &Env) -> Address {
-    // For testing, we'll use a simple mock that tracks ownership
-    // In a real scenario, this would be the actual NFT contract
-    Address::generate(env)
+// Helper function to create a mock NFT contract with `token_id` minted to `owner`
+fn create_mock_nft(env: &Env, owner: &Address, token_id: u32) -> Address {
+    let nft = MockNftClient::new(env, &env.register_contract(None, MockNft));
+    nft.mint(owner, &token_id, &0);
+    nft.address
 }
 
 // Helper function to create a test asset
-fn create_test_asset(env: &Env, nft_contract: Address, token_id: u32) -> Asset {
+fn create_test_asset(_env: &Env, nft_contract: Address, token_id: u32) -> Asset {
     Asset {
         asset_type: AssetType::NFT,
         contract: nft_contract,
@@ -36,7 +37,7 @@ fn test_initialize() {
         &fee_recipient,
         &250, // 2.5% fee
         &3600, // 1 hour min
-        &86400 * 30, // 30 days max
+        &(86400 * 30), // 30 days max
     );
 
     let config = client.get_config();
@@ -53,8 +54,6 @@ fn test_create_listing() {
     // Setup token
     let token_admin = Address::generate(&env);
     let token_contract_id = env.register_stellar_asset_contract_v2(token_admin.clone()).address();
-    let token_client = token::Client::new(&env, &token_contract_id);
-    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract_id);
 
     // Setup marketplace
     let contract_id = env.register_contract(None, MarketplaceContract);
@@ -62,11 +61,11 @@ fn test_create_listing() {
 
     let admin = Address::generate(&env);
     let fee_recipient = Address::generate(&env);
-    client.initialize(&admin, &fee_recipient, &250, &3600, &86400 * 30);
+    client.initialize(&admin, &fee_recipient, &250, &3600, &(86400 * 30));
 
     // Setup seller and NFT
     let seller = Address::generate(&env);
-    let nft_contract = create_mock_nft(&env);
+    let nft_contract = create_mock_nft(&env, &seller, 1);
     let token_id = 1u32;
 
     // Create listing
@@ -91,11 +90,11 @@ fn test_create_listing() {
 
     // Verify listing appears in seller's listings
     let seller_listings = client.get_listings_by_seller(&seller);
-    assert!(seller_listings.contains(&listing_id));
+    assert!(seller_listings.contains(listing_id));
 
     // Verify listing appears in active listings
     let active_listings = client.get_active_listings();
-    assert!(active_listings.contains(&listing_id));
+    assert!(active_listings.contains(listing_id));
 }
 
 #[test]
@@ -115,7 +114,7 @@ fn test_buy_listing() {
 
     let admin = Address::generate(&env);
     let fee_recipient = Address::generate(&env);
-    client.initialize(&admin, &fee_recipient, &250, &3600, &86400 * 30);
+    client.initialize(&admin, &fee_recipient, &250, &3600, &(86400 * 30));
 
     // Setup users
     let seller = Address::generate(&env);
@@ -126,7 +125,7 @@ fn test_buy_listing() {
     token_admin_client.mint(&buyer, &10000);
 
     // Create listing
-    let nft_contract = create_mock_nft(&env);
+    let nft_contract = create_mock_nft(&env, &seller, 1);
     let asset = create_test_asset(&env, nft_contract.clone(), 1u32);
     let listing_id = client.create_listing(
         &seller,
@@ -161,7 +160,7 @@ fn test_buy_listing() {
     // Verify price history
     let history = client.get_price_history(&nft_contract, &1u32);
     assert_eq!(history.len(), 1);
-    assert_eq!(history.get(0).unwrap(), &1000);
+    assert_eq!(history.get(0).unwrap(), 1000);
 }
 
 #[test]
@@ -181,7 +180,7 @@ fn test_create_and_accept_offer() {
 
     let admin = Address::generate(&env);
     let fee_recipient = Address::generate(&env);
-    client.initialize(&admin, &fee_recipient, &250, &3600, &86400 * 30);
+    client.initialize(&admin, &fee_recipient, &250, &3600, &(86400 * 30));
 
     // Setup users
     let seller = Address::generate(&env);
@@ -192,7 +191,7 @@ fn test_create_and_accept_offer() {
     token_admin_client.mint(&buyer, &10000);
 
     // Create listing
-    let nft_contract = create_mock_nft(&env);
+    let nft_contract = create_mock_nft(&env, &seller, 1);
     let asset = create_test_asset(&env, nft_contract.clone(), 1u32);
     let listing_id = client.create_listing(
         &seller,
@@ -257,7 +256,7 @@ fn test_counter_offer() {
 
     let admin = Address::generate(&env);
     let fee_recipient = Address::generate(&env);
-    client.initialize(&admin, &fee_recipient, &250, &3600, &86400 * 30);
+    client.initialize(&admin, &fee_recipient, &250, &3600, &(86400 * 30));
 
     // Setup users
     let seller = Address::generate(&env);
@@ -267,7 +266,7 @@ fn test_counter_offer() {
     token_admin_client.mint(&buyer, &10000);
 
     // Create listing
-    let nft_contract = create_mock_nft(&env);
+    let nft_contract = create_mock_nft(&env, &seller, 1);
     let asset = create_test_asset(&env, nft_contract.clone(), 1u32);
     let listing_id = client.create_listing(
         &seller,
@@ -320,7 +319,7 @@ fn test_cancel_listing() {
 
     let admin = Address::generate(&env);
     let fee_recipient = Address::generate(&env);
-    client.initialize(&admin, &fee_recipient, &250, &3600, &86400 * 30);
+    client.initialize(&admin, &fee_recipient, &250, &3600, &(86400 * 30));
 
     // Setup users
     let seller = Address::generate(&env);
@@ -330,7 +329,7 @@ fn test_cancel_listing() {
     token_admin_client.mint(&buyer, &10000);
 
     // Create listing
-    let nft_contract = create_mock_nft(&env);
+    let nft_contract = create_mock_nft(&env, &seller, 1);
     let asset = create_test_asset(&env, nft_contract.clone(), 1u32);
     let listing_id = client.create_listing(
         &seller,
@@ -382,7 +381,7 @@ fn test_cancel_offer() {
 
     let admin = Address::generate(&env);
     let fee_recipient = Address::generate(&env);
-    client.initialize(&admin, &fee_recipient, &250, &3600, &86400 * 30);
+    client.initialize(&admin, &fee_recipient, &250, &3600, &(86400 * 30));
 
     // Setup users
     let seller = Address::generate(&env);
@@ -392,7 +391,7 @@ fn test_cancel_offer() {
     token_admin_client.mint(&buyer, &10000);
 
     // Create listing
-    let nft_contract = create_mock_nft(&env);
+    let nft_contract = create_mock_nft(&env, &seller, 1);
     let asset = create_test_asset(&env, nft_contract.clone(), 1u32);
     let listing_id = client.create_listing(
         &seller,
@@ -439,7 +438,7 @@ fn test_reject_offer() {
 
     let admin = Address::generate(&env);
     let fee_recipient = Address::generate(&env);
-    client.initialize(&admin, &fee_recipient, &250, &3600, &86400 * 30);
+    client.initialize(&admin, &fee_recipient, &250, &3600, &(86400 * 30));
 
     // Setup users
     let seller = Address::generate(&env);
@@ -449,7 +448,7 @@ fn test_reject_offer() {
     token_admin_client.mint(&buyer, &10000);
 
     // Create listing
-    let nft_contract = create_mock_nft(&env);
+    let nft_contract = create_mock_nft(&env, &seller, 1);
     let asset = create_test_asset(&env, nft_contract.clone(), 1u32);
     let listing_id = client.create_listing(
         &seller,
@@ -491,7 +490,7 @@ fn test_price_discovery() {
 
     let admin = Address::generate(&env);
     let fee_recipient = Address::generate(&env);
-    client.initialize(&admin, &fee_recipient, &250, &3600, &86400 * 30);
+    client.initialize(&admin, &fee_recipient, &250, &3600, &(86400 * 30));
 
     // Setup users
     let seller1 = Address::generate(&env);
@@ -503,7 +502,7 @@ fn test_price_discovery() {
     token_admin_client.mint(&buyer1, &10000);
     token_admin_client.mint(&buyer2, &10000);
 
-    let nft_contract = create_mock_nft(&env);
+    let nft_contract = create_mock_nft(&env, &seller1, 1);
     let asset = create_test_asset(&env, nft_contract.clone(), 1u32);
 
     // Create and sell first listing
@@ -516,6 +515,7 @@ fn test_price_discovery() {
         &0,
     );
     client.buy(&buyer1, &listing_id1);
+    MockNftClient::new(&env, &nft_contract).transfer(&buyer1, &seller2, &1);
 
     // Create and sell second listing (different price)
     let listing_id2 = client.create_listing(
@@ -531,8 +531,8 @@ fn test_price_discovery() {
     // Check price history
     let history = client.get_price_history(&nft_contract, &1u32);
     assert_eq!(history.len(), 2);
-    assert_eq!(history.get(0).unwrap(), &1000);
-    assert_eq!(history.get(1).unwrap(), &1500);
+    assert_eq!(history.get(0).unwrap(), 1000);
+    assert_eq!(history.get(1).unwrap(), 1500);
 
     // Check average price
     let avg_price = client.get_average_price(&nft_contract, &1u32).unwrap();
@@ -564,7 +564,7 @@ fn test_multiple_offers_refund() {
 
     let admin = Address::generate(&env);
     let fee_recipient = Address::generate(&env);
-    client.initialize(&admin, &fee_recipient, &250, &3600, &86400 * 30);
+    client.initialize(&admin, &fee_recipient, &250, &3600, &(86400 * 30));
 
     // Setup users
     let seller = Address::generate(&env);
@@ -578,7 +578,7 @@ fn test_multiple_offers_refund() {
     token_admin_client.mint(&buyer3, &10000);
 
     // Create listing
-    let nft_contract = create_mock_nft(&env);
+    let nft_contract = create_mock_nft(&env, &seller, 1);
     let asset = create_test_asset(&env, nft_contract.clone(), 1u32);
     let listing_id = client.create_listing(
         &seller,
@@ -628,7 +628,7 @@ fn test_update_config() {
     let fee_recipient = Address::generate(&env);
     let new_fee_recipient = Address::generate(&env);
 
-    client.initialize(&admin, &fee_recipient, &250, &3600, &86400 * 30);
+    client.initialize(&admin, &fee_recipient, &250, &3600, &(86400 * 30));
 
     // Update config
     client.update_config(
@@ -643,3 +643,281 @@ fn test_update_config() {
     assert_eq!(config.fee_recipient, new_fee_recipient);
     assert_eq!(config.fee_bps, 300);
 }
+
+// ──────────────────────────────────────────────────────────
+// ROYALTY TESTS
+// ──────────────────────────────────────────────────────────
+
+#[contracttype]
+enum MockKey {
+    Owner(u32),
+    Puzzle(u32),
+    Royalty,
+}
+
+/// NFT that tracks ownership and optionally exposes `royalty_info`
+#[contract]
+pub struct MockNft;
+
+#[contractimpl]
+impl MockNft {
+    pub fn mint(env: Env, to: Address, token_id: u32, puzzle_id: u32) {
+        env.storage().instance().set(&MockKey::Owner(token_id), &to);
+        env.storage().instance().set(&MockKey::Puzzle(token_id), &puzzle_id);
+    }
+
+    pub fn owner_of(env: Env, token_id: u32) -> Address {
+        env.storage().instance().get(&MockKey::Owner(token_id)).unwrap()
+    }
+
+    pub fn transfer(env: Env, from: Address, to: Address, token_id: u32) {
+        from.require_auth();
+        env.storage().instance().set(&MockKey::Owner(token_id), &to);
+    }
+
+    pub fn puzzle_of(env: Env, token_id: u32) -> u32 {
+        env.storage().instance().get(&MockKey::Puzzle(token_id)).unwrap()
+    }
+
+    pub fn set_royalty(env: Env, recipient: Address, royalty_bps: u32) {
+        env.storage().instance().set(&MockKey::Royalty, &(recipient, royalty_bps));
+    }
+
+    pub fn royalty_info(env: Env, _token_id: u32, price: i128) -> (Address, i128) {
+        let (recipient, royalty_bps): (Address, u32) = env
+            .storage()
+            .instance()
+            .get(&MockKey::Royalty)
+            .expect("No royalty");
+        (recipient, price * royalty_bps as i128 / 10000)
+    }
+}
+
+mod puzzle_factory {
+    use super::*;
+
+    /// Puzzle factory exposing per-puzzle royalty percentages
+    #[contract]
+    pub struct MockPuzzleFactory;
+
+    #[contractimpl]
+    impl MockPuzzleFactory {
+        pub fn set_royalty(env: Env, puzzle_id: u32, creator: Address, royalty_bps: u32) {
+            env.storage().instance().set(&MockKey::Puzzle(puzzle_id), &(creator, royalty_bps));
+        }
+
+        pub fn royalty_info(env: Env, puzzle_id: u32, sale_price: i128) -> (Address, i128) {
+            let (creator, royalty_bps): (Address, u32) =
+                env.storage().instance().get(&MockKey::Puzzle(puzzle_id)).unwrap();
+            (creator, sale_price * royalty_bps as i128 / 10000)
+        }
+    }
+}
+use puzzle_factory::{MockPuzzleFactory, MockPuzzleFactoryClient};
+use royalty_splitter::{RoyaltySplitter, RoyaltySplitterClient};
+
+struct RoyaltySetup<'a> {
+    env: Env,
+    client: MarketplaceContractClient<'a>,
+    token: token::Client<'a>,
+    nft: MockNftClient<'a>,
+    seller: Address,
+    buyer: Address,
+}
+
+fn setup_royalty_sale<'a>() -> RoyaltySetup<'a> {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let token_admin = Address::generate(&env);
+    let token_contract_id = env.register_stellar_asset_contract_v2(token_admin.clone()).address();
+    let token = token::Client::new(&env, &token_contract_id);
+
+    let contract_id = env.register_contract(None, MarketplaceContract);
+    let client = MarketplaceContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let fee_recipient = Address::generate(&env);
+    client.initialize(&admin, &fee_recipient, &250, &3600, &(86400 * 30));
+
+    let nft = MockNftClient::new(&env, &env.register_contract(None, MockNft));
+    let seller = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    nft.mint(&seller, &1, &42);
+    token::StellarAssetClient::new(&env, &token_contract_id).mint(&buyer, &10000);
+
+    RoyaltySetup { env, client, token, nft, seller, buyer }
+}
+
+/// Lists token 1 at 1000 with the seller zeroing out the royalty, then buys it
+fn list_and_buy(s: &RoyaltySetup) {
+    let asset = create_test_asset(&s.env, s.nft.address.clone(), 1);
+    let listing_id = s.client.create_listing(&s.seller, &asset, &s.token.address, &1000, &None, &0);
+    s.client.buy(&s.buyer, &listing_id);
+    assert_eq!(s.nft.owner_of(&1), s.buyer);
+}
+
+#[test]
+fn test_royalty_from_asset_contract() {
+    let s = setup_royalty_sale();
+    let creator = Address::generate(&s.env);
+    s.nft.set_royalty(&creator, &1000);
+
+    // Registry entries don't override the collection's own royalty_info
+    s.client.set_collection_royalty(&s.nft.address, &RoyaltySource::Fixed(Address::generate(&s.env), 500));
+
+    list_and_buy(&s);
+
+    // Fee 25, royalty 100 despite the listing's zero royalty
+    assert_eq!(s.token.balance(&creator), 100);
+    assert_eq!(s.token.balance(&s.seller), 875);
+}
+
+#[test]
+fn test_registry_royalty_fallback() {
+    let s = setup_royalty_sale();
+    let creator = Address::generate(&s.env);
+    let asset = create_test_asset(&s.env, s.nft.address.clone(), 1);
+
+    assert_eq!(s.client.get_royalty(&asset, &1000), None);
+    s.client.set_collection_royalty(&s.nft.address, &RoyaltySource::Fixed(creator.clone(), 500));
+    assert_eq!(s.client.get_royalty(&asset, &1000), Some((creator.clone(), 50)));
+
+    list_and_buy(&s);
+
+    assert_eq!(s.token.balance(&creator), 50);
+    assert_eq!(s.token.balance(&s.seller), 925);
+}
+
+#[test]
+fn test_puzzle_factory_royalty() {
+    let s = setup_royalty_sale();
+    let creator = Address::generate(&s.env);
+    let factory = MockPuzzleFactoryClient::new(&s.env, &s.env.register_contract(None, MockPuzzleFactory));
+    factory.set_royalty(&42, &creator, &300);
+
+    s.client.set_collection_royalty(&s.nft.address, &RoyaltySource::PuzzleFactory(factory.address.clone()));
+
+    list_and_buy(&s);
+
+    // Token 1 was minted for puzzle 42, which carries a 3% royalty
+    assert_eq!(s.token.balance(&creator), 30);
+    assert_eq!(s.token.balance(&s.seller), 945);
+}
+
+#[test]
+fn test_royalty_routed_to_splitter() {
+    let s = setup_royalty_sale();
+    let creator = Address::generate(&s.env);
+    let partner = Address::generate(&s.env);
+    let splitter = RoyaltySplitterClient::new(&s.env, &s.env.register_contract(None, RoyaltySplitter));
+    let mut splits = Map::new(&s.env);
+    splits.set(creator.clone(), 6000);
+    splits.set(partner.clone(), 4000);
+    splitter.init(&Address::generate(&s.env), &splits, &100);
+
+    s.nft.set_royalty(&creator, &1000);
+    s.client.set_royalty_splitter(&s.nft.address, &Some(splitter.address.clone()));
+    assert_eq!(s.client.get_royalty_splitter(&s.nft.address), Some(splitter.address.clone()));
+
+    list_and_buy(&s);
+
+    // The splitter holds the 100 royalty and pays it out to its recipients
+    assert_eq!(s.token.balance(&splitter.address), 100);
+    assert_eq!(splitter.get_total_received(&s.token.address), 100);
+    assert_eq!(splitter.withdraw(&creator, &s.token.address), 60);
+    assert_eq!(splitter.withdraw(&partner, &s.token.address), 40);
+    assert_eq!(s.token.balance(&creator), 60);
+    assert_eq!(s.token.balance(&partner), 40);
+
+    // A royalty below the splitter's minimum is kept until it can be split
+    let listing_id = s.client.create_listing(
+        &s.buyer,
+        &create_test_asset(&s.env, s.nft.address.clone(), 1),
+        &s.token.address,
+        &500,
+        &None,
+        &0,
+    );
+    s.client.buy(&s.seller, &listing_id);
+    assert_eq!(s.token.balance(&splitter.address), 50);
+    assert_eq!(splitter.get_total_received(&s.token.address), 100);
+    assert_eq!(s.token.balance(&creator), 60);
+    assert!(splitter.try_receive(&s.token.address, &50).is_err());
+}
+
+// ──────────────────────────────────────────────────────────
+// COLLECTION OFFER TESTS
+// ──────────────────────────────────────────────────────────
+
+#[test]
+fn test_collection_offer_partial_fills() {
+    let s = setup_royalty_sale();
+    let creator = Address::generate(&s.env);
+    s.nft.set_royalty(&creator, &1000);
+    s.nft.mint(&s.seller, &2, &7);
+
+    let target = OfferTarget::Collection(s.nft.address.clone());
+    let offer_id = s.client.create_collection_offer(&s.buyer, &target, &s.token.address, &200, &3, &1000);
+
+    // Escrowed for all three tokens
+    assert_eq!(s.token.balance(&s.client.address), 600);
+    assert_eq!(s.client.get_collection_offers(&s.nft.address).len(), 1);
+
+    s.client.fill_collection_offer(&s.seller, &offer_id, &1);
+    s.client.fill_collection_offer(&s.seller, &offer_id, &2);
+
+    assert_eq!(s.nft.owner_of(&1), s.buyer);
+    assert_eq!(s.nft.owner_of(&2), s.buyer);
+
+    // Per fill: fee 5, royalty 20, seller 175
+    assert_eq!(s.token.balance(&s.seller), 350);
+    assert_eq!(s.token.balance(&creator), 40);
+
+    let offer = s.client.get_collection_offer(&offer_id).unwrap();
+    assert_eq!(offer.filled, 2);
+    assert_eq!(offer.status, OfferStatus::Open);
+
+    // Cancelling refunds the unfilled token
+    s.client.cancel_collection_offer(&s.buyer, &offer_id);
+    assert_eq!(s.token.balance(&s.buyer), 10000 - 400);
+    assert_eq!(s.token.balance(&s.client.address), 0);
+    assert_eq!(s.client.get_collection_offer(&offer_id).unwrap().status, OfferStatus::Cancelled);
+    assert_eq!(s.client.get_collection_offers(&s.nft.address).len(), 0);
+}
+
+#[test]
+fn test_puzzle_offer_matches_trait() {
+    let s = setup_royalty_sale();
+    s.nft.mint(&s.seller, &2, &7);
+
+    let target = OfferTarget::Puzzle(s.nft.address.clone(), 42);
+    let offer_id = s.client.create_collection_offer(&s.buyer, &target, &s.token.address, &500, &1, &1000);
+
+    // Token 2 is for puzzle 7
+    assert!(s.client.try_fill_collection_offer(&s.seller, &offer_id, &2).is_err());
+
+    assert_eq!(s.client.get_collection_offers(&s.nft.address).len(), 1);
+    s.client.fill_collection_offer(&s.seller, &offer_id, &1);
+    assert_eq!(s.nft.owner_of(&1), s.buyer);
+    assert_eq!(s.client.get_collection_offer(&offer_id).unwrap().status, OfferStatus::Accepted);
+    assert_eq!(s.client.get_collection_offers(&s.nft.address).len(), 0);
+
+    // Fully filled
+    assert!(s.client.try_fill_collection_offer(&s.seller, &offer_id, &2).is_err());
+}
+
+#[test]
+fn test_collection_offer_expiry() {
+    let s = setup_royalty_sale();
+
+    let target = OfferTarget::Collection(s.nft.address.clone());
+    let offer_id = s.client.create_collection_offer(&s.buyer, &target, &s.token.address, &200, &2, &1000);
+
+    s.env.ledger().with_mut(|li| li.timestamp = 1001);
+    assert!(s.client.try_fill_collection_offer(&s.seller, &offer_id, &1).is_err());
+    assert_eq!(s.nft.owner_of(&1), s.seller);
+
+    // Buyer reclaims the full escrow
+    s.client.cancel_collection_offer(&s.buyer, &offer_id);
+    assert_eq!(s.token.balance(&s.buyer), 10000);
+}
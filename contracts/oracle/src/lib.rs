@@ -1,11 +1,17 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, xdr::ToXdr, Address, BytesN, Env, Map, Symbol, Vec};
+use soroban_sdk::{
+    contract, contractimpl, symbol_short, xdr::ToXdr, Address, BytesN, Env, Map, Symbol, Vec,
+};
 
 mod storage;
 mod types;
 
 use storage::Storage;
-use types::{Config, OracleError, PriceData};
+use types::{AssetConfig, Config, OracleError, PriceData, RoundInfo, SignerReport, SignerStats};
+
+/// Maximum clock drift tolerated for submitted timestamps, in seconds.
+const MAX_FUTURE_DRIFT: u64 = 60;
+const BPS_DENOMINATOR: i128 = 10_000;
 
 #[contract]
 pub struct OracleContract;
@@ -56,17 +62,8 @@ impl OracleContract {
             return Err(OracleError::Disputed);
         }
 
-        let current_time = env.ledger().timestamp();
-        // Allow 60s future drift
-        if timestamp > current_time + 60 {
-            // allow slight drift
-        }
-
-        if let Some(last_data) = Storage::get_price(&env, &asset) {
-            if timestamp <= last_data.timestamp {
-                return Err(OracleError::StalePrice);
-            }
-        }
+        Self::check_timestamp(&env, timestamp)?;
+        Self::check_round(&env, &asset, price, timestamp)?;
 
         let signers_map = Storage::get_signers(&env)?;
 
@@ -106,22 +103,247 @@ impl OracleContract {
             timestamp,
             round_id,
         };
-        Storage::set_price(&env, &asset, &new_data);
+        Self::record_round(&env, &asset, &new_data, used_signers.keys(), Vec::new(&env));
 
         Ok(())
     }
 
+    /// Submits one signer's own observation for a round.
+    ///
+    /// Once `threshold` reports are in, the median is computed and reports
+    /// further than the asset's outlier band from it are discarded. The round
+    /// is finalized with the median of the remaining reports as soon as at
+    /// least `threshold` of them agree; otherwise it stays open for more. If
+    /// that median fails the asset's deviation or staleness guard, the round
+    /// is rejected and closed rather than reverting the submission.
+    pub fn submit_report(
+        env: Env,
+        signer: BytesN<32>,
+        asset: Symbol,
+        price: i128,
+        timestamp: u64,
+        round_id: u64,
+        signature: BytesN<64>,
+    ) -> Result<(), OracleError> {
+        let config = Storage::get_config(&env)?;
+
+        if config.paused {
+            return Err(OracleError::Paused);
+        }
+
+        if Storage::get_dispute(&env, &asset) {
+            return Err(OracleError::Disputed);
+        }
+
+        let signers_map = Storage::get_signers(&env)?;
+        if !signers_map.contains_key(signer.clone()) {
+            return Err(OracleError::Unauthorized);
+        }
+
+        Self::check_timestamp(&env, timestamp)?;
+
+        if let Some(last_data) = Storage::get_price(&env, &asset) {
+            if round_id <= last_data.round_id {
+                return Err(OracleError::RoundClosed);
+            }
+        }
+
+        if Storage::is_round_rejected(&env, &asset, round_id) {
+            return Err(OracleError::RoundClosed);
+        }
+
+        let payload_tuple = (
+            asset.clone(),
+            price,
+            timestamp,
+            round_id,
+            env.current_contract_address(),
+        );
+        let payload_bytes = payload_tuple.to_xdr(&env);
+        env.crypto()
+            .ed25519_verify(&signer, &payload_bytes, &signature);
+
+        let mut reports = Storage::get_reports(&env, &asset, round_id);
+        for report in reports.iter() {
+            if report.signer == signer {
+                return Err(OracleError::DuplicateReport);
+            }
+        }
+
+        let mut stats = Storage::get_signer_stats(&env, &signer);
+        stats.reports += 1;
+        stats.last_round = round_id;
+        Storage::set_signer_stats(&env, &signer, &stats);
+
+        reports.push_back(SignerReport {
+            signer,
+            price,
+            timestamp,
+        });
+
+        if reports.len() < config.threshold {
+            Storage::set_reports(&env, &asset, round_id, &reports);
+            Storage::add_pending_round(&env, &asset, round_id);
+            return Ok(());
+        }
+
+        let mut prices = Vec::new(&env);
+        for report in reports.iter() {
+            prices.push_back(report.price);
+        }
+        let median = Self::median(&env, &prices);
+
+        let band_bps = Storage::get_asset_config(&env, &asset).outlier_band_bps;
+        let mut accepted_prices = Vec::new(&env);
+        let mut contributors = Vec::new(&env);
+        let mut outliers = Vec::new(&env);
+        let mut latest_timestamp = 0;
+        for report in reports.iter() {
+            if band_bps > 0 && Self::deviation_bps(report.price, median) > band_bps as i128 {
+                outliers.push_back(report.signer);
+                continue;
+            }
+            accepted_prices.push_back(report.price);
+            contributors.push_back(report.signer);
+            latest_timestamp = latest_timestamp.max(report.timestamp);
+        }
+
+        if contributors.len() < config.threshold {
+            Storage::set_reports(&env, &asset, round_id, &reports);
+            Storage::add_pending_round(&env, &asset, round_id);
+            return Ok(());
+        }
+
+        let aggregated_price = Self::median(&env, &accepted_prices);
+
+        // A median that fails the round guards closes the round instead of
+        // reverting, so the reports that reached quorum still count.
+        if let Err(err) = Self::check_round(&env, &asset, aggregated_price, latest_timestamp) {
+            Storage::set_round_rejected(&env, &asset, round_id);
+            Storage::remove_reports(&env, &asset, round_id);
+            Storage::clear_pending_rounds(&env, &asset, round_id, false);
+            env.events().publish(
+                (symbol_short!("rnd_rej"), asset),
+                (round_id, aggregated_price, err as u32),
+            );
+            return Ok(());
+        }
+
+        let new_data = PriceData {
+            price: aggregated_price,
+            timestamp: latest_timestamp,
+            round_id,
+        };
+        Self::record_round(&env, &asset, &new_data, contributors, outliers);
+        Storage::remove_reports(&env, &asset, round_id);
+        // Rounds skipped by this one can no longer finalize.
+        Storage::clear_pending_rounds(&env, &asset, round_id, true);
+
+        Ok(())
+    }
+
+    /// Reports received so far for a round that has not been finalized yet.
+    pub fn get_pending_reports(env: Env, asset: Symbol, round_id: u64) -> Vec<SignerReport> {
+        Storage::get_reports(&env, &asset, round_id)
+    }
+
+    /// Whether a round reached quorum but was closed by the asset's guards.
+    pub fn is_round_rejected(env: Env, asset: Symbol, round_id: u64) -> bool {
+        Storage::is_round_rejected(&env, &asset, round_id)
+    }
+
+    /// Finalized round details, including which signers contributed.
+    pub fn get_round(env: Env, asset: Symbol, round_id: u64) -> Result<RoundInfo, OracleError> {
+        Storage::get_round(&env, &asset, round_id).ok_or(OracleError::NotFound)
+    }
+
+    pub fn get_signer_stats(env: Env, signer: BytesN<32>) -> SignerStats {
+        Storage::get_signer_stats(&env, &signer)
+    }
+
     pub fn get_price(env: Env, asset: Symbol) -> Result<PriceData, OracleError> {
         let data = Storage::get_price(&env, &asset).ok_or(OracleError::NotFound)?;
 
+        let max_staleness = Storage::get_asset_config(&env, &asset).max_staleness;
         let current_time = env.ledger().timestamp();
-        if current_time > data.timestamp + 3600 {
+        if current_time.saturating_sub(data.timestamp) > max_staleness {
             return Err(OracleError::StalePrice);
         }
 
         Ok(data)
     }
 
+    /// Returns the recent rounds for an asset, oldest first.
+    pub fn get_price_history(env: Env, asset: Symbol) -> Vec<PriceData> {
+        Storage::get_history(&env, &asset)
+    }
+
+    /// Time-weighted average price over the last `window` seconds.
+    ///
+    /// Each round's price is weighted by how long it stayed the latest value.
+    /// If the retained history is shorter than the window, only the covered
+    /// span is averaged.
+    pub fn get_twap(env: Env, asset: Symbol, window: u64) -> Result<i128, OracleError> {
+        if window == 0 {
+            return Err(OracleError::InvalidWindow);
+        }
+
+        // Also enforces the staleness guard on the most recent round.
+        let latest = Self::get_price(env.clone(), asset.clone())?;
+        let history = Storage::get_history(&env, &asset);
+
+        let now = env.ledger().timestamp();
+        let start = now.saturating_sub(window);
+        let mut end = now;
+        let mut weighted_sum: i128 = 0;
+        let mut total_weight: u64 = 0;
+
+        for i in (0..history.len()).rev() {
+            let point = history.get_unchecked(i);
+            let from = point.timestamp.clamp(start, now);
+            if end > from {
+                weighted_sum += point.price * (end - from) as i128;
+                total_weight += end - from;
+            }
+            if point.timestamp <= start {
+                break;
+            }
+            end = from;
+        }
+
+        if total_weight == 0 {
+            return Ok(latest.price);
+        }
+
+        Ok(weighted_sum / total_weight as i128)
+    }
+
+    pub fn set_asset_config(
+        env: Env,
+        asset: Symbol,
+        max_staleness: u64,
+        max_deviation_bps: u32,
+        outlier_band_bps: u32,
+    ) -> Result<(), OracleError> {
+        let config = Storage::get_config(&env)?;
+        config.admin.require_auth();
+
+        Storage::set_asset_config(
+            &env,
+            &asset,
+            &AssetConfig {
+                max_staleness,
+                max_deviation_bps,
+                outlier_band_bps,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn get_asset_config(env: Env, asset: Symbol) -> AssetConfig {
+        Storage::get_asset_config(&env, &asset)
+    }
+
     pub fn add_signer(env: Env, signer: BytesN<32>) -> Result<(), OracleError> {
         let config = Storage::get_config(&env)?;
         config.admin.require_auth();
@@ -181,6 +403,94 @@ impl OracleContract {
         Storage::set_dispute(&env, &asset, false);
         Ok(())
     }
+
+    fn check_timestamp(env: &Env, timestamp: u64) -> Result<(), OracleError> {
+        if timestamp > env.ledger().timestamp() + MAX_FUTURE_DRIFT {
+            return Err(OracleError::FutureTimestamp);
+        }
+        Ok(())
+    }
+
+    /// Checks a candidate round against the last recorded one.
+    fn check_round(
+        env: &Env,
+        asset: &Symbol,
+        price: i128,
+        timestamp: u64,
+    ) -> Result<(), OracleError> {
+        let asset_config = Storage::get_asset_config(env, asset);
+        if let Some(last_data) = Storage::get_price(env, asset) {
+            if timestamp <= last_data.timestamp {
+                return Err(OracleError::StalePrice);
+            }
+            if asset_config.max_deviation_bps > 0
+                && last_data.price != 0
+                && Self::deviation_bps(price, last_data.price)
+                    > asset_config.max_deviation_bps as i128
+            {
+                return Err(OracleError::DeviationExceeded);
+            }
+        }
+        Ok(())
+    }
+
+    fn record_round(
+        env: &Env,
+        asset: &Symbol,
+        data: &PriceData,
+        contributors: Vec<BytesN<32>>,
+        outliers: Vec<BytesN<32>>,
+    ) {
+        Storage::set_price(env, asset, data);
+        Storage::push_history(env, asset, data);
+
+        for signer in contributors.iter() {
+            let mut stats = Storage::get_signer_stats(env, &signer);
+            stats.accepted += 1;
+            Storage::set_signer_stats(env, &signer, &stats);
+        }
+        for signer in outliers.iter() {
+            let mut stats = Storage::get_signer_stats(env, &signer);
+            stats.rejected += 1;
+            Storage::set_signer_stats(env, &signer, &stats);
+        }
+
+        let round = RoundInfo {
+            round_id: data.round_id,
+            price: data.price,
+            timestamp: data.timestamp,
+            contributors,
+            outliers,
+        };
+        Storage::set_round(env, asset, &round);
+    }
+
+    fn deviation_bps(price: i128, reference: i128) -> i128 {
+        if reference == 0 {
+            return 0;
+        }
+        (price - reference).abs() * BPS_DENOMINATOR / reference.abs()
+    }
+
+    fn median(env: &Env, values: &Vec<i128>) -> i128 {
+        // Insertion sort; report sets are bounded by the signer count.
+        let mut sorted: Vec<i128> = Vec::new(env);
+        for value in values.iter() {
+            let mut index = sorted.len();
+            while index > 0 && sorted.get_unchecked(index - 1) > value {
+                index -= 1;
+            }
+            sorted.insert(index, value);
+        }
+
+        let len = sorted.len();
+        let mid = len / 2;
+        if len.is_multiple_of(2) {
+            (sorted.get_unchecked(mid - 1) + sorted.get_unchecked(mid)) / 2
+        } else {
+            sorted.get_unchecked(mid)
+        }
+    }
 }
 
 mod test;
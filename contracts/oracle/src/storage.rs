@@ -1,5 +1,12 @@
-use crate::types::{Config, OracleError, PriceData};
-use soroban_sdk::{symbol_short, BytesN, Env, Map, Symbol};
+use crate::types::{
+    AssetConfig, Config, OracleError, PriceData, RoundInfo, SignerReport, SignerStats,
+};
+use soroban_sdk::{symbol_short, BytesN, Env, Map, Symbol, Vec};
+
+/// Number of recent rounds retained per asset.
+pub const HISTORY_CAPACITY: u32 = 32;
+/// Staleness window used for assets without an explicit config.
+pub const DEFAULT_MAX_STALENESS: u64 = 3600;
 
 pub struct Storage;
 
@@ -42,6 +49,134 @@ impl Storage {
         env.storage().persistent().get(asset)
     }
 
+    /// Appends a round to the asset's history, evicting the oldest once full.
+    pub fn push_history(env: &Env, asset: &Symbol, data: &PriceData) {
+        let mut history = Self::get_history(env, asset);
+        history.push_back(data.clone());
+        while history.len() > HISTORY_CAPACITY {
+            history.pop_front();
+        }
+        env.storage()
+            .persistent()
+            .set(&(symbol_short!("history"), asset.clone()), &history);
+    }
+
+    pub fn get_history(env: &Env, asset: &Symbol) -> Vec<PriceData> {
+        env.storage()
+            .persistent()
+            .get(&(symbol_short!("history"), asset.clone()))
+            .unwrap_or(Vec::new(env))
+    }
+
+    pub fn set_asset_config(env: &Env, asset: &Symbol, config: &AssetConfig) {
+        env.storage()
+            .persistent()
+            .set(&(symbol_short!("asset_cfg"), asset.clone()), config);
+    }
+
+    pub fn get_asset_config(env: &Env, asset: &Symbol) -> AssetConfig {
+        env.storage()
+            .persistent()
+            .get(&(symbol_short!("asset_cfg"), asset.clone()))
+            .unwrap_or(AssetConfig {
+                max_staleness: DEFAULT_MAX_STALENESS,
+                max_deviation_bps: 0,
+                outlier_band_bps: 0,
+            })
+    }
+
+    pub fn set_reports(env: &Env, asset: &Symbol, round_id: u64, reports: &Vec<SignerReport>) {
+        env.storage().persistent().set(
+            &(symbol_short!("reports"), asset.clone(), round_id),
+            reports,
+        );
+    }
+
+    pub fn get_reports(env: &Env, asset: &Symbol, round_id: u64) -> Vec<SignerReport> {
+        env.storage()
+            .persistent()
+            .get(&(symbol_short!("reports"), asset.clone(), round_id))
+            .unwrap_or(Vec::new(env))
+    }
+
+    pub fn remove_reports(env: &Env, asset: &Symbol, round_id: u64) {
+        env.storage()
+            .persistent()
+            .remove(&(symbol_short!("reports"), asset.clone(), round_id));
+    }
+
+    /// Rounds with reports pending, oldest first.
+    pub fn get_pending_rounds(env: &Env, asset: &Symbol) -> Vec<u64> {
+        env.storage()
+            .persistent()
+            .get(&(symbol_short!("pending"), asset.clone()))
+            .unwrap_or(Vec::new(env))
+    }
+
+    pub fn add_pending_round(env: &Env, asset: &Symbol, round_id: u64) {
+        let mut rounds = Self::get_pending_rounds(env, asset);
+        if rounds.contains(round_id) {
+            return;
+        }
+        rounds.push_back(round_id);
+        env.storage()
+            .persistent()
+            .set(&(symbol_short!("pending"), asset.clone()), &rounds);
+    }
+
+    /// Drops the pending reports of `round_id`, or of every round up to it.
+    pub fn clear_pending_rounds(env: &Env, asset: &Symbol, round_id: u64, older: bool) {
+        let mut remaining = Vec::new(env);
+        for pending in Self::get_pending_rounds(env, asset).iter() {
+            if pending == round_id || (older && pending < round_id) {
+                Self::remove_reports(env, asset, pending);
+            } else {
+                remaining.push_back(pending);
+            }
+        }
+        env.storage()
+            .persistent()
+            .set(&(symbol_short!("pending"), asset.clone()), &remaining);
+    }
+
+    pub fn set_round_rejected(env: &Env, asset: &Symbol, round_id: u64) {
+        env.storage()
+            .persistent()
+            .set(&(symbol_short!("rejected"), asset.clone(), round_id), &true);
+    }
+
+    pub fn is_round_rejected(env: &Env, asset: &Symbol, round_id: u64) -> bool {
+        env.storage()
+            .persistent()
+            .has(&(symbol_short!("rejected"), asset.clone(), round_id))
+    }
+
+    pub fn set_round(env: &Env, asset: &Symbol, round: &RoundInfo) {
+        env.storage().persistent().set(
+            &(symbol_short!("round"), asset.clone(), round.round_id),
+            round,
+        );
+    }
+
+    pub fn get_round(env: &Env, asset: &Symbol, round_id: u64) -> Option<RoundInfo> {
+        env.storage()
+            .persistent()
+            .get(&(symbol_short!("round"), asset.clone(), round_id))
+    }
+
+    pub fn set_signer_stats(env: &Env, signer: &BytesN<32>, stats: &SignerStats) {
+        env.storage()
+            .persistent()
+            .set(&(symbol_short!("sgn_stats"), signer.clone()), stats);
+    }
+
+    pub fn get_signer_stats(env: &Env, signer: &BytesN<32>) -> SignerStats {
+        env.storage()
+            .persistent()
+            .get(&(symbol_short!("sgn_stats"), signer.clone()))
+            .unwrap_or_default()
+    }
+
     pub fn set_dispute(env: &Env, asset: &Symbol, is_disputed: bool) {
         env.storage()
             .persistent()
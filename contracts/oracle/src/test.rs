@@ -126,3 +126,322 @@ fn test_oracle_flow() {
     );
     assert!(res_insufficient.is_err());
 }
+
+fn setup_oracle(
+    env: &Env,
+    signer_count: usize,
+    threshold: u32,
+) -> (OracleContractClient<'_>, std::vec::Vec<SigningKey>) {
+    let contract_id = env.register_contract(None, OracleContract);
+    let client = OracleContractClient::new(env, &contract_id);
+    let admin = Address::generate(env);
+
+    let mut csprng = OsRng;
+    let mut keys = std::vec::Vec::new();
+    let mut signers_vec = Vec::new(env);
+    for _ in 0..signer_count {
+        let key = SigningKey::generate(&mut csprng);
+        signers_vec.push_back(BytesN::from_array(
+            env,
+            &VerifyingKey::from(&key).to_bytes(),
+        ));
+        keys.push(key);
+    }
+
+    client.initialize(&admin, &signers_vec, &threshold);
+    (client, keys)
+}
+
+fn sign_price(
+    env: &Env,
+    client: &OracleContractClient,
+    keys: &[SigningKey],
+    asset: &Symbol,
+    price: i128,
+    timestamp: u64,
+    round_id: u64,
+) -> Vec<(BytesN<32>, BytesN<64>)> {
+    let payload = (
+        asset.clone(),
+        price,
+        timestamp,
+        round_id,
+        client.address.clone(),
+    )
+        .to_xdr(env);
+    let mut bytes = std::vec![0u8; payload.len() as usize];
+    payload.copy_into_slice(&mut bytes);
+
+    let mut signatures = Vec::new(env);
+    for key in keys {
+        signatures.push_back((
+            BytesN::from_array(env, &VerifyingKey::from(key).to_bytes()),
+            BytesN::from_array(env, &key.sign(&bytes).to_bytes()),
+        ));
+    }
+    signatures
+}
+
+fn submit(
+    env: &Env,
+    client: &OracleContractClient,
+    keys: &[SigningKey],
+    asset: &Symbol,
+    price: i128,
+    timestamp: u64,
+    round_id: u64,
+) {
+    let signatures = sign_price(env, client, keys, asset, price, timestamp, round_id);
+    client.submit_price(asset, &price, &timestamp, &round_id, &signatures);
+}
+
+#[test]
+fn test_future_timestamp_rejected() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    let (client, keys) = setup_oracle(&env, 2, 2);
+    let asset = Symbol::new(&env, "XLM");
+
+    // Within the allowed drift
+    submit(&env, &client, &keys, &asset, 100, 1060, 1);
+
+    let signatures = sign_price(&env, &client, &keys, &asset, 100, 1200, 2);
+    let res = client.try_submit_price(&asset, &100, &1200, &2, &signatures);
+    assert_eq!(res, Err(Ok(OracleError::FutureTimestamp)));
+}
+
+#[test]
+fn test_history_ring_buffer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 100_000);
+
+    let (client, keys) = setup_oracle(&env, 1, 1);
+    let asset = Symbol::new(&env, "XLM");
+
+    for round in 1..=(storage::HISTORY_CAPACITY as u64 + 5) {
+        submit(&env, &client, &keys, &asset, round as i128, round, round);
+    }
+
+    let history = client.get_price_history(&asset);
+    assert_eq!(history.len(), storage::HISTORY_CAPACITY);
+    assert_eq!(history.first().unwrap().round_id, 6);
+    assert_eq!(
+        history.last().unwrap().round_id,
+        storage::HISTORY_CAPACITY as u64 + 5
+    );
+}
+
+#[test]
+fn test_twap() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    let (client, keys) = setup_oracle(&env, 1, 1);
+    let asset = Symbol::new(&env, "XLM");
+
+    submit(&env, &client, &keys, &asset, 100, 1000, 1);
+    env.ledger().with_mut(|li| li.timestamp = 1300);
+    submit(&env, &client, &keys, &asset, 200, 1300, 2);
+    env.ledger().with_mut(|li| li.timestamp = 1400);
+
+    // 300s at 100 and 100s at 200
+    assert_eq!(client.get_twap(&asset, &400), 125);
+    // Only the last 100s, all at 200
+    assert_eq!(client.get_twap(&asset, &100), 200);
+    // Window longer than history only averages the covered span
+    assert_eq!(client.get_twap(&asset, &10_000), 125);
+
+    assert_eq!(
+        client.try_get_twap(&asset, &0),
+        Err(Ok(OracleError::InvalidWindow))
+    );
+}
+
+#[test]
+fn test_asset_config_guards() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    let (client, keys) = setup_oracle(&env, 1, 1);
+    let asset = Symbol::new(&env, "XLM");
+
+    // 5% max move per round, 60s staleness
+    client.set_asset_config(&asset, &60, &500, &0);
+    submit(&env, &client, &keys, &asset, 1000, 1000, 1);
+
+    let signatures = sign_price(&env, &client, &keys, &asset, 1100, 1010, 2);
+    let res = client.try_submit_price(&asset, &1100, &1010, &2, &signatures);
+    assert_eq!(res, Err(Ok(OracleError::DeviationExceeded)));
+
+    submit(&env, &client, &keys, &asset, 1050, 1010, 2);
+    assert_eq!(client.get_price(&asset).price, 1050);
+
+    env.ledger().with_mut(|li| li.timestamp = 1071);
+    assert_eq!(
+        client.try_get_price(&asset),
+        Err(Ok(OracleError::StalePrice))
+    );
+
+    // u64::MAX means prices never go stale
+    client.set_asset_config(&asset, &u64::MAX, &500, &0);
+    assert_eq!(client.get_price(&asset).price, 1050);
+}
+
+fn report(
+    env: &Env,
+    client: &OracleContractClient,
+    key: &SigningKey,
+    asset: &Symbol,
+    price: i128,
+    timestamp: u64,
+    round_id: u64,
+) -> Result<(), OracleError> {
+    let signed = sign_price(
+        env,
+        client,
+        core::slice::from_ref(key),
+        asset,
+        price,
+        timestamp,
+        round_id,
+    );
+    let (signer, signature) = signed.get(0).unwrap();
+    match client.try_submit_report(&signer, asset, &price, &timestamp, &round_id, &signature) {
+        Ok(_) => Ok(()),
+        Err(Ok(err)) => Err(err),
+        Err(Err(_)) => panic!("unexpected host error"),
+    }
+}
+
+fn public_key(env: &Env, key: &SigningKey) -> BytesN<32> {
+    BytesN::from_array(env, &VerifyingKey::from(key).to_bytes())
+}
+
+#[test]
+fn test_report_median_aggregation() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    let (client, keys) = setup_oracle(&env, 3, 3);
+    let asset = Symbol::new(&env, "XLM");
+
+    report(&env, &client, &keys[0], &asset, 100, 990, 1).unwrap();
+    report(&env, &client, &keys[1], &asset, 104, 995, 1).unwrap();
+    assert_eq!(client.get_pending_reports(&asset, &1).len(), 2);
+    assert_eq!(client.try_get_price(&asset), Err(Ok(OracleError::NotFound)));
+
+    report(&env, &client, &keys[2], &asset, 101, 1000, 1).unwrap();
+
+    let data = client.get_price(&asset);
+    assert_eq!(data.price, 101);
+    assert_eq!(data.timestamp, 1000);
+    assert_eq!(data.round_id, 1);
+
+    let round = client.get_round(&asset, &1);
+    assert_eq!(round.contributors.len(), 3);
+    assert!(round.outliers.is_empty());
+    assert!(client.get_pending_reports(&asset, &1).is_empty());
+
+    // Late reports for a finalized round are refused
+    assert_eq!(
+        report(&env, &client, &keys[0], &asset, 100, 1000, 1),
+        Err(OracleError::RoundClosed)
+    );
+}
+
+#[test]
+fn test_report_outlier_rejection() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    let (client, keys) = setup_oracle(&env, 4, 3);
+    let asset = Symbol::new(&env, "XLM");
+    client.set_asset_config(&asset, &3600, &0, &200);
+
+    report(&env, &client, &keys[0], &asset, 100, 1000, 1).unwrap();
+    assert_eq!(
+        report(&env, &client, &keys[0], &asset, 100, 1000, 1),
+        Err(OracleError::DuplicateReport)
+    );
+    report(&env, &client, &keys[1], &asset, 101, 1000, 1).unwrap();
+    // Far outside the 2% band: threshold reached but only two reports agree
+    report(&env, &client, &keys[2], &asset, 500, 1000, 1).unwrap();
+    assert_eq!(client.try_get_price(&asset), Err(Ok(OracleError::NotFound)));
+
+    report(&env, &client, &keys[3], &asset, 102, 1000, 1).unwrap();
+    assert_eq!(client.get_price(&asset).price, 101);
+
+    let round = client.get_round(&asset, &1);
+    assert_eq!(round.contributors.len(), 3);
+    assert_eq!(
+        round.outliers,
+        Vec::from_array(&env, [public_key(&env, &keys[2])])
+    );
+
+    let honest = client.get_signer_stats(&public_key(&env, &keys[0]));
+    assert_eq!(honest.reports, 1);
+    assert_eq!(honest.accepted, 1);
+    assert_eq!(honest.rejected, 0);
+
+    let outlier = client.get_signer_stats(&public_key(&env, &keys[2]));
+    assert_eq!(outlier.accepted, 0);
+    assert_eq!(outlier.rejected, 1);
+}
+
+#[test]
+fn test_report_round_rejected_by_deviation_guard() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    let (client, keys) = setup_oracle(&env, 2, 2);
+    let asset = Symbol::new(&env, "XLM");
+    client.set_asset_config(&asset, &3600, &500, &0);
+
+    report(&env, &client, &keys[0], &asset, 100, 990, 1).unwrap();
+    report(&env, &client, &keys[1], &asset, 100, 990, 1).unwrap();
+
+    // A report left pending for round 2, which round 4 will skip
+    report(&env, &client, &keys[0], &asset, 101, 995, 2).unwrap();
+
+    // Round 3 reaches quorum at a 20% jump: rejected, not reverted
+    report(&env, &client, &keys[0], &asset, 120, 1000, 3).unwrap();
+    report(&env, &client, &keys[1], &asset, 120, 1000, 3).unwrap();
+    assert!(client.is_round_rejected(&asset, &3));
+    assert!(client.get_pending_reports(&asset, &3).is_empty());
+    assert_eq!(client.get_price(&asset).round_id, 1);
+    assert_eq!(
+        report(&env, &client, &keys[0], &asset, 101, 1000, 3),
+        Err(OracleError::RoundClosed)
+    );
+    assert_eq!(client.get_pending_reports(&asset, &2).len(), 1);
+
+    // Finalizing round 4 clears the skipped round's reports
+    report(&env, &client, &keys[0], &asset, 102, 1000, 4).unwrap();
+    report(&env, &client, &keys[1], &asset, 102, 1000, 4).unwrap();
+    assert_eq!(client.get_price(&asset).round_id, 4);
+    assert!(client.get_pending_reports(&asset, &2).is_empty());
+}
+
+#[test]
+fn test_report_from_unknown_signer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    let (client, _keys) = setup_oracle(&env, 2, 2);
+    let asset = Symbol::new(&env, "XLM");
+    let stranger = SigningKey::generate(&mut OsRng);
+
+    assert_eq!(
+        report(&env, &client, &stranger, &asset, 100, 1000, 1),
+        Err(OracleError::Unauthorized)
+    );
+}
@@ -1,4 +1,4 @@
-use soroban_sdk::{contracterror, contracttype, Address};
+use soroban_sdk::{contracterror, contracttype, Address, BytesN, Vec};
 
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
@@ -13,6 +13,11 @@ pub enum OracleError {
     InvalidSignature = 7,
     Paused = 8,
     Disputed = 9,
+    FutureTimestamp = 10,
+    DeviationExceeded = 11,
+    InvalidWindow = 12,
+    RoundClosed = 13,
+    DuplicateReport = 14,
 }
 
 #[contracttype]
@@ -30,3 +35,46 @@ pub struct Config {
     pub threshold: u32,
     pub paused: bool,
 }
+
+/// Per-asset guards applied when prices are submitted and read.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AssetConfig {
+    /// Maximum age in seconds before a price is considered stale.
+    pub max_staleness: u64,
+    /// Maximum move between consecutive rounds in basis points (0 disables the check).
+    pub max_deviation_bps: u32,
+    /// Maximum distance of a signer report from the round median in basis
+    /// points (0 disables outlier rejection).
+    pub outlier_band_bps: u32,
+}
+
+/// A single signer's observation for an open round.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignerReport {
+    pub signer: BytesN<32>,
+    pub price: i128,
+    pub timestamp: u64,
+}
+
+/// A finalized round and the signers whose reports made it up.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoundInfo {
+    pub round_id: u64,
+    pub price: i128,
+    pub timestamp: u64,
+    pub contributors: Vec<BytesN<32>>,
+    pub outliers: Vec<BytesN<32>>,
+}
+
+/// Running reliability counters for a signer.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SignerStats {
+    pub reports: u64,
+    pub accepted: u64,
+    pub rejected: u64,
+    pub last_round: u64,
+}
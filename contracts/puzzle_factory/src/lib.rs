@@ -547,6 +547,13 @@ impl PuzzleFactory {
             .expect("puzzle not found")
     }
 
+    /// Royalty owed on a sale of this puzzle's NFTs: (creator, amount)
+    pub fn royalty_info(env: Env, puzzle_id: u32, sale_price: i128) -> (Address, i128) {
+        let puzzle = Self::get_puzzle(env.clone(), puzzle_id);
+        let amount = Self::calculate_royalty_amount(&env, sale_price, puzzle.metadata.royalty_percentage);
+        (puzzle.metadata.creator, amount)
+    }
+
     pub fn get_puzzles_by_category(env: Env, category: PuzzleCategory) -> Vec<u32> {
         env.storage()
             .instance()
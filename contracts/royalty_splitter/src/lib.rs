@@ -1,7 +1,7 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contracterror, contractimpl, contracttype, Address, Env, Map,
+    contract, contracterror, contractimpl, contracttype, token, Address, Env, Map,
 };
 
 const BPS_DENOMINATOR: u32 = 10_000;
@@ -22,8 +22,11 @@ pub enum DataKey {
     Admin,
     Initialized,
     Splits,
-    Balances,
-    TotalReceived,
+    // Per-token entries live in persistent storage: any caller can add a
+    // token, so they must not grow the instance entry every call loads
+    Balances(Address),      // Map<Address, i128> owed to each recipient, per token
+    TotalReceived(Address), // i128, per token
+    Accounted(Address),     // i128 held for recipients, per token
     MinThreshold,
 }
 
@@ -55,48 +58,55 @@ impl RoyaltySplitter {
 
         env.storage().instance().set(&DataKey::Admin, &admin);
         env.storage().instance().set(&DataKey::Splits, &splits);
-        env.storage().instance().set(&DataKey::Balances, &Map::<Address, i128>::new(&env));
-        env.storage().instance().set(&DataKey::TotalReceived, &0i128);
         env.storage().instance().set(&DataKey::MinThreshold, &min_threshold);
         env.storage().instance().set(&DataKey::Initialized, &true);
     }
 
     /* ================= RECEIVE FUNDS ================= */
 
-    pub fn distribute(env: Env, amount: i128) {
+    /// Split `amount` of `token` between the recipients. The tokens must
+    /// already have been sent to the splitter; only funds not yet credited
+    /// can be split, so anyone may call this.
+    pub fn receive(env: Env, token: Address, amount: i128) {
         Self::require_init(&env);
 
         let min = env.storage().instance().get::<_, i128>(&DataKey::MinThreshold).unwrap();
-        if amount < min {
+        if amount <= 0 || amount < min {
             panic!("Below minimum threshold");
         }
 
+        let accounted = Self::accounted(&env, &token);
+        let held = token::Client::new(&env, &token).balance(&env.current_contract_address());
+        if held - accounted < amount {
+            panic!("Amount not received");
+        }
+
         let splits = env.storage().instance().get::<_, Map<Address, u32>>(&DataKey::Splits).unwrap();
-        let mut balances =
-            env.storage().instance().get::<_, Map<Address, i128>>(&DataKey::Balances).unwrap();
+        let mut balances = Self::balances(&env, &token);
 
+        // Rounding dust stays uncredited and is split with the next receipt
+        let mut credited: i128 = 0;
         for (recipient, pct) in splits.iter() {
             let share = amount * pct as i128 / BPS_DENOMINATOR as i128;
             let current = balances.get(recipient.clone()).unwrap_or(0);
             balances.set(recipient, current + share);
+            credited += share;
         }
 
-        let total = env
-            .storage()
-            .instance()
-            .get::<_, i128>(&DataKey::TotalReceived)
-            .unwrap();
-        env.storage().instance().set(&DataKey::TotalReceived, &(total + amount));
-        env.storage().instance().set(&DataKey::Balances, &balances);
+        let total = Self::get_total_received(env.clone(), token.clone());
+        env.storage().persistent().set(&DataKey::TotalReceived(token.clone()), &(total + amount));
+        env.storage().persistent().set(&DataKey::Accounted(token.clone()), &(accounted + credited));
+        env.storage().persistent().set(&DataKey::Balances(token), &balances);
     }
 
     /* ================= WITHDRAW ================= */
 
-    pub fn withdraw(env: Env, recipient: Address) {
+    /// Pay out the recipient's whole balance of `token`
+    pub fn withdraw(env: Env, recipient: Address, token: Address) -> i128 {
         Self::require_init(&env);
+        recipient.require_auth();
 
-        let mut balances =
-            env.storage().instance().get::<_, Map<Address, i128>>(&DataKey::Balances).unwrap();
+        let mut balances = Self::balances(&env, &token);
         let amount = balances.get(recipient.clone()).unwrap_or(0);
 
         if amount <= 0 {
@@ -104,10 +114,18 @@ impl RoyaltySplitter {
         }
 
         balances.set(recipient.clone(), 0);
-        env.storage().instance().set(&DataKey::Balances, &balances);
+        Self::pay_out(&env, &token, &recipient, balances, amount);
+        amount
+    }
 
-        recipient.require_auth();
-        // actual transfer handled by calling contract or off-chain escrow
+    /* ================= VIEWS ================= */
+
+    pub fn get_balance(env: Env, recipient: Address, token: Address) -> i128 {
+        Self::balances(&env, &token).get(recipient).unwrap_or(0)
+    }
+
+    pub fn get_total_received(env: Env, token: Address) -> i128 {
+        env.storage().persistent().get(&DataKey::TotalReceived(token)).unwrap_or(0)
     }
 
     /* ================= UPDATE SPLITS ================= */
@@ -129,23 +147,43 @@ impl RoyaltySplitter {
 
     /* ================= EMERGENCY ================= */
 
-    pub fn emergency_withdraw(env: Env, recipient: Address, amount: i128) {
+    /// Push part of a recipient's balance of `token` out to them (admin only)
+    pub fn emergency_withdraw(env: Env, token: Address, recipient: Address, amount: i128) {
         Self::require_admin(&env);
 
-        let mut balances =
-            env.storage().instance().get::<_, Map<Address, i128>>(&DataKey::Balances).unwrap();
+        let mut balances = Self::balances(&env, &token);
         let current = balances.get(recipient.clone()).unwrap_or(0);
 
-        if amount > current {
+        if amount <= 0 || amount > current {
             panic!("Insufficient funds");
         }
 
-        balances.set(recipient, current - amount);
-        env.storage().instance().set(&DataKey::Balances, &balances);
+        balances.set(recipient.clone(), current - amount);
+        Self::pay_out(&env, &token, &recipient, balances, amount);
     }
 
     /* ================= HELPERS ================= */
 
+    fn balances(env: &Env, token: &Address) -> Map<Address, i128> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Balances(token.clone()))
+            .unwrap_or(Map::new(env))
+    }
+
+    fn accounted(env: &Env, token: &Address) -> i128 {
+        env.storage().persistent().get(&DataKey::Accounted(token.clone())).unwrap_or(0)
+    }
+
+    /// Store the debited balances and transfer `amount` to the recipient
+    fn pay_out(env: &Env, token: &Address, recipient: &Address, balances: Map<Address, i128>, amount: i128) {
+        let accounted = Self::accounted(env, token);
+        env.storage().persistent().set(&DataKey::Accounted(token.clone()), &(accounted - amount));
+        env.storage().persistent().set(&DataKey::Balances(token.clone()), &balances);
+
+        token::Client::new(env, token).transfer(&env.current_contract_address(), recipient, &amount);
+    }
+
     fn require_init(env: &Env) {
         if !env
             .storage()
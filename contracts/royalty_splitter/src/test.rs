@@ -3,27 +3,103 @@
 use super::*;
 use soroban_sdk::{Env, Map, Address};
 use soroban_sdk::testutils::Address as _;
+use soroban_sdk::token::{Client as TokenClient, StellarAssetClient};
 
 #[test]
 fn test_distribution_and_withdraw() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let contract_id = env.register(RoyaltySplitter, ());
+    let contract_id = env.register_contract(None, RoyaltySplitter);
+    let client = RoyaltySplitterClient::new(&env, &contract_id);
+    let token = env.register_stellar_asset_contract_v2(Address::generate(&env)).address();
+    let token_client = TokenClient::new(&env, &token);
 
     let admin = Address::generate(&env);
     let alice = Address::generate(&env);
     let bob = Address::generate(&env);
+    let payer = Address::generate(&env);
 
     let mut splits = Map::new(&env);
     splits.set(alice.clone(), 6000);
     splits.set(bob.clone(), 4000);
+    client.init(&admin, &splits, &10);
+
+    // Only tokens actually held can be split
+    assert!(client.try_receive(&token, &1000).is_err());
+
+    StellarAssetClient::new(&env, &token).mint(&payer, &1000);
+    token_client.transfer(&payer, &contract_id, &1000);
+    client.receive(&token, &1000);
+    assert!(client.try_receive(&token, &1000).is_err());
+    assert_eq!(client.get_total_received(&token), 1000);
+
+    assert_eq!(client.withdraw(&alice, &token), 600);
+    assert_eq!(client.withdraw(&bob, &token), 400);
+    assert_eq!(token_client.balance(&alice), 600);
+    assert_eq!(token_client.balance(&bob), 400);
+    assert_eq!(token_client.balance(&contract_id), 0);
+    assert!(client.try_withdraw(&alice, &token).is_err());
+}
+
+#[test]
+fn test_rounding_dust_carried_over() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, RoyaltySplitter);
+    let client = RoyaltySplitterClient::new(&env, &contract_id);
+    let token = env.register_stellar_asset_contract_v2(Address::generate(&env)).address();
+
+    let admin = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+
+    let mut splits = Map::new(&env);
+    splits.set(alice.clone(), 5000);
+    splits.set(bob.clone(), 5000);
+    client.init(&admin, &splits, &1);
+
+    StellarAssetClient::new(&env, &token).mint(&contract_id, &3);
+    client.receive(&token, &3);
+    assert_eq!(client.get_balance(&alice, &token), 1);
+    assert_eq!(client.get_balance(&bob, &token), 1);
+
+    // The odd token left uncredited is split with the next receipt
+    StellarAssetClient::new(&env, &token).mint(&contract_id, &1);
+    client.receive(&token, &2);
+    assert_eq!(client.get_balance(&alice, &token), 2);
+    assert_eq!(client.get_balance(&bob, &token), 2);
+}
+
+#[test]
+fn test_per_token_state_is_persistent() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, RoyaltySplitter);
+    let client = RoyaltySplitterClient::new(&env, &contract_id);
+    let token = env.register_stellar_asset_contract_v2(Address::generate(&env)).address();
+
+    let admin = Address::generate(&env);
+    let alice = Address::generate(&env);
+
+    let mut splits = Map::new(&env);
+    splits.set(alice.clone(), 10000);
+    client.init(&admin, &splits, &1);
+
+    StellarAssetClient::new(&env, &token).mint(&contract_id, &10);
+    client.receive(&token, &10);
 
     env.as_contract(&contract_id, || {
-        RoyaltySplitter::init(env.clone(), admin.clone(), splits, 10);
-        RoyaltySplitter::distribute(env.clone(), 1000);
-        RoyaltySplitter::withdraw(env.clone(), alice.clone());
-        RoyaltySplitter::withdraw(env.clone(), bob.clone());
+        for key in [
+            DataKey::Balances(token.clone()),
+            DataKey::TotalReceived(token.clone()),
+            DataKey::Accounted(token.clone()),
+        ] {
+            assert!(!env.storage().instance().has(&key));
+            assert!(env.storage().persistent().has(&key));
+        }
     });
 }
 
@@ -33,7 +109,7 @@ fn test_invalid_split() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let contract_id = env.register(RoyaltySplitter, ());
+    let contract_id = env.register_contract(None, RoyaltySplitter);
 
     let admin = Address::generate(&env);
     let alice = Address::generate(&env);
@@ -17,6 +17,15 @@ pub enum StakingTier {
     Gold = 3,
 }
 
+/// How staking rewards are paid: open-ended APY out of `RewardPool`, or
+/// fixed-rate emissions shared pro rata from funded reward schedules
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RewardMode {
+    Apy = 0,
+    Emission = 1,
+}
+
 //
 // ──────────────────────────────────────────────────────────
 // DATA KEYS
@@ -30,6 +39,20 @@ pub enum DataKey {
     StakersList,               // Vec<Address>
     TotalStaked,               // i128
     RewardPool,                // i128
+    RewardTokens,              // Vec<Address>
+    RewardSchedule(Address),   // RewardSchedule, per reward token
+    StakerEmission(Address, Address), // StakerEmission, per (staker, reward token)
+    TotalWeight,               // i128, boosted stake used for emission shares
+    LockOptions,               // Vec<LockOption>
+    Position(u64),             // StakePosition
+    StakerPositions(Address),  // Vec<u64>
+    NextPositionId,            // u64
+    Vault,                     // VaultConfig
+    VaultShares(Address),      // VaultPosition
+    Delegation(Address),       // Address the staker delegates to
+    DelegationTarget(Address), // DelegationTarget
+    DelegatorIndex(Address),   // i128, target's commission index at the staker's last settlement
+    TargetEmission(Address, Address), // TargetEmission, per (target, reward token)
 }
 
 //
@@ -55,6 +78,7 @@ pub struct StakingConfig {
     pub early_unstake_penalty: u32, // Penalty in basis points (1000 = 10%)
     pub emergency_penalty: u32,     // Emergency withdrawal penalty
     pub paused: bool,               // Contract pause state
+    pub reward_mode: RewardMode,    // APY or emission rewards
 }
 
 #[contracttype]
@@ -65,6 +89,89 @@ pub struct StakerInfo {
     pub last_reward_claim: u64,
     pub accumulated_rewards: i128,
     pub tier: StakingTier,
+    pub locked_amount: i128,        // Held in lock positions
+    pub reward_weight: i128,        // Flexible stake plus boosted positions
+}
+
+/// A lock duration offered for positions and the boost it earns
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LockOption {
+    pub duration: u64,
+    pub boost_bps: u32,             // Reward multiplier (15_000 = 1.5x)
+}
+
+/// An independent stake locked for a fixed duration
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct StakePosition {
+    pub id: u64,
+    pub owner: Address,
+    pub amount: i128,
+    pub boost_bps: u32,
+    pub start_time: u64,
+    pub unlock_time: u64,
+    pub last_reward_claim: u64,
+}
+
+/// Emission schedule for one reward token. `reward_rate` tokens per second
+/// are shared across all stake until `period_finish`.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct RewardSchedule {
+    pub reward_token: Address,
+    pub reward_rate: i128,
+    pub period_finish: u64,
+    pub last_update: u64,
+    pub acc_reward_per_share: i128, // Scaled by ACC_PRECISION
+    pub total_funded: i128,
+    pub total_claimed: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct StakerEmission {
+    pub reward_per_share_paid: i128,
+    pub pending: i128,
+    pub net_per_share_paid: i128,   // Target's net_per_share at the last checkpoint, while delegated
+}
+
+/// Auto-compounding vault. Its principal is staked under the contract's own
+/// address and owned pro rata by share holders.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct VaultConfig {
+    pub keeper_fee_bps: u32,        // Share of each harvest paid to the caller
+    pub total_shares: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct VaultPosition {
+    pub shares: i128,
+    pub last_deposit: u64,          // Early unstake penalty runs from here
+}
+
+/// An address stakers delegate to, such as a guild or puzzle creator.
+/// `delegated` is the raw stake backing it.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct DelegationTarget {
+    pub commission_bps: u32,        // Cut of delegators' rewards
+    pub delegated: i128,
+    pub delegators: u32,
+    pub pending_commission: i128,   // Unclaimed APY commission
+    pub commission_index: i128,     // Seconds times (BASIS_POINTS - commission) up to index_updated
+    pub index_updated: u64,
+}
+
+/// A target's running share of one reward schedule left to its delegators
+/// after commission, so a rate change never has to visit each delegator
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct TargetEmission {
+    pub acc_paid: i128,             // Schedule accumulator folded in so far
+    pub net_per_share: i128,        // Scaled by ACC_PRECISION
 }
 
 //
@@ -75,6 +182,13 @@ pub struct StakerInfo {
 
 const SECONDS_PER_YEAR: u64 = 31_536_000;
 const BASIS_POINTS: u64 = 10_000;
+const ACC_PRECISION: i128 = 1_000_000_000_000;
+const MAX_REWARD_TOKENS: u32 = 5;
+const SECONDS_PER_DAY: u64 = 86_400;
+const MAX_POSITIONS: u32 = 20;
+const SHARE_PRICE_PRECISION: i128 = 1_000_000_000;
+const MAX_KEEPER_FEE: u32 = 1_000;
+const MAX_COMMISSION: u32 = 5_000;
 
 //
 // ──────────────────────────────────────────────────────────
@@ -126,6 +240,7 @@ impl StakingContract {
             early_unstake_penalty: 1_000,  // 10% penalty
             emergency_penalty: 2_000,      // 20% penalty
             paused: false,
+            reward_mode: RewardMode::Apy,
         };
 
         env.storage().persistent().set(&DataKey::Config, &config);
@@ -221,6 +336,150 @@ impl StakingContract {
         env.storage().persistent().set(&DataKey::RewardPool, &(current_pool + amount));
     }
 
+    /// Switch between APY and emission rewards (admin only). APY earned so far
+    /// stays claimable through `claim_rewards`.
+    pub fn set_reward_mode(env: Env, admin: Address, mode: RewardMode) {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin);
+
+        let mut config: StakingConfig = env.storage().persistent().get(&DataKey::Config).unwrap();
+        if config.reward_mode == mode {
+            return;
+        }
+
+        // Checkpoint APY up to now so the switch neither adds nor drops rewards
+        for staker in Self::get_all_stakers(env.clone()).iter() {
+            let key = DataKey::StakerInfo(staker.clone());
+            let mut staker_info: StakerInfo = env.storage().persistent().get(&key).unwrap();
+            Self::accrue_rewards(&env, &staker, &mut staker_info, &config);
+            env.storage().persistent().set(&key, &staker_info);
+        }
+
+        config.reward_mode = mode;
+        env.storage().persistent().set(&DataKey::Config, &config);
+    }
+
+    /// Offer a lock duration with the given boost, or update its boost (admin only)
+    pub fn set_lock_option(env: Env, admin: Address, duration: u64, boost_bps: u32) {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin);
+
+        if duration == 0 {
+            panic!("Duration must be positive");
+        }
+        if (boost_bps as u64) < BASIS_POINTS {
+            panic!("Boost below 1x");
+        }
+
+        let mut options: Vec<LockOption> = Vec::new(&env);
+        for option in Self::get_lock_options(env.clone()).iter() {
+            if option.duration != duration {
+                options.push_back(option);
+            }
+        }
+        options.push_back(LockOption { duration, boost_bps });
+        env.storage().persistent().set(&DataKey::LockOptions, &options);
+    }
+
+    /// Stop offering a lock duration; existing positions keep their terms (admin only)
+    pub fn remove_lock_option(env: Env, admin: Address, duration: u64) {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin);
+
+        let mut options: Vec<LockOption> = Vec::new(&env);
+        for option in Self::get_lock_options(env.clone()).iter() {
+            if option.duration != duration {
+                options.push_back(option);
+            }
+        }
+        env.storage().persistent().set(&DataKey::LockOptions, &options);
+    }
+
+    /// Fund an emission schedule for `reward_token` paying out `amount` over
+    /// `duration` seconds (admin only). Funding a running schedule rolls the
+    /// undistributed remainder into the new period.
+    pub fn fund_reward_schedule(
+        env: Env,
+        admin: Address,
+        reward_token: Address,
+        amount: i128,
+        duration: u64,
+    ) {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin);
+
+        if amount <= 0 {
+            panic!("Amount must be positive");
+        }
+        if duration == 0 {
+            panic!("Duration must be positive");
+        }
+
+        let now = env.ledger().timestamp();
+        let total_weight = Self::schedule_weight(&env, &reward_token);
+        let mut reward_tokens = Self::get_reward_tokens(env.clone());
+
+        let mut schedule = match Self::get_reward_schedule(env.clone(), reward_token.clone()) {
+            Some(schedule) => Self::accrue_schedule(schedule, now, total_weight),
+            None => {
+                if reward_tokens.len() >= MAX_REWARD_TOKENS {
+                    panic!("Too many reward tokens");
+                }
+                reward_tokens.push_back(reward_token.clone());
+                env.storage().persistent().set(&DataKey::RewardTokens, &reward_tokens);
+
+                RewardSchedule {
+                    reward_token: reward_token.clone(),
+                    reward_rate: 0,
+                    period_finish: now,
+                    last_update: now,
+                    acc_reward_per_share: 0,
+                    total_funded: 0,
+                    total_claimed: 0,
+                }
+            }
+        };
+
+        token::Client::new(&env, &reward_token).transfer(&admin, &env.current_contract_address(), &amount);
+
+        let leftover = if now < schedule.period_finish {
+            schedule.reward_rate * (schedule.period_finish - now) as i128
+        } else {
+            0
+        };
+        let reward_rate = (amount + leftover) / duration as i128;
+        if reward_rate <= 0 {
+            panic!("Reward rate too low");
+        }
+
+        schedule.reward_rate = reward_rate;
+        schedule.last_update = now;
+        schedule.period_finish = now + duration;
+        schedule.total_funded += amount;
+        env.storage().persistent().set(&DataKey::RewardSchedule(reward_token), &schedule);
+    }
+
+    /// Enable the auto-compounding vault or update its keeper fee (admin only).
+    /// Only available when rewards are paid in the staking token.
+    pub fn configure_vault(env: Env, admin: Address, keeper_fee_bps: u32) {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin);
+
+        let config: StakingConfig = env.storage().persistent().get(&DataKey::Config).unwrap();
+        if config.reward_token != config.staking_token {
+            panic!("Vault requires reward token to be the staking token");
+        }
+        if keeper_fee_bps > MAX_KEEPER_FEE {
+            panic!("Keeper fee too high");
+        }
+
+        let vault = Self::get_vault_config(env.clone()).unwrap_or(VaultConfig {
+            keeper_fee_bps,
+            total_shares: 0,
+        });
+        env.storage().persistent().set(&DataKey::Vault, &VaultConfig { keeper_fee_bps, ..vault });
+    }
+
     // ───────────── STAKING FUNCTIONS ─────────────
 
     /// Stake tokens
@@ -246,19 +505,21 @@ impl StakingContract {
                 last_reward_claim: env.ledger().timestamp(),
                 accumulated_rewards: 0,
                 tier: StakingTier::None,
+                locked_amount: 0,
+                reward_weight: 0,
             });
 
         // If existing stake, claim pending rewards first
-        if staker_info.staked_amount > 0 {
-            let pending = Self::calculate_pending_rewards(&env, &staker_info, &config);
-            staker_info.accumulated_rewards += pending;
-        }
+        Self::checkpoint_emissions(&env, &staker, staker_info.reward_weight);
+        Self::accrue_rewards(&env, &staker, &mut staker_info, &config);
 
         // Update staker info
         staker_info.staked_amount += amount;
         staker_info.stake_timestamp = env.ledger().timestamp();
         staker_info.last_reward_claim = env.ledger().timestamp();
-        staker_info.tier = Self::calculate_tier(staker_info.staked_amount, &config);
+        staker_info.tier = Self::calculate_tier(staker_info.staked_amount + staker_info.locked_amount, &config);
+        Self::adjust_weight(&env, &mut staker_info, amount);
+        Self::adjust_delegated(&env, &staker, amount);
 
         env.storage().persistent().set(&DataKey::StakerInfo(staker.clone()), &staker_info);
 
@@ -289,8 +550,8 @@ impl StakingContract {
         }
 
         // Calculate pending rewards before unstaking
-        let pending = Self::calculate_pending_rewards(&env, &staker_info, &config);
-        staker_info.accumulated_rewards += pending;
+        Self::checkpoint_emissions(&env, &staker, staker_info.reward_weight);
+        Self::accrue_rewards(&env, &staker, &mut staker_info, &config);
 
         // Check if early unstake (before lock period ends)
         let time_staked = env.ledger().timestamp() - staker_info.stake_timestamp;
@@ -305,7 +566,9 @@ impl StakingContract {
         // Update staker info
         staker_info.staked_amount -= amount;
         staker_info.last_reward_claim = env.ledger().timestamp();
-        staker_info.tier = Self::calculate_tier(staker_info.staked_amount, &config);
+        staker_info.tier = Self::calculate_tier(staker_info.staked_amount + staker_info.locked_amount, &config);
+        Self::adjust_weight(&env, &mut staker_info, -amount);
+        Self::adjust_delegated(&env, &staker, -amount);
 
         env.storage().persistent().set(&DataKey::StakerInfo(staker.clone()), &staker_info);
 
@@ -318,11 +581,129 @@ impl StakingContract {
         env.storage().persistent().set(&DataKey::TotalStaked, &(total_staked - amount));
 
         // Remove from stakers list if fully unstaked
-        if staker_info.staked_amount == 0 {
+        if staker_info.staked_amount == 0 && staker_info.locked_amount == 0 {
             Self::remove_from_stakers_list(&env, staker);
         }
     }
 
+    /// Open a new position locked for `lock_duration` seconds, which must be one
+    /// of the offered lock options. Returns the position id.
+    pub fn stake_locked(env: Env, staker: Address, amount: i128, lock_duration: u64) -> u64 {
+        staker.require_auth();
+        Self::assert_not_paused(&env);
+
+        if amount <= 0 {
+            panic!("Amount must be positive");
+        }
+
+        let option = Self::get_lock_options(env.clone())
+            .iter()
+            .find(|option| option.duration == lock_duration)
+            .expect("Unsupported lock duration");
+
+        let mut position_ids = Self::get_staker_positions(env.clone(), staker.clone());
+        if position_ids.len() >= MAX_POSITIONS {
+            panic!("Too many positions");
+        }
+
+        let config: StakingConfig = env.storage().persistent().get(&DataKey::Config).unwrap();
+        let staking_client = token::Client::new(&env, &config.staking_token);
+        staking_client.transfer(&staker, &env.current_contract_address(), &amount);
+
+        let now = env.ledger().timestamp();
+        let mut staker_info = Self::get_staker_info(env.clone(), staker.clone())
+            .unwrap_or(StakerInfo {
+                staked_amount: 0,
+                stake_timestamp: now,
+                last_reward_claim: now,
+                accumulated_rewards: 0,
+                tier: StakingTier::None,
+                locked_amount: 0,
+                reward_weight: 0,
+            });
+
+        // Settle rewards at the old weight and tier
+        Self::checkpoint_emissions(&env, &staker, staker_info.reward_weight);
+        Self::accrue_rewards(&env, &staker, &mut staker_info, &config);
+
+        let id: u64 = env.storage().persistent().get(&DataKey::NextPositionId).unwrap_or(1);
+        env.storage().persistent().set(&DataKey::NextPositionId, &(id + 1));
+
+        let position = StakePosition {
+            id,
+            owner: staker.clone(),
+            amount,
+            boost_bps: option.boost_bps,
+            start_time: now,
+            unlock_time: now + lock_duration,
+            last_reward_claim: now,
+        };
+        env.storage().persistent().set(&DataKey::Position(id), &position);
+        position_ids.push_back(id);
+        env.storage().persistent().set(&DataKey::StakerPositions(staker.clone()), &position_ids);
+
+        staker_info.locked_amount += amount;
+        staker_info.tier = Self::calculate_tier(staker_info.staked_amount + staker_info.locked_amount, &config);
+        Self::adjust_weight(&env, &mut staker_info, Self::boosted(amount, option.boost_bps));
+        Self::adjust_delegated(&env, &staker, amount);
+        env.storage().persistent().set(&DataKey::StakerInfo(staker.clone()), &staker_info);
+
+        Self::add_to_stakers_list(&env, staker);
+
+        let total_staked: i128 = env.storage().persistent().get(&DataKey::TotalStaked).unwrap_or(0);
+        env.storage().persistent().set(&DataKey::TotalStaked, &(total_staked + amount));
+
+        id
+    }
+
+    /// Close a position, applying `early_unstake_penalty` if it is still locked.
+    /// Returns the amount sent back to the staker.
+    pub fn unstake_position(env: Env, staker: Address, position_id: u64) -> i128 {
+        staker.require_auth();
+        Self::assert_not_paused(&env);
+
+        let position: StakePosition = env.storage().persistent()
+            .get(&DataKey::Position(position_id))
+            .expect("Position not found");
+        if position.owner != staker {
+            panic!("Not position owner");
+        }
+
+        let config: StakingConfig = env.storage().persistent().get(&DataKey::Config).unwrap();
+        let mut staker_info: StakerInfo = env.storage().persistent()
+            .get(&DataKey::StakerInfo(staker.clone()))
+            .expect("Not staked");
+
+        Self::checkpoint_emissions(&env, &staker, staker_info.reward_weight);
+        Self::accrue_rewards(&env, &staker, &mut staker_info, &config);
+
+        let mut penalty_amount: i128 = 0;
+        if env.ledger().timestamp() < position.unlock_time {
+            penalty_amount = (position.amount * config.early_unstake_penalty as i128) / BASIS_POINTS as i128;
+        }
+        let amount_to_return = position.amount - penalty_amount;
+
+        Self::remove_position(&env, &staker, position_id);
+
+        staker_info.locked_amount -= position.amount;
+        staker_info.tier = Self::calculate_tier(staker_info.staked_amount + staker_info.locked_amount, &config);
+        Self::adjust_weight(&env, &mut staker_info, -Self::boosted(position.amount, position.boost_bps));
+        Self::adjust_delegated(&env, &staker, -position.amount);
+        env.storage().persistent().set(&DataKey::StakerInfo(staker.clone()), &staker_info);
+
+        let staking_client = token::Client::new(&env, &config.staking_token);
+        staking_client.transfer(&env.current_contract_address(), &staker, &amount_to_return);
+
+        let total_staked: i128 = env.storage().persistent().get(&DataKey::TotalStaked).unwrap_or(0);
+        env.storage().persistent().set(&DataKey::TotalStaked, &(total_staked - position.amount));
+
+        if staker_info.staked_amount == 0 && staker_info.locked_amount == 0 {
+            Self::remove_from_stakers_list(&env, staker);
+        }
+
+        amount_to_return
+    }
+
     /// Claim accumulated rewards
     pub fn claim_rewards(env: Env, staker: Address) -> i128 {
         staker.require_auth();
@@ -334,8 +715,8 @@ impl StakingContract {
             .expect("Not staked");
 
         // Calculate total rewards
-        let pending = Self::calculate_pending_rewards(&env, &staker_info, &config);
-        let total_rewards = staker_info.accumulated_rewards + pending;
+        Self::accrue_rewards(&env, &staker, &mut staker_info, &config);
+        let total_rewards = staker_info.accumulated_rewards;
 
         if total_rewards <= 0 {
             panic!("No rewards to claim");
@@ -362,6 +743,305 @@ impl StakingContract {
         total_rewards
     }
 
+    /// Claim emissions from every reward schedule. Returns (reward token, amount) pairs paid out.
+    pub fn claim_emissions(env: Env, staker: Address) -> Vec<(Address, i128)> {
+        staker.require_auth();
+        Self::assert_not_paused(&env);
+
+        let weight = Self::get_staker_info(env.clone(), staker.clone())
+            .map(|info| info.reward_weight)
+            .unwrap_or(0);
+        Self::checkpoint_emissions(&env, &staker, weight);
+
+        let mut claimed: Vec<(Address, i128)> = Vec::new(&env);
+        for reward_token in Self::get_reward_tokens(env.clone()).iter() {
+            let key = DataKey::StakerEmission(staker.clone(), reward_token.clone());
+            let mut emission: StakerEmission = env.storage().persistent().get(&key).unwrap();
+            if emission.pending <= 0 {
+                continue;
+            }
+
+            let amount = emission.pending;
+            emission.pending = 0;
+            env.storage().persistent().set(&key, &emission);
+
+            let schedule_key = DataKey::RewardSchedule(reward_token.clone());
+            let mut schedule: RewardSchedule = env.storage().persistent().get(&schedule_key).unwrap();
+            schedule.total_claimed += amount;
+            env.storage().persistent().set(&schedule_key, &schedule);
+
+            token::Client::new(&env, &reward_token).transfer(&env.current_contract_address(), &staker, &amount);
+            claimed.push_back((reward_token, amount));
+        }
+
+        if claimed.is_empty() {
+            panic!("No rewards to claim");
+        }
+
+        claimed
+    }
+
+    // ───────────── DELEGATION FUNCTIONS ─────────────
+
+    /// Set the commission a target takes from its delegators' rewards.
+    /// Rewards earned before the change keep the old rate.
+    pub fn set_commission(env: Env, target: Address, commission_bps: u32) {
+        target.require_auth();
+
+        if commission_bps > MAX_COMMISSION {
+            panic!("Commission too high");
+        }
+
+        // Fold the old rate into the target's indices so delegators settle
+        // what they earned so far at that rate, whenever they next checkpoint
+        let now = env.ledger().timestamp();
+        let mut target_info = Self::get_delegation_target(env.clone(), target.clone());
+        target_info.commission_index = Self::commission_index_at(&target_info, now);
+        target_info.index_updated = now;
+        for reward_token in Self::get_reward_tokens(env.clone()).iter() {
+            let schedule_key = DataKey::RewardSchedule(reward_token.clone());
+            let schedule: RewardSchedule = env.storage().persistent().get(&schedule_key).unwrap();
+            let schedule = Self::accrue_schedule(schedule, now, Self::schedule_weight(&env, &reward_token));
+            env.storage().persistent().set(&schedule_key, &schedule);
+
+            let index = Self::target_emission(&env, &target, &schedule, target_info.commission_bps);
+            env.storage().persistent().set(&DataKey::TargetEmission(target.clone(), reward_token), &index);
+        }
+
+        target_info.commission_bps = commission_bps;
+        env.storage().persistent().set(&DataKey::DelegationTarget(target), &target_info);
+    }
+
+    /// Delegate the staker's whole stake (flexible and locked) to `target`,
+    /// replacing any previous delegation
+    pub fn delegate(env: Env, staker: Address, target: Address) {
+        staker.require_auth();
+        Self::assert_not_paused(&env);
+
+        if staker == target {
+            panic!("Cannot delegate to self");
+        }
+
+        let mut staker_info: StakerInfo = env.storage().persistent()
+            .get(&DataKey::StakerInfo(staker.clone()))
+            .expect("Not staked");
+        if staker_info.staked_amount + staker_info.locked_amount <= 0 {
+            panic!("Not staked");
+        }
+
+        // Settle everything earned so far under the current delegation, if any,
+        // so the new target's commission only applies from now on
+        Self::settle_rewards(&env, &staker, &mut staker_info);
+        Self::undelegate_inner(&env, &staker, &staker_info);
+
+        env.storage().persistent().set(&DataKey::Delegation(staker.clone()), &target);
+        let mut target_info = Self::get_delegation_target(env.clone(), target.clone());
+
+        // Start from the target's current indices; rewards were just settled
+        let now = env.ledger().timestamp();
+        env.storage().persistent().set(
+            &DataKey::DelegatorIndex(staker.clone()),
+            &Self::commission_index_at(&target_info, now),
+        );
+        for reward_token in Self::get_reward_tokens(env.clone()).iter() {
+            let schedule: RewardSchedule = env.storage().persistent()
+                .get(&DataKey::RewardSchedule(reward_token.clone()))
+                .unwrap();
+            let index = Self::target_emission(&env, &target, &schedule, target_info.commission_bps);
+            let mut emission = Self::get_staker_emission(&env, &staker, &reward_token);
+            emission.net_per_share_paid = index.net_per_share;
+            env.storage().persistent().set(&DataKey::StakerEmission(staker.clone(), reward_token), &emission);
+        }
+
+        target_info.delegated += staker_info.staked_amount + staker_info.locked_amount;
+        target_info.delegators += 1;
+        env.storage().persistent().set(&DataKey::DelegationTarget(target), &target_info);
+    }
+
+    /// Withdraw the staker's delegation
+    pub fn undelegate(env: Env, staker: Address) {
+        staker.require_auth();
+
+        let mut staker_info: StakerInfo = env.storage().persistent()
+            .get(&DataKey::StakerInfo(staker.clone()))
+            .expect("Not staked");
+        if !env.storage().persistent().has(&DataKey::Delegation(staker.clone())) {
+            panic!("Not delegated");
+        }
+
+        Self::settle_rewards(&env, &staker, &mut staker_info);
+        Self::undelegate_inner(&env, &staker, &staker_info);
+    }
+
+    /// Claim APY commission earned from delegators. Emission commission is
+    /// claimed through `claim_emissions`.
+    pub fn claim_commission(env: Env, target: Address) -> i128 {
+        target.require_auth();
+        Self::assert_not_paused(&env);
+
+        let mut target_info = Self::get_delegation_target(env.clone(), target.clone());
+        let amount = target_info.pending_commission;
+        if amount <= 0 {
+            panic!("No rewards to claim");
+        }
+
+        let reward_pool: i128 = env.storage().persistent().get(&DataKey::RewardPool).unwrap_or(0);
+        if reward_pool < amount {
+            panic!("Insufficient reward pool");
+        }
+
+        target_info.pending_commission = 0;
+        env.storage().persistent().set(&DataKey::DelegationTarget(target.clone()), &target_info);
+        env.storage().persistent().set(&DataKey::RewardPool, &(reward_pool - amount));
+
+        let config: StakingConfig = env.storage().persistent().get(&DataKey::Config).unwrap();
+        let reward_client = token::Client::new(&env, &config.reward_token);
+        reward_client.transfer(&env.current_contract_address(), &target, &amount);
+
+        amount
+    }
+
+    // ───────────── VAULT FUNCTIONS ─────────────
+
+    /// Deposit staking tokens into the vault. Returns the shares minted.
+    pub fn vault_deposit(env: Env, user: Address, amount: i128) -> i128 {
+        user.require_auth();
+        Self::assert_not_paused(&env);
+
+        if amount <= 0 {
+            panic!("Amount must be positive");
+        }
+
+        let mut vault = Self::get_vault_config(env.clone()).expect("Vault not enabled");
+        Self::compound_vault(&env, 0);
+
+        let principal = Self::vault_principal(&env);
+        let shares = if vault.total_shares == 0 || principal == 0 {
+            amount
+        } else {
+            amount * vault.total_shares / principal
+        };
+        if shares <= 0 {
+            panic!("Deposit too small");
+        }
+
+        let config: StakingConfig = env.storage().persistent().get(&DataKey::Config).unwrap();
+        let staking_client = token::Client::new(&env, &config.staking_token);
+        staking_client.transfer(&user, &env.current_contract_address(), &amount);
+        Self::adjust_vault_principal(&env, amount);
+
+        let mut position = Self::get_vault_position(env.clone(), user.clone());
+        position.shares += shares;
+        position.last_deposit = env.ledger().timestamp();
+        env.storage().persistent().set(&DataKey::VaultShares(user), &position);
+
+        vault.total_shares += shares;
+        env.storage().persistent().set(&DataKey::Vault, &vault);
+
+        shares
+    }
+
+    /// Redeem vault shares for staking tokens. Withdrawing within the lock period
+    /// of the last deposit pays the early unstake penalty, which stays in the vault,
+    /// or goes to the reward pool when the last shares are redeemed.
+    /// Returns the amount sent to the user.
+    pub fn vault_withdraw(env: Env, user: Address, shares: i128) -> i128 {
+        user.require_auth();
+        Self::assert_not_paused(&env);
+
+        if shares <= 0 {
+            panic!("Amount must be positive");
+        }
+
+        let mut vault = Self::get_vault_config(env.clone()).expect("Vault not enabled");
+        let mut position = Self::get_vault_position(env.clone(), user.clone());
+        if position.shares < shares {
+            panic!("Insufficient shares");
+        }
+
+        Self::compound_vault(&env, 0);
+
+        let config: StakingConfig = env.storage().persistent().get(&DataKey::Config).unwrap();
+        let amount = shares * Self::vault_principal(&env) / vault.total_shares;
+
+        let mut penalty_amount: i128 = 0;
+        if env.ledger().timestamp() < position.last_deposit + config.min_lock_period {
+            penalty_amount = (amount * config.early_unstake_penalty as i128) / BASIS_POINTS as i128;
+        }
+        let amount_to_return = amount - penalty_amount;
+
+        position.shares -= shares;
+        env.storage().persistent().set(&DataKey::VaultShares(user.clone()), &position);
+        vault.total_shares -= shares;
+        env.storage().persistent().set(&DataKey::Vault, &vault);
+
+        Self::adjust_vault_principal(&env, -amount_to_return);
+
+        // With no shares left, whatever principal remains would go to the next
+        // depositor; move it to the reward pool instead (the vault pays rewards
+        // in the staking token)
+        if vault.total_shares == 0 {
+            let leftover = Self::vault_principal(&env);
+            if leftover > 0 {
+                Self::adjust_vault_principal(&env, -leftover);
+                let reward_pool: i128 = env.storage().persistent().get(&DataKey::RewardPool).unwrap_or(0);
+                env.storage().persistent().set(&DataKey::RewardPool, &(reward_pool + leftover));
+            }
+        }
+
+        let staking_client = token::Client::new(&env, &config.staking_token);
+        staking_client.transfer(&env.current_contract_address(), &user, &amount_to_return);
+
+        amount_to_return
+    }
+
+    /// Move vault shares to another holder. The recipient's penalty window
+    /// takes the later of the two deposits.
+    pub fn transfer_vault_shares(env: Env, from: Address, to: Address, shares: i128) {
+        from.require_auth();
+
+        if shares <= 0 {
+            panic!("Amount must be positive");
+        }
+        if from == to {
+            panic!("Cannot transfer to self");
+        }
+
+        let mut sender = Self::get_vault_position(env.clone(), from.clone());
+        if sender.shares < shares {
+            panic!("Insufficient shares");
+        }
+        let mut recipient = Self::get_vault_position(env.clone(), to.clone());
+
+        sender.shares -= shares;
+        recipient.shares += shares;
+        recipient.last_deposit = recipient.last_deposit.max(sender.last_deposit);
+
+        env.storage().persistent().set(&DataKey::VaultShares(from), &sender);
+        env.storage().persistent().set(&DataKey::VaultShares(to), &recipient);
+    }
+
+    /// Compound the vault's pending rewards into principal. Anyone can call;
+    /// the caller earns the keeper fee. Returns the amount compounded.
+    pub fn harvest(env: Env, keeper: Address) -> i128 {
+        keeper.require_auth();
+        Self::assert_not_paused(&env);
+
+        let vault = Self::get_vault_config(env.clone()).expect("Vault not enabled");
+        let (compounded, fee) = Self::compound_vault(&env, vault.keeper_fee_bps);
+        if compounded + fee <= 0 {
+            panic!("No rewards to claim");
+        }
+
+        if fee > 0 {
+            let config: StakingConfig = env.storage().persistent().get(&DataKey::Config).unwrap();
+            let staking_client = token::Client::new(&env, &config.staking_token);
+            staking_client.transfer(&env.current_contract_address(), &keeper, &fee);
+        }
+
+        compounded
+    }
+
     /// Emergency withdrawal - withdraw all staked tokens with higher penalty
     pub fn emergency_withdraw(env: Env, staker: Address) -> i128 {
         staker.require_auth();
@@ -371,12 +1051,40 @@ impl StakingContract {
             .get(&DataKey::StakerInfo(staker.clone()))
             .expect("Not staked");
 
-        if staker_info.staked_amount <= 0 {
+        if staker_info.staked_amount <= 0 && staker_info.locked_amount <= 0 {
             panic!("Nothing to withdraw");
         }
 
-        let penalty_amount = (staker_info.staked_amount * config.emergency_penalty as i128) / BASIS_POINTS as i128;
-        let amount_to_return = staker_info.staked_amount - penalty_amount;
+        // Emergency exits forfeit pending emissions along with accumulated rewards
+        Self::checkpoint_emissions(&env, &staker, staker_info.reward_weight);
+        for reward_token in Self::get_reward_tokens(env.clone()).iter() {
+            let key = DataKey::StakerEmission(staker.clone(), reward_token);
+            if let Some(mut emission) = env.storage().persistent().get::<_, StakerEmission>(&key) {
+                emission.pending = 0;
+                env.storage().persistent().set(&key, &emission);
+            }
+        }
+
+        // Lock positions are withdrawn too
+        for position_id in Self::get_staker_positions(env.clone(), staker.clone()).iter() {
+            env.storage().persistent().remove(&DataKey::Position(position_id));
+        }
+        env.storage().persistent().remove(&DataKey::StakerPositions(staker.clone()));
+
+        let withdrawn = staker_info.staked_amount + staker_info.locked_amount;
+        let penalty_amount = (withdrawn * config.emergency_penalty as i128) / BASIS_POINTS as i128;
+        let amount_to_return = withdrawn - penalty_amount;
+
+        let total_weight: i128 = env.storage().persistent().get(&DataKey::TotalWeight).unwrap_or(0);
+        env.storage().persistent().set(&DataKey::TotalWeight, &(total_weight - staker_info.reward_weight));
+        if let Some(target) = env.storage().persistent().get::<_, Address>(&DataKey::Delegation(staker.clone())) {
+            let mut target_info = Self::get_delegation_target(env.clone(), target.clone());
+            target_info.delegated -= withdrawn;
+            target_info.delegators -= 1;
+            env.storage().persistent().set(&DataKey::DelegationTarget(target.clone()), &target_info);
+            env.storage().persistent().remove(&DataKey::Delegation(staker.clone()));
+            env.storage().persistent().remove(&DataKey::DelegatorIndex(staker.clone()));
+        }
 
         // Clear staker info
         let empty_info = StakerInfo {
@@ -385,6 +1093,8 @@ impl StakingContract {
             last_reward_claim: 0,
             accumulated_rewards: 0,
             tier: StakingTier::None,
+            locked_amount: 0,
+            reward_weight: 0,
         };
         env.storage().persistent().set(&DataKey::StakerInfo(staker.clone()), &empty_info);
 
@@ -394,7 +1104,7 @@ impl StakingContract {
 
         // Update total staked
         let total_staked: i128 = env.storage().persistent().get(&DataKey::TotalStaked).unwrap_or(0);
-        env.storage().persistent().set(&DataKey::TotalStaked, &(total_staked - staker_info.staked_amount));
+        env.storage().persistent().set(&DataKey::TotalStaked, &(total_staked - withdrawn));
 
         // Remove from stakers list
         Self::remove_from_stakers_list(&env, staker);
@@ -413,9 +1123,13 @@ impl StakingContract {
     pub fn get_pending_rewards(env: Env, staker: Address) -> i128 {
         let config: StakingConfig = env.storage().persistent().get(&DataKey::Config).unwrap();
 
-        if let Some(staker_info) = Self::get_staker_info(env.clone(), staker) {
-            let pending = Self::calculate_pending_rewards(&env, &staker_info, &config);
-            staker_info.accumulated_rewards + pending
+        if let Some(staker_info) = Self::get_staker_info(env.clone(), staker.clone()) {
+            let mut pending = Self::calculate_pending_rewards(&env, &staker_info, &config);
+            for position in Self::get_positions(env.clone(), staker.clone()).iter() {
+                pending += Self::calculate_position_rewards(&env, &position, staker_info.tier, &config);
+            }
+            let commission = Self::apy_commission(&env, &staker, &staker_info, pending);
+            staker_info.accumulated_rewards + pending - commission
         } else {
             0
         }
@@ -455,11 +1169,7 @@ impl StakingContract {
             let unlock_time = staker_info.stake_timestamp + config.min_lock_period;
             let current_time = env.ledger().timestamp();
 
-            if current_time >= unlock_time {
-                0
-            } else {
-                unlock_time - current_time
-            }
+            unlock_time.saturating_sub(current_time)
         } else {
             0
         }
@@ -475,10 +1185,463 @@ impl StakingContract {
         env.storage().persistent().get(&DataKey::StakersList).unwrap_or(Vec::new(&env))
     }
 
+    /// Get the reward tokens with emission schedules
+    pub fn get_reward_tokens(env: Env) -> Vec<Address> {
+        env.storage().persistent().get(&DataKey::RewardTokens).unwrap_or(Vec::new(&env))
+    }
+
+    /// Get the emission schedule for a reward token
+    pub fn get_reward_schedule(env: Env, reward_token: Address) -> Option<RewardSchedule> {
+        env.storage().persistent().get(&DataKey::RewardSchedule(reward_token))
+    }
+
+    /// Get unclaimed emissions of `reward_token` for a staker
+    pub fn get_pending_emissions(env: Env, staker: Address, reward_token: Address) -> i128 {
+        let schedule = match Self::get_reward_schedule(env.clone(), reward_token.clone()) {
+            Some(schedule) => schedule,
+            None => return 0,
+        };
+        let total_weight = Self::schedule_weight(&env, &reward_token);
+        let schedule = Self::accrue_schedule(schedule, env.ledger().timestamp(), total_weight);
+
+        let weight = Self::get_staker_info(env.clone(), staker.clone())
+            .map(|info| Self::emission_weight(&env, &staker, &reward_token, info.reward_weight))
+            .unwrap_or(0);
+        let emission = Self::get_staker_emission(&env, &staker, &reward_token);
+
+        let earned = Self::earned_since(weight, &schedule, &emission);
+        emission.pending + earned - Self::emission_commission(&env, &staker, &schedule, &emission, weight, earned).0
+    }
+
+    /// Get the lock durations on offer and their boosts
+    pub fn get_lock_options(env: Env) -> Vec<LockOption> {
+        env.storage().persistent().get(&DataKey::LockOptions).unwrap_or(Vec::from_array(
+            &env,
+            [
+                LockOption { duration: 30 * SECONDS_PER_DAY, boost_bps: 11_000 },  // 1.1x
+                LockOption { duration: 90 * SECONDS_PER_DAY, boost_bps: 12_500 },  // 1.25x
+                LockOption { duration: 365 * SECONDS_PER_DAY, boost_bps: 20_000 }, // 2x
+            ],
+        ))
+    }
+
+    /// Get a lock position
+    pub fn get_position(env: Env, position_id: u64) -> Option<StakePosition> {
+        env.storage().persistent().get(&DataKey::Position(position_id))
+    }
+
+    /// Get the ids of a staker's open positions
+    pub fn get_staker_positions(env: Env, staker: Address) -> Vec<u64> {
+        env.storage().persistent().get(&DataKey::StakerPositions(staker)).unwrap_or(Vec::new(&env))
+    }
+
+    /// Get all of a staker's open positions
+    pub fn get_positions(env: Env, staker: Address) -> Vec<StakePosition> {
+        let mut positions: Vec<StakePosition> = Vec::new(&env);
+        for position_id in Self::get_staker_positions(env.clone(), staker).iter() {
+            positions.push_back(env.storage().persistent().get(&DataKey::Position(position_id)).unwrap());
+        }
+        positions
+    }
+
+    /// Get the target a staker delegates to
+    pub fn get_delegation(env: Env, staker: Address) -> Option<Address> {
+        env.storage().persistent().get(&DataKey::Delegation(staker))
+    }
+
+    /// Get a delegation target's commission, backing and unclaimed commission
+    pub fn get_delegation_target(env: Env, target: Address) -> DelegationTarget {
+        env.storage().persistent().get(&DataKey::DelegationTarget(target)).unwrap_or(DelegationTarget {
+            commission_bps: 0,
+            delegated: 0,
+            delegators: 0,
+            pending_commission: 0,
+            commission_index: 0,
+            index_updated: 0,
+        })
+    }
+
+    /// Get the total stake delegated to a target
+    pub fn get_delegated_total(env: Env, target: Address) -> i128 {
+        Self::get_delegation_target(env, target).delegated
+    }
+
+    /// Get the vault configuration, if the vault is enabled
+    pub fn get_vault_config(env: Env) -> Option<VaultConfig> {
+        env.storage().persistent().get(&DataKey::Vault)
+    }
+
+    /// Get a holder's vault shares
+    pub fn get_vault_position(env: Env, user: Address) -> VaultPosition {
+        env.storage().persistent().get(&DataKey::VaultShares(user)).unwrap_or(VaultPosition {
+            shares: 0,
+            last_deposit: 0,
+        })
+    }
+
+    /// Get staking tokens held by the vault, excluding rewards not yet harvested
+    pub fn get_vault_assets(env: Env) -> i128 {
+        Self::vault_principal(&env)
+    }
+
+    /// Get staking tokens per vault share, scaled by 1e9. Starts at 1e9 and
+    /// grows as harvests compound.
+    pub fn get_vault_share_price(env: Env) -> i128 {
+        let total_shares = Self::get_vault_config(env.clone()).map(|vault| vault.total_shares).unwrap_or(0);
+        if total_shares == 0 {
+            return SHARE_PRICE_PRECISION;
+        }
+        Self::vault_principal(&env) * SHARE_PRICE_PRECISION / total_shares
+    }
+
+    /// Get the total boosted stake that emissions are shared across
+    pub fn get_total_weight(env: Env) -> i128 {
+        env.storage().persistent().get(&DataKey::TotalWeight).unwrap_or(0)
+    }
+
     // ───────────── INTERNAL HELPERS ─────────────
 
+    /// Bring every reward schedule up to date and credit the staker's share
+    /// earned on `weight`. Must run before the staker's weight or the total changes.
+    fn checkpoint_emissions(env: &Env, staker: &Address, weight: i128) {
+        let now = env.ledger().timestamp();
+
+        let target: Option<Address> = env.storage().persistent().get(&DataKey::Delegation(staker.clone()));
+
+        for reward_token in Self::get_reward_tokens(env.clone()).iter() {
+            let schedule_key = DataKey::RewardSchedule(reward_token.clone());
+            let schedule: RewardSchedule = env.storage().persistent().get(&schedule_key).unwrap();
+            let schedule = Self::accrue_schedule(schedule, now, Self::schedule_weight(env, &reward_token));
+            env.storage().persistent().set(&schedule_key, &schedule);
+
+            let mut emission = Self::get_staker_emission(env, staker, &reward_token);
+            let token_weight = Self::emission_weight(env, staker, &reward_token, weight);
+            let earned = Self::earned_since(token_weight, &schedule, &emission);
+            let (commission, net_per_share) =
+                Self::emission_commission(env, staker, &schedule, &emission, token_weight, earned);
+            emission.pending += earned - commission;
+            emission.reward_per_share_paid = schedule.acc_reward_per_share;
+            emission.net_per_share_paid = net_per_share;
+            env.storage().persistent().set(&DataKey::StakerEmission(staker.clone(), reward_token.clone()), &emission);
+
+            // The target claims its cut through `claim_emissions`
+            if let (Some(target), true) = (target.clone(), commission > 0) {
+                let mut target_emission = Self::get_staker_emission(env, &target, &reward_token);
+                target_emission.pending += commission;
+                env.storage().persistent().set(&DataKey::StakerEmission(target, reward_token), &target_emission);
+            }
+        }
+    }
+
+    /// Advance the reward-per-share accumulator to `now`. Time with nothing
+    /// staked emits nothing.
+    fn accrue_schedule(mut schedule: RewardSchedule, now: u64, total_weight: i128) -> RewardSchedule {
+        let applicable = now.min(schedule.period_finish);
+        if applicable > schedule.last_update {
+            if total_weight > 0 {
+                let emitted = schedule.reward_rate * (applicable - schedule.last_update) as i128;
+                schedule.acc_reward_per_share += emitted * ACC_PRECISION / total_weight;
+            }
+            schedule.last_update = applicable;
+        }
+        schedule
+    }
+
+    /// Weight sharing `reward_token`'s emissions. The vault only compounds
+    /// the staking token, so its stake sits out every other schedule.
+    fn schedule_weight(env: &Env, reward_token: &Address) -> i128 {
+        let total_weight: i128 = env.storage().persistent().get(&DataKey::TotalWeight).unwrap_or(0);
+        if Self::is_staking_token(env, reward_token) {
+            return total_weight;
+        }
+        let vault_weight = Self::get_staker_info(env.clone(), env.current_contract_address())
+            .map(|info| info.reward_weight)
+            .unwrap_or(0);
+        total_weight - vault_weight
+    }
+
+    /// The part of a staker's `weight` that earns `reward_token`
+    fn emission_weight(env: &Env, staker: &Address, reward_token: &Address, weight: i128) -> i128 {
+        if *staker == env.current_contract_address() && !Self::is_staking_token(env, reward_token) {
+            return 0;
+        }
+        weight
+    }
+
+    fn is_staking_token(env: &Env, token: &Address) -> bool {
+        let config: StakingConfig = env.storage().persistent().get(&DataKey::Config).unwrap();
+        *token == config.staking_token
+    }
+
+    fn earned_since(weight: i128, schedule: &RewardSchedule, emission: &StakerEmission) -> i128 {
+        weight * (schedule.acc_reward_per_share - emission.reward_per_share_paid) / ACC_PRECISION
+    }
+
+    /// Change a staker's emission weight and the total to match
+    fn adjust_weight(env: &Env, staker_info: &mut StakerInfo, delta: i128) {
+        staker_info.reward_weight += delta;
+        let total_weight: i128 = env.storage().persistent().get(&DataKey::TotalWeight).unwrap_or(0);
+        env.storage().persistent().set(&DataKey::TotalWeight, &(total_weight + delta));
+    }
+
+    fn boosted(amount: i128, boost_bps: u32) -> i128 {
+        amount * boost_bps as i128 / BASIS_POINTS as i128
+    }
+
+    /// Move APY earned by the flexible stake and each position into
+    /// `accumulated_rewards`, less the delegation target's commission.
+    /// Must run before the staker's tier or delegation changes.
+    fn accrue_rewards(env: &Env, staker: &Address, staker_info: &mut StakerInfo, config: &StakingConfig) {
+        let now = env.ledger().timestamp();
+        let mut earned = Self::calculate_pending_rewards(env, staker_info, config);
+
+        if staker_info.locked_amount > 0 {
+            for position_id in Self::get_staker_positions(env.clone(), staker.clone()).iter() {
+                let key = DataKey::Position(position_id);
+                let mut position: StakePosition = env.storage().persistent().get(&key).unwrap();
+                earned += Self::calculate_position_rewards(env, &position, staker_info.tier, config);
+                position.last_reward_claim = now;
+                env.storage().persistent().set(&key, &position);
+            }
+        }
+
+        let commission = Self::apy_commission(env, staker, staker_info, earned);
+        if let Some(target) = env.storage().persistent().get::<_, Address>(&DataKey::Delegation(staker.clone())) {
+            let mut target_info = Self::get_delegation_target(env.clone(), target.clone());
+            env.storage().persistent().set(
+                &DataKey::DelegatorIndex(staker.clone()),
+                &Self::commission_index_at(&target_info, now),
+            );
+            if commission > 0 {
+                target_info.pending_commission += commission;
+                env.storage().persistent().set(&DataKey::DelegationTarget(target), &target_info);
+            }
+        }
+        staker_info.last_reward_claim = now;
+        staker_info.accumulated_rewards += earned - commission;
+    }
+
+    /// The share of APY `earned` since the staker's last settlement owed to
+    /// their delegation target, at the rates in effect over that time
+    fn apy_commission(env: &Env, staker: &Address, staker_info: &StakerInfo, earned: i128) -> i128 {
+        let target = match env.storage().persistent().get::<_, Address>(&DataKey::Delegation(staker.clone())) {
+            Some(target) => target,
+            None => return 0,
+        };
+        let now = env.ledger().timestamp();
+        let elapsed = (now - staker_info.last_reward_claim) as i128;
+        if earned <= 0 || elapsed == 0 {
+            return 0;
+        }
+
+        // APY accrues evenly between settlements, so the index gives the
+        // time-weighted share left to the staker
+        let target_info = Self::get_delegation_target(env.clone(), target);
+        let paid: i128 = env.storage().persistent().get(&DataKey::DelegatorIndex(staker.clone())).unwrap_or(0);
+        let net = earned * (Self::commission_index_at(&target_info, now) - paid) / (elapsed * BASIS_POINTS as i128);
+        earned - net
+    }
+
+    /// The share of emissions `earned` on `weight` owed to the staker's
+    /// delegation target, and the target's net reward per share to record
+    fn emission_commission(
+        env: &Env,
+        staker: &Address,
+        schedule: &RewardSchedule,
+        emission: &StakerEmission,
+        weight: i128,
+        earned: i128,
+    ) -> (i128, i128) {
+        match env.storage().persistent().get::<_, Address>(&DataKey::Delegation(staker.clone())) {
+            Some(target) => {
+                let commission_bps = Self::get_delegation_target(env.clone(), target.clone()).commission_bps;
+                let index = Self::target_emission(env, &target, schedule, commission_bps);
+                let net = weight * (index.net_per_share - emission.net_per_share_paid) / ACC_PRECISION;
+                (earned - net, index.net_per_share)
+            }
+            None => (0, emission.net_per_share_paid),
+        }
+    }
+
+    /// The target's commission index brought forward to `now` at its current rate
+    fn commission_index_at(target_info: &DelegationTarget, now: u64) -> i128 {
+        let share = BASIS_POINTS as i128 - target_info.commission_bps as i128;
+        target_info.commission_index + (now - target_info.index_updated) as i128 * share
+    }
+
+    /// The target's net reward per share for `schedule`, brought up to its
+    /// accumulator at the target's current rate
+    fn target_emission(env: &Env, target: &Address, schedule: &RewardSchedule, commission_bps: u32) -> TargetEmission {
+        let mut index: TargetEmission = env.storage().persistent()
+            .get(&DataKey::TargetEmission(target.clone(), schedule.reward_token.clone()))
+            .unwrap_or(TargetEmission { acc_paid: 0, net_per_share: 0 });
+        let share = BASIS_POINTS as i128 - commission_bps as i128;
+        index.net_per_share += (schedule.acc_reward_per_share - index.acc_paid) * share / BASIS_POINTS as i128;
+        index.acc_paid = schedule.acc_reward_per_share;
+        index
+    }
+
+    /// Keep the staker's delegation target's backing in step with their stake
+    fn adjust_delegated(env: &Env, staker: &Address, delta: i128) {
+        if let Some(target) = env.storage().persistent().get::<_, Address>(&DataKey::Delegation(staker.clone())) {
+            let mut target_info = Self::get_delegation_target(env.clone(), target.clone());
+            target_info.delegated += delta;
+            env.storage().persistent().set(&DataKey::DelegationTarget(target), &target_info);
+        }
+    }
+
+    fn calculate_position_rewards(
+        env: &Env,
+        position: &StakePosition,
+        tier: StakingTier,
+        config: &StakingConfig,
+    ) -> i128 {
+        if config.reward_mode == RewardMode::Emission {
+            return 0;
+        }
+
+        let time_elapsed = (env.ledger().timestamp() - position.last_reward_claim) as i128;
+        let apy = Self::get_apy_for_tier(tier, config) as i128;
+
+        // Same as the flexible stake, on the boosted amount
+        let annual_reward = (Self::boosted(position.amount, position.boost_bps) * apy) / BASIS_POINTS as i128;
+        (annual_reward * time_elapsed) / SECONDS_PER_YEAR as i128
+    }
+
+    /// Credit APY and emissions earned so far, under the current delegation
+    fn settle_rewards(env: &Env, staker: &Address, staker_info: &mut StakerInfo) {
+        let config: StakingConfig = env.storage().persistent().get(&DataKey::Config).unwrap();
+        Self::checkpoint_emissions(env, staker, staker_info.reward_weight);
+        Self::accrue_rewards(env, staker, staker_info, &config);
+        env.storage().persistent().set(&DataKey::StakerInfo(staker.clone()), staker_info);
+    }
+
+    /// Remove the staker's delegation, if any. Rewards must be settled first.
+    fn undelegate_inner(env: &Env, staker: &Address, staker_info: &StakerInfo) {
+        let current: Option<Address> = env.storage().persistent().get(&DataKey::Delegation(staker.clone()));
+        let current = match current {
+            Some(target) => target,
+            None => return,
+        };
+
+        let mut target_info = Self::get_delegation_target(env.clone(), current.clone());
+        target_info.delegated -= staker_info.staked_amount + staker_info.locked_amount;
+        target_info.delegators -= 1;
+        env.storage().persistent().set(&DataKey::DelegationTarget(current.clone()), &target_info);
+        env.storage().persistent().remove(&DataKey::Delegation(staker.clone()));
+        env.storage().persistent().remove(&DataKey::DelegatorIndex(staker.clone()));
+    }
+
+    fn vault_principal(env: &Env) -> i128 {
+        Self::get_staker_info(env.clone(), env.current_contract_address())
+            .map(|info| info.staked_amount)
+            .unwrap_or(0)
+    }
+
+    /// Change the vault's stake, settling its rewards at the old balance first
+    fn adjust_vault_principal(env: &Env, delta: i128) {
+        let vault = env.current_contract_address();
+        let config: StakingConfig = env.storage().persistent().get(&DataKey::Config).unwrap();
+        let now = env.ledger().timestamp();
+
+        let mut staker_info = Self::get_staker_info(env.clone(), vault.clone()).unwrap_or(StakerInfo {
+            staked_amount: 0,
+            stake_timestamp: now,
+            last_reward_claim: now,
+            accumulated_rewards: 0,
+            tier: StakingTier::None,
+            locked_amount: 0,
+            reward_weight: 0,
+        });
+
+        Self::checkpoint_emissions(env, &vault, staker_info.reward_weight);
+        staker_info.accumulated_rewards += Self::calculate_pending_rewards(env, &staker_info, &config);
+        staker_info.last_reward_claim = now;
+
+        staker_info.staked_amount += delta;
+        staker_info.tier = Self::calculate_tier(staker_info.staked_amount, &config);
+        Self::adjust_weight(env, &mut staker_info, delta);
+        env.storage().persistent().set(&DataKey::StakerInfo(vault.clone()), &staker_info);
+
+        if staker_info.staked_amount > 0 {
+            Self::add_to_stakers_list(env, vault);
+        } else {
+            Self::remove_from_stakers_list(env, vault);
+        }
+
+        let total_staked: i128 = env.storage().persistent().get(&DataKey::TotalStaked).unwrap_or(0);
+        env.storage().persistent().set(&DataKey::TotalStaked, &(total_staked + delta));
+    }
+
+    /// Restake the vault's APY (as far as the reward pool covers it) and its
+    /// staking-token emissions, less `keeper_fee_bps`. Returns (compounded, fee).
+    fn compound_vault(env: &Env, keeper_fee_bps: u32) -> (i128, i128) {
+        let vault = env.current_contract_address();
+        let mut staker_info = match Self::get_staker_info(env.clone(), vault.clone()) {
+            Some(info) if info.staked_amount > 0 => info,
+            _ => return (0, 0),
+        };
+        let config: StakingConfig = env.storage().persistent().get(&DataKey::Config).unwrap();
+
+        Self::checkpoint_emissions(env, &vault, staker_info.reward_weight);
+        let mut harvested: i128 = 0;
+
+        let emission_key = DataKey::StakerEmission(vault.clone(), config.staking_token.clone());
+        if let Some(mut emission) = env.storage().persistent().get::<_, StakerEmission>(&emission_key) {
+            if emission.pending > 0 {
+                let schedule_key = DataKey::RewardSchedule(config.staking_token.clone());
+                let mut schedule: RewardSchedule = env.storage().persistent().get(&schedule_key).unwrap();
+                schedule.total_claimed += emission.pending;
+                env.storage().persistent().set(&schedule_key, &schedule);
+
+                harvested += emission.pending;
+                emission.pending = 0;
+                env.storage().persistent().set(&emission_key, &emission);
+            }
+        }
+
+        let earned = staker_info.accumulated_rewards + Self::calculate_pending_rewards(env, &staker_info, &config);
+        let reward_pool: i128 = env.storage().persistent().get(&DataKey::RewardPool).unwrap_or(0);
+        let from_pool = earned.min(reward_pool);
+        staker_info.accumulated_rewards = earned - from_pool;
+        staker_info.last_reward_claim = env.ledger().timestamp();
+        env.storage().persistent().set(&DataKey::StakerInfo(vault), &staker_info);
+        env.storage().persistent().set(&DataKey::RewardPool, &(reward_pool - from_pool));
+        harvested += from_pool;
+
+        let fee = harvested * keeper_fee_bps as i128 / BASIS_POINTS as i128;
+        let compounded = harvested - fee;
+        if compounded > 0 {
+            Self::adjust_vault_principal(env, compounded);
+        }
+
+        (compounded, fee)
+    }
+
+    fn remove_position(env: &Env, staker: &Address, position_id: u64) {
+        let mut remaining: Vec<u64> = Vec::new(env);
+        for id in Self::get_staker_positions(env.clone(), staker.clone()).iter() {
+            if id != position_id {
+                remaining.push_back(id);
+            }
+        }
+        env.storage().persistent().set(&DataKey::StakerPositions(staker.clone()), &remaining);
+        env.storage().persistent().remove(&DataKey::Position(position_id));
+    }
+
+    fn get_staker_emission(env: &Env, staker: &Address, reward_token: &Address) -> StakerEmission {
+        env.storage()
+            .persistent()
+            .get(&DataKey::StakerEmission(staker.clone(), reward_token.clone()))
+            .unwrap_or(StakerEmission {
+                reward_per_share_paid: 0,
+                pending: 0,
+                net_per_share_paid: 0,
+            })
+    }
+
     fn calculate_pending_rewards(env: &Env, staker_info: &StakerInfo, config: &StakingConfig) -> i128 {
-        if staker_info.staked_amount <= 0 {
+        if staker_info.staked_amount <= 0 || config.reward_mode == RewardMode::Emission {
             return 0;
         }
 
@@ -496,9 +1659,7 @@ impl StakingContract {
         // Calculate: (staked * apy / BASIS_POINTS) * time / SECONDS_PER_YEAR
         // This avoids overflow by doing division earlier
         let annual_reward = (staked * apy) / basis_points;
-        let rewards = (annual_reward * time) / seconds_per_year;
-
-        rewards
+        (annual_reward * time) / seconds_per_year
     }
 
     fn calculate_tier(staked_amount: i128, config: &StakingConfig) -> StakingTier {
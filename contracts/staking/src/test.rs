@@ -15,14 +15,14 @@ fn create_token_contract<'a>(env: &Env, admin: &Address) -> (Address, TokenClien
 }
 
 fn setup_staking_contract(env: &Env) -> (
-    StakingContractClient,
+    StakingContractClient<'_>,
     Address,
     Address,
     Address,
-    TokenClient,
-    TokenClient,
-    StellarAssetClient,
-    StellarAssetClient,
+    TokenClient<'_>,
+    TokenClient<'_>,
+    StellarAssetClient<'_>,
+    StellarAssetClient<'_>,
 ) {
     let admin = Address::generate(env);
     let staker = Address::generate(env);
@@ -669,3 +669,523 @@ fn test_full_staking_lifecycle() {
     assert_eq!(staker_info.staked_amount, 0);
     assert_eq!(client.get_total_staked(), 0);
 }
+
+#[test]
+fn test_emissions_shared_pro_rata() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(0);
+
+    let (client, admin, staker, _, _, reward_token_client, staking_admin_client, reward_admin_client) =
+        setup_staking_contract(&env);
+    let staker2 = Address::generate(&env);
+
+    staking_admin_client.mint(&staker, &1_000);
+    staking_admin_client.mint(&staker2, &3_000);
+    client.set_reward_mode(&admin, &RewardMode::Emission);
+    client.stake(&staker, &1_000);
+
+    // 10,000 reward tokens over 1,000 seconds = 10 per second
+    reward_admin_client.mint(&admin, &10_000);
+    client.fund_reward_schedule(&admin, &reward_token_client.address, &10_000, &1_000);
+
+    // Staker 1 earns everything for the first half
+    env.ledger().set_timestamp(500);
+    assert_eq!(client.get_pending_emissions(&staker, &reward_token_client.address), 5_000);
+    client.stake(&staker2, &3_000);
+
+    // Then a quarter of the second half; emissions stop at the period end
+    env.ledger().set_timestamp(2_000);
+    assert_eq!(client.get_pending_emissions(&staker, &reward_token_client.address), 6_250);
+    assert_eq!(client.get_pending_emissions(&staker2, &reward_token_client.address), 3_750);
+
+    let claimed = client.claim_emissions(&staker);
+    assert_eq!(claimed.len(), 1);
+    assert_eq!(claimed.get(0).unwrap(), (reward_token_client.address.clone(), 6_250));
+    client.claim_emissions(&staker2);
+
+    assert_eq!(reward_token_client.balance(&staker), 6_250);
+    assert_eq!(reward_token_client.balance(&staker2), 3_750);
+
+    // APY is off in emission mode
+    assert_eq!(client.get_pending_rewards(&staker), 0);
+
+    let schedule = client.get_reward_schedule(&reward_token_client.address).unwrap();
+    assert_eq!(schedule.total_funded, 10_000);
+    assert_eq!(schedule.total_claimed, 10_000);
+}
+
+#[test]
+fn test_multiple_reward_schedules() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(0);
+
+    let (client, admin, staker, token_admin, _, reward_token_client, staking_admin_client, reward_admin_client) =
+        setup_staking_contract(&env);
+    let (bonus_token_addr, bonus_token_client) = create_token_contract(&env, &token_admin);
+    let bonus_admin_client = StellarAssetClient::new(&env, &bonus_token_addr);
+
+    staking_admin_client.mint(&staker, &1_000);
+    client.stake(&staker, &1_000);
+
+    reward_admin_client.mint(&admin, &4_000);
+    bonus_admin_client.mint(&admin, &1_000);
+    client.fund_reward_schedule(&admin, &reward_token_client.address, &2_000, &1_000);
+    client.fund_reward_schedule(&admin, &bonus_token_addr, &1_000, &100);
+    assert_eq!(client.get_reward_tokens().len(), 2);
+
+    // Top up halfway: 1,000 left over + 2,000 new over another 1,000 seconds
+    env.ledger().set_timestamp(500);
+    client.fund_reward_schedule(&admin, &reward_token_client.address, &2_000, &1_000);
+    let schedule = client.get_reward_schedule(&reward_token_client.address).unwrap();
+    assert_eq!(schedule.reward_rate, 3);
+    assert_eq!(schedule.period_finish, 1_500);
+
+    env.ledger().set_timestamp(1_500);
+    let claimed = client.claim_emissions(&staker);
+    assert_eq!(claimed.len(), 2);
+    assert_eq!(reward_token_client.balance(&staker), 4_000);
+    assert_eq!(bonus_token_client.balance(&staker), 1_000);
+
+    // Nothing left to claim
+    assert!(client.try_claim_emissions(&staker).is_err());
+}
+
+#[test]
+fn test_emissions_forfeited_on_emergency_withdraw() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(0);
+
+    let (client, admin, staker, _, _, reward_token_client, staking_admin_client, reward_admin_client) =
+        setup_staking_contract(&env);
+
+    staking_admin_client.mint(&staker, &1_000);
+    client.stake(&staker, &1_000);
+    reward_admin_client.mint(&admin, &1_000);
+    client.fund_reward_schedule(&admin, &reward_token_client.address, &1_000, &100);
+
+    env.ledger().set_timestamp(50);
+    assert_eq!(client.get_pending_emissions(&staker, &reward_token_client.address), 500);
+    client.emergency_withdraw(&staker);
+    assert_eq!(client.get_pending_emissions(&staker, &reward_token_client.address), 0);
+}
+
+#[test]
+fn test_lock_positions_unstake_independently() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(0);
+
+    let (client, _, staker, _, staking_token_client, _, staking_admin_client, _) = setup_staking_contract(&env);
+    let day = 86_400u64;
+
+    staking_admin_client.mint(&staker, &3_000);
+    let short_id = client.stake_locked(&staker, &1_000, &(30 * day));
+    let long_id = client.stake_locked(&staker, &1_000, &(365 * day));
+    assert_eq!(client.get_staker_positions(&staker).len(), 2);
+    assert_eq!(client.get_total_staked(), 2_000);
+
+    // Unsupported durations are rejected
+    assert!(client.try_stake_locked(&staker, &1_000, &(10 * day)).is_err());
+
+    // A later top-up doesn't touch either position's lock
+    env.ledger().set_timestamp(29 * day);
+    client.stake(&staker, &1_000);
+    assert_eq!(client.get_position(&short_id).unwrap().unlock_time, 30 * day);
+
+    // The 30-day position unlocks on its own schedule
+    env.ledger().set_timestamp(30 * day);
+    assert_eq!(client.unstake_position(&staker, &short_id), 1_000);
+
+    // The 365-day position is still locked: 10% early unstake penalty
+    assert_eq!(client.unstake_position(&staker, &long_id), 900);
+    assert_eq!(staking_token_client.balance(&staker), 1_900);
+
+    assert!(client.get_position(&long_id).is_none());
+    assert_eq!(client.get_staker_positions(&staker).len(), 0);
+    let info = client.get_staker_info(&staker).unwrap();
+    assert_eq!(info.locked_amount, 0);
+    assert_eq!(info.reward_weight, 1_000);
+}
+
+#[test]
+fn test_lock_boost_rewards() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(0);
+
+    let (client, admin, staker, _, _, reward_token_client, staking_admin_client, reward_admin_client) =
+        setup_staking_contract(&env);
+    let day = 86_400u64;
+
+    // 2x boost on a one-year lock: Bronze APY (6%) on double the stake
+    staking_admin_client.mint(&staker, &1_000_000_000);
+    let id = client.stake_locked(&staker, &1_000_000_000, &(365 * day));
+    assert_eq!(client.get_staker_info(&staker).unwrap().tier, StakingTier::Bronze);
+
+    env.ledger().set_timestamp(31_536_000);
+    assert_eq!(client.get_pending_rewards(&staker), 120_000_000);
+
+    // Emission shares follow boosted weight: 2,000 vs 1,000
+    let staker2 = Address::generate(&env);
+    staking_admin_client.mint(&staker2, &1_000_000_000);
+    client.stake(&staker2, &1_000_000_000);
+    assert_eq!(client.get_total_weight(), 3_000_000_000);
+
+    client.set_reward_mode(&admin, &RewardMode::Emission);
+    reward_admin_client.mint(&admin, &3_000);
+    client.fund_reward_schedule(&admin, &reward_token_client.address, &3_000, &100);
+
+    env.ledger().set_timestamp(31_536_000 + 100);
+    assert_eq!(client.get_pending_emissions(&staker, &reward_token_client.address), 2_000);
+    assert_eq!(client.get_pending_emissions(&staker2, &reward_token_client.address), 1_000);
+
+    // APY earned before the switch is kept, nothing more accrues
+    assert_eq!(client.get_pending_rewards(&staker), 120_000_000);
+    assert_eq!(client.get_position(&id).unwrap().boost_bps, 20_000);
+
+    // Admin can retune the boosts on offer
+    client.set_lock_option(&admin, &(365 * day), &25_000);
+    client.remove_lock_option(&admin, &(30 * day));
+    let options = client.get_lock_options();
+    assert_eq!(options.len(), 2);
+    assert_eq!(options.get(1).unwrap(), LockOption { duration: 365 * day, boost_bps: 25_000 });
+}
+
+fn setup_vault(env: &Env) -> (StakingContractClient<'_>, Address, TokenClient<'_>, StellarAssetClient<'_>) {
+    let admin = Address::generate(env);
+    let token_admin = Address::generate(env);
+    let (token_addr, token_client) = create_token_contract(env, &token_admin);
+    let token_admin_client = StellarAssetClient::new(env, &token_addr);
+
+    let contract_id = env.register_contract(None, StakingContract);
+    let client = StakingContractClient::new(env, &contract_id);
+
+    // Rewards paid in the staking token, 5% base APY, 7 day lock
+    client.initialize(&admin, &token_addr, &token_addr, &500u32, &(7 * 24 * 60 * 60u64));
+    client.configure_vault(&admin, &100); // 1% keeper fee
+
+    (client, admin, token_client, token_admin_client)
+}
+
+#[test]
+fn test_vault_harvest_compounds() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(0);
+
+    let (client, admin, token_client, token_admin_client) = setup_vault(&env);
+    let user = Address::generate(&env);
+    let user2 = Address::generate(&env);
+    let keeper = Address::generate(&env);
+
+    token_admin_client.mint(&admin, &1_000_000_000);
+    client.add_rewards(&admin, &1_000_000_000);
+
+    token_admin_client.mint(&user, &1_000_000_000);
+    assert_eq!(client.vault_deposit(&user, &1_000_000_000), 1_000_000_000);
+    assert_eq!(client.get_vault_share_price(), 1_000_000_000);
+
+    // One year at Bronze APY (6%): 60 tokens harvested, 1% to the keeper
+    env.ledger().set_timestamp(31_536_000);
+    assert_eq!(client.harvest(&keeper), 59_400_000);
+    assert_eq!(token_client.balance(&keeper), 600_000);
+    assert_eq!(client.get_vault_assets(), 1_059_400_000);
+    assert_eq!(client.get_vault_share_price(), 1_059_400_000);
+    assert!(client.try_harvest(&keeper).is_err());
+
+    // New deposits buy in at the compounded price
+    token_admin_client.mint(&user2, &529_700_000);
+    assert_eq!(client.vault_deposit(&user2, &529_700_000), 500_000_000);
+
+    // Shares are transferable
+    client.transfer_vault_shares(&user, &user2, &500_000_000);
+    assert_eq!(client.get_vault_position(&user).shares, 500_000_000);
+    assert_eq!(client.get_vault_position(&user2).shares, 1_000_000_000);
+
+    // user2's position is inside its lock period: 10% penalty stays in the vault
+    assert_eq!(client.vault_withdraw(&user2, &500_000_000), 476_730_000);
+    assert!(client.get_vault_share_price() > 1_059_400_000);
+
+    env.ledger().set_timestamp(31_536_000 + 7 * 24 * 60 * 60);
+    let out = client.vault_withdraw(&user, &500_000_000);
+    assert!(out > 529_700_000);
+    assert_eq!(client.get_vault_position(&user).shares, 0);
+}
+
+#[test]
+fn test_vault_self_transfer_rejected() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _, _, token_admin_client) = setup_vault(&env);
+    let user = Address::generate(&env);
+
+    token_admin_client.mint(&user, &1_000);
+    client.vault_deposit(&user, &1_000);
+
+    assert!(client.try_transfer_vault_shares(&user, &user, &1_000).is_err());
+    assert_eq!(client.get_vault_position(&user).shares, 1_000);
+    assert_eq!(client.get_vault_assets(), 1_000);
+}
+
+#[test]
+fn test_last_vault_withdrawal_penalty_goes_to_reward_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(0);
+
+    let (client, _, token_client, token_admin_client) = setup_vault(&env);
+    let user = Address::generate(&env);
+    let next = Address::generate(&env);
+
+    token_admin_client.mint(&user, &1_000);
+    client.vault_deposit(&user, &1_000);
+
+    // Leaving inside the lock period: the 10% penalty can't stay behind without shares
+    assert_eq!(client.vault_withdraw(&user, &1_000), 900);
+    assert_eq!(client.get_vault_assets(), 0);
+    assert_eq!(client.get_reward_pool(), 100);
+    assert_eq!(client.get_total_staked(), 0);
+
+    // The next depositor only gets back what they put in
+    token_admin_client.mint(&next, &1_000);
+    assert_eq!(client.vault_deposit(&next, &1_000), 1_000);
+    env.ledger().set_timestamp(7 * 24 * 60 * 60);
+    assert_eq!(client.vault_withdraw(&next, &1_000), 1_000);
+    assert_eq!(token_client.balance(&next), 1_000);
+}
+
+#[test]
+fn test_vault_sits_out_other_reward_schedules() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(0);
+
+    let (client, admin, token_client, token_admin_client) = setup_vault(&env);
+    let staker = Address::generate(&env);
+    let depositor = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    let (bonus_token, bonus_client) = create_token_contract(&env, &Address::generate(&env));
+    client.set_reward_mode(&admin, &RewardMode::Emission);
+
+    token_admin_client.mint(&staker, &1_000);
+    token_admin_client.mint(&depositor, &1_000);
+    client.stake(&staker, &1_000);
+    client.vault_deposit(&depositor, &1_000);
+
+    // Staking-token emissions are shared with the vault, bonus emissions are not
+    token_admin_client.mint(&admin, &2_000);
+    client.fund_reward_schedule(&admin, &token_client.address, &2_000, &1_000);
+    StellarAssetClient::new(&env, &bonus_token).mint(&admin, &10_000);
+    client.fund_reward_schedule(&admin, &bonus_token, &10_000, &1_000);
+
+    env.ledger().set_timestamp(1_000);
+    assert_eq!(client.get_pending_emissions(&staker, &token_client.address), 1_000);
+    assert_eq!(client.get_pending_emissions(&staker, &bonus_token), 10_000);
+    assert_eq!(client.get_pending_emissions(&client.address, &bonus_token), 0);
+
+    // The vault compounds its staking-token share, less the 1% keeper fee
+    assert_eq!(client.harvest(&keeper), 990);
+
+    // Every bonus token emitted can be claimed
+    client.claim_emissions(&staker);
+    assert_eq!(bonus_client.balance(&staker), 10_000);
+    assert_eq!(bonus_client.balance(&client.address), 0);
+}
+
+#[test]
+fn test_vault_requires_matching_tokens() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin, staker, _, _, _, staking_admin_client, _) = setup_staking_contract(&env);
+
+    assert!(client.try_configure_vault(&admin, &100).is_err());
+
+    staking_admin_client.mint(&staker, &1_000);
+    assert!(client.try_vault_deposit(&staker, &1_000).is_err());
+}
+
+#[test]
+fn test_delegation_commission_and_totals() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(0);
+
+    let (client, admin, staker, _, _, reward_token_client, staking_admin_client, reward_admin_client) =
+        setup_staking_contract(&env);
+    let guild = Address::generate(&env);
+    let day = 86_400u64;
+
+    reward_admin_client.mint(&admin, &1_000_000_000_000);
+    client.add_rewards(&admin, &1_000_000_000_000);
+
+    client.set_commission(&guild, &1_000); // 10%
+    assert!(client.try_set_commission(&guild, &6_000).is_err());
+
+    staking_admin_client.mint(&staker, &2_000_000_000);
+    client.stake(&staker, &1_000_000_000);
+    client.delegate(&staker, &guild);
+    assert_eq!(client.get_delegation(&staker), Some(guild.clone()));
+    assert_eq!(client.get_delegated_total(&guild), 1_000_000_000);
+
+    // Locked positions count toward the backing too
+    client.stake_locked(&staker, &1_000_000_000, &(30 * day));
+    assert_eq!(client.get_delegated_total(&guild), 2_000_000_000);
+    assert_eq!(client.get_delegation_target(&guild).delegators, 1);
+
+    // One year at Bronze (6%): flexible earns 60, the 1.1x position 66;
+    // the guild keeps 10%.
+    env.ledger().set_timestamp(365 * day);
+    assert_eq!(client.get_pending_rewards(&staker), 113_400_000);
+
+    assert_eq!(client.claim_rewards(&staker), 113_400_000);
+    assert_eq!(client.claim_commission(&guild), 12_600_000);
+    assert_eq!(reward_token_client.balance(&guild), 12_600_000);
+
+    // Undelegating removes the backing; later rewards are commission free
+    client.undelegate(&staker);
+    assert_eq!(client.get_delegated_total(&guild), 0);
+    assert_eq!(client.get_delegation_target(&guild).delegators, 0);
+    env.ledger().set_timestamp(2 * 365 * day);
+    assert_eq!(client.get_pending_rewards(&staker), 126_000_000);
+    assert!(client.try_claim_commission(&guild).is_err());
+}
+
+#[test]
+fn test_delegating_later_settles_earlier_rewards() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(0);
+
+    let (client, admin, staker, _, _, _, staking_admin_client, reward_admin_client) =
+        setup_staking_contract(&env);
+    let guild = Address::generate(&env);
+    let year = 365 * 86_400u64;
+
+    reward_admin_client.mint(&admin, &1_000_000_000_000);
+    client.add_rewards(&admin, &1_000_000_000_000);
+    client.set_commission(&guild, &1_000); // 10%
+
+    staking_admin_client.mint(&staker, &1_000_000_000);
+    client.stake(&staker, &1_000_000_000);
+
+    // The first year was earned undelegated and is commission free
+    env.ledger().set_timestamp(year);
+    client.delegate(&staker, &guild);
+    assert_eq!(client.get_pending_rewards(&staker), 60_000_000);
+
+    // Only the second year pays the guild its 10%
+    env.ledger().set_timestamp(2 * year);
+    assert_eq!(client.claim_rewards(&staker), 114_000_000);
+    assert_eq!(client.claim_commission(&guild), 6_000_000);
+}
+
+#[test]
+fn test_commission_change_not_retroactive() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(0);
+
+    let (client, admin, staker, _, _, reward_token_client, staking_admin_client, reward_admin_client) =
+        setup_staking_contract(&env);
+    let creator = Address::generate(&env);
+
+    client.set_commission(&creator, &1_000); // 10%
+    client.set_reward_mode(&admin, &RewardMode::Emission);
+
+    staking_admin_client.mint(&staker, &1_000);
+    client.stake(&staker, &1_000);
+    client.delegate(&staker, &creator);
+
+    reward_admin_client.mint(&admin, &1_000);
+    client.fund_reward_schedule(&admin, &reward_token_client.address, &1_000, &100);
+
+    // The first half is settled at 10% before the rate rises to 50%
+    env.ledger().set_timestamp(50);
+    client.set_commission(&creator, &5_000);
+    assert_eq!(client.get_pending_emissions(&staker, &reward_token_client.address), 450);
+
+    env.ledger().set_timestamp(100);
+    assert_eq!(client.get_pending_emissions(&staker, &reward_token_client.address), 700);
+    // The creator's cut is credited when the delegator next settles
+    assert_eq!(client.get_pending_emissions(&creator, &reward_token_client.address), 0);
+
+    client.claim_emissions(&staker);
+    client.claim_emissions(&creator);
+    assert_eq!(reward_token_client.balance(&staker), 700);
+    assert_eq!(reward_token_client.balance(&creator), 300);
+}
+
+#[test]
+fn test_commission_changes_apply_per_period_without_settling_delegators() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(0);
+
+    let (client, admin, staker, _, _, _, staking_admin_client, reward_admin_client) =
+        setup_staking_contract(&env);
+    let other = Address::generate(&env);
+    let guild = Address::generate(&env);
+    let year = 365 * 86_400u64;
+
+    reward_admin_client.mint(&admin, &1_000_000_000_000);
+    client.add_rewards(&admin, &1_000_000_000_000);
+    client.set_commission(&guild, &1_000); // 10%
+
+    for delegator in [&staker, &other] {
+        staking_admin_client.mint(delegator, &1_000_000_000);
+        client.stake(delegator, &1_000_000_000);
+        client.delegate(delegator, &guild);
+    }
+
+    // Neither delegator settles between the changes: 60 a year at 10%, 50%, then 0%
+    env.ledger().set_timestamp(year);
+    client.set_commission(&guild, &5_000);
+    env.ledger().set_timestamp(2 * year);
+    client.set_commission(&guild, &0);
+    env.ledger().set_timestamp(3 * year);
+
+    assert_eq!(client.get_pending_rewards(&staker), 144_000_000);
+    assert_eq!(client.claim_rewards(&staker), 144_000_000);
+    assert_eq!(client.claim_rewards(&other), 144_000_000);
+    assert_eq!(client.claim_commission(&guild), 72_000_000);
+}
+
+#[test]
+fn test_delegation_emission_commission() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(0);
+
+    let (client, admin, staker, _, _, reward_token_client, staking_admin_client, reward_admin_client) =
+        setup_staking_contract(&env);
+    let creator = Address::generate(&env);
+
+    client.set_commission(&creator, &2_000); // 20%
+    client.set_reward_mode(&admin, &RewardMode::Emission);
+
+    staking_admin_client.mint(&staker, &1_000);
+    client.stake(&staker, &1_000);
+    client.delegate(&staker, &creator);
+    assert!(client.try_delegate(&staker, &staker).is_err());
+
+    reward_admin_client.mint(&admin, &1_000);
+    client.fund_reward_schedule(&admin, &reward_token_client.address, &1_000, &100);
+
+    env.ledger().set_timestamp(100);
+    assert_eq!(client.get_pending_emissions(&staker, &reward_token_client.address), 800);
+
+    client.claim_emissions(&staker);
+    client.claim_emissions(&creator);
+    assert_eq!(reward_token_client.balance(&staker), 800);
+    assert_eq!(reward_token_client.balance(&creator), 200);
+
+    // Emergency exit drops the delegation along with the stake
+    client.emergency_withdraw(&staker);
+    assert_eq!(client.get_delegated_total(&creator), 0);
+    assert_eq!(client.get_delegation(&staker), None);
+}
@@ -1,6 +1,6 @@
 #![no_std]
 
-use soroban_sdk::{contract, contractimpl, contracttype, token, Address, Env, Vec};
+use soroban_sdk::{contract, contractimpl, contracttype, symbol_short, token, Address, Env, Symbol, Vec};
 
 //
 // ──────────────────────────────────────────────────────────
@@ -18,6 +18,15 @@ const GRACE_PERIOD_SECONDS: u64 = 259_200; // 3 days
 #[cfg(test)]
 const GRACE_PERIOD_SECONDS: u64 = 3;
 
+#[cfg(not(test))]
+const DEFAULT_TRIAL_SECONDS: u64 = 604_800; // 7 days
+#[cfg(test)]
+const DEFAULT_TRIAL_SECONDS: u64 = 5;
+
+const BASIS_POINTS: i128 = 10_000;
+const DEFAULT_KEEPER_FEE_BPS: u32 = 100; // 1% of each renewal
+const MAX_KEEPER_FEE_BPS: u32 = 1_000;
+
 //
 // ──────────────────────────────────────────────────────────
 // SUBSCRIPTION TIERS
@@ -32,6 +41,31 @@ pub enum SubscriptionTier {
     Enterprise = 3,
 }
 
+/// Billing period; the value is its length in months
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BillingPeriod {
+    Monthly = 1,
+    Quarterly = 3,
+    Annual = 12,
+}
+
+/// What cancelling pays back
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RefundMode {
+    NoRefund = 0,     // Auto-renew stops, access runs to expiry
+    Prorated = 1,     // Unused time is refunded and access ends now
+}
+
+/// How a promo code discounts the period price
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PromoKind {
+    Percent = 0,      // value in basis points
+    Fixed = 1,        // value in payment token units
+}
+
 //
 // ──────────────────────────────────────────────────────────
 // DATA KEYS
@@ -48,6 +82,16 @@ pub enum DataKey {
     UserGroup(Address),         // user -> group_id
     TotalSubscribers,
     TierPrice(SubscriptionTier),
+    BenefitQuota(Symbol, SubscriptionTier), // feature, tier -> u32 per period
+    BenefitUsage(Address, Symbol),          // user, feature -> BenefitUsage
+    BenefitConsumer(Address),               // contracts allowed to meter benefits
+    PeriodDiscount(BillingPeriod),          // discount in basis points
+    RefundPolicy,
+    PendingTier(Address),                   // downgrade applied at next renewal
+    PromoCode(Symbol),
+    PromoRedemption(Address),               // promo still discounting renewals
+    TrialDuration,
+    TrialUsed(Address),
 }
 
 //
@@ -65,6 +109,7 @@ pub struct Config {
     pub premium_price: i128,
     pub enterprise_price: i128,
     pub paused: bool,
+    pub keeper_fee_bps: u32,      // Paid to whoever runs `renew_due`
 }
 
 #[contracttype]
@@ -79,6 +124,45 @@ pub struct Subscription {
     pub benefits_used: u32,
     pub is_gifted: bool,
     pub gifted_by: Option<Address>,
+    pub payment_failed: bool,     // Last keeper renewal couldn't collect payment
+    pub billing_period: BillingPeriod,
+    pub period_price: i128,       // Price of the current period at the current tier
+    pub is_trial: bool,           // Free trial, converts to paid at renewal
+}
+
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct PromoCode {
+    pub kind: PromoKind,
+    pub value: i128,
+    pub periods: u32,             // Billing periods discounted, including the first
+    pub max_uses: u32,
+    pub uses: u32,
+    pub expires_at: u64,          // Last moment the code can be redeemed
+    pub tiers: Vec<SubscriptionTier>, // Empty applies to every tier
+}
+
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct PromoRedemption {
+    pub code: Symbol,
+    pub periods_left: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct RefundPolicy {
+    pub mode: RefundMode,
+    pub cancellation_fee_bps: u32, // Kept from prorated refunds
+}
+
+/// Metered use of one benefit. Usage belongs to the billing period ending at
+/// `period_end` and starts over once the subscription renews.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct BenefitUsage {
+    pub period_end: u64,
+    pub used: u32,
 }
 
 #[contracttype]
@@ -129,6 +213,7 @@ impl SubscriptionContract {
             premium_price,
             enterprise_price,
             paused: false,
+            keeper_fee_bps: DEFAULT_KEEPER_FEE_BPS,
         };
 
         env.storage().persistent().set(&DataKey::Config, &config);
@@ -166,6 +251,20 @@ impl SubscriptionContract {
         env.storage().persistent().set(&DataKey::Config, &config);
     }
 
+    /// Set the share of each keeper renewal paid to the keeper (admin only)
+    pub fn set_keeper_fee(env: Env, admin: Address, keeper_fee_bps: u32) {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin);
+
+        if keeper_fee_bps > MAX_KEEPER_FEE_BPS {
+            panic!("Keeper fee too high");
+        }
+
+        let mut config: Config = env.storage().persistent().get(&DataKey::Config).unwrap();
+        config.keeper_fee_bps = keeper_fee_bps;
+        env.storage().persistent().set(&DataKey::Config, &config);
+    }
+
     /// Withdraw accumulated payments (admin only)
     pub fn withdraw(env: Env, admin: Address, amount: i128) {
         admin.require_auth();
@@ -176,49 +275,197 @@ impl SubscriptionContract {
         token_client.transfer(&env.current_contract_address(), &admin, &amount);
     }
 
+    /// Set the discount for a billing period (admin only)
+    pub fn set_period_discount(env: Env, admin: Address, period: BillingPeriod, discount_bps: u32) {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin);
+
+        if discount_bps as i128 >= BASIS_POINTS {
+            panic!("Discount too high");
+        }
+
+        env.storage().persistent().set(&DataKey::PeriodDiscount(period), &discount_bps);
+    }
+
+    /// Set how cancellations are refunded (admin only)
+    pub fn set_refund_policy(env: Env, admin: Address, mode: RefundMode, cancellation_fee_bps: u32) {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin);
+
+        if cancellation_fee_bps as i128 > BASIS_POINTS {
+            panic!("Fee too high");
+        }
+
+        env.storage().persistent().set(&DataKey::RefundPolicy, &RefundPolicy { mode, cancellation_fee_bps });
+    }
+
+    /// Create a promo code (admin only). Percent codes take `value` in basis
+    /// points, fixed codes in token units. The discount applies to `periods`
+    /// billing periods, starting with the purchase.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_promo_code(
+        env: Env,
+        admin: Address,
+        code: Symbol,
+        kind: PromoKind,
+        value: i128,
+        periods: u32,
+        max_uses: u32,
+        expires_at: u64,
+        tiers: Vec<SubscriptionTier>,
+    ) {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin);
+
+        if env.storage().persistent().has(&DataKey::PromoCode(code.clone())) {
+            panic!("Promo code already exists");
+        }
+
+        if value <= 0 || (kind == PromoKind::Percent && value > BASIS_POINTS) {
+            panic!("Invalid discount");
+        }
+
+        if periods == 0 || max_uses == 0 {
+            panic!("Invalid promo terms");
+        }
+
+        let promo = PromoCode { kind, value, periods, max_uses, uses: 0, expires_at, tiers };
+        env.storage().persistent().set(&DataKey::PromoCode(code), &promo);
+    }
+
+    /// Stop a promo code from being redeemed; existing redemptions keep their discount
+    pub fn disable_promo_code(env: Env, admin: Address, code: Symbol) {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin);
+
+        let mut promo: PromoCode = env.storage().persistent()
+            .get(&DataKey::PromoCode(code.clone()))
+            .expect("Promo code not found");
+
+        promo.expires_at = env.ledger().timestamp().saturating_sub(1);
+        env.storage().persistent().set(&DataKey::PromoCode(code), &promo);
+    }
+
+    /// Set the free trial length (admin only); zero disables trials
+    pub fn set_trial_duration(env: Env, admin: Address, duration: u64) {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin);
+
+        env.storage().persistent().set(&DataKey::TrialDuration, &duration);
+    }
+
     // ───────────── SUBSCRIPTION PURCHASE ─────────────
 
-    /// Purchase a new subscription
+    /// Purchase a new monthly subscription
     pub fn purchase_subscription(
         env: Env,
         user: Address,
         tier: SubscriptionTier,
         auto_renew: bool,
+    ) {
+        Self::purchase_plan(env, user, tier, BillingPeriod::Monthly, auto_renew);
+    }
+
+    /// Purchase a new subscription billed monthly, quarterly or annually.
+    /// Longer periods get the configured discount.
+    pub fn purchase_plan(
+        env: Env,
+        user: Address,
+        tier: SubscriptionTier,
+        billing_period: BillingPeriod,
+        auto_renew: bool,
     ) {
         user.require_auth();
         Self::assert_not_paused(&env);
 
-        // Check if user already has an active subscription
+        env.storage().persistent().remove(&DataKey::PromoRedemption(user.clone()));
+        Self::open_subscription(&env, &user, tier, billing_period, auto_renew);
+    }
+
+    /// Purchase a subscription with a promo code. The code's discount applies
+    /// to this period and to renewals until its period count runs out.
+    pub fn purchase_with_promo(
+        env: Env,
+        user: Address,
+        tier: SubscriptionTier,
+        billing_period: BillingPeriod,
+        auto_renew: bool,
+        code: Symbol,
+    ) {
+        user.require_auth();
+        Self::assert_not_paused(&env);
+
+        let mut promo: PromoCode = env.storage().persistent()
+            .get(&DataKey::PromoCode(code.clone()))
+            .expect("Promo code not found");
+
+        if env.ledger().timestamp() > promo.expires_at {
+            panic!("Promo code expired");
+        }
+
+        if promo.uses >= promo.max_uses {
+            panic!("Promo code fully redeemed");
+        }
+
+        if !promo.tiers.is_empty() && !promo.tiers.contains(tier) {
+            panic!("Promo code not valid for tier");
+        }
+
+        promo.uses += 1;
+        env.storage().persistent().set(&DataKey::PromoCode(code.clone()), &promo);
+        env.storage().persistent().set(
+            &DataKey::PromoRedemption(user.clone()),
+            &PromoRedemption { code: code.clone(), periods_left: promo.periods },
+        );
+
+        Self::open_subscription(&env, &user, tier, billing_period, auto_renew);
+        env.events().publish((symbol_short!("promo"), user), code);
+    }
+
+    /// Start a one-time free trial. With auto-renew on, the first renewal
+    /// charges the monthly price and converts it into a paid subscription.
+    pub fn start_trial(env: Env, user: Address, tier: SubscriptionTier, auto_renew: bool) {
+        user.require_auth();
+        Self::assert_not_paused(&env);
+
+        let duration = Self::get_trial_duration(env.clone());
+        if duration == 0 {
+            panic!("Trials disabled");
+        }
+
+        if env.storage().persistent().has(&DataKey::TrialUsed(user.clone())) {
+            panic!("Trial already used");
+        }
+
         if Self::has_active_subscription(env.clone(), user.clone()) {
             panic!("Already has active subscription");
         }
 
-        let config: Config = env.storage().persistent().get(&DataKey::Config).unwrap();
-        let price = Self::get_tier_price(&tier, &config);
-        
-        // Transfer payment
-        let token_client = token::Client::new(&env, &config.payment_token);
-        token_client.transfer(&user, &env.current_contract_address(), &price);
-
-        // Create subscription
         let current_time = env.ledger().timestamp();
         let subscription = Subscription {
             tier,
             start_time: current_time,
-            expiry_time: current_time + MONTH_IN_SECONDS,
+            expiry_time: current_time + duration,
             auto_renew,
             is_active: true,
             total_renewals: 0,
             benefits_used: 0,
             is_gifted: false,
             gifted_by: None,
+            payment_failed: false,
+            billing_period: BillingPeriod::Monthly,
+            period_price: 0,
+            is_trial: true,
         };
 
         env.storage().persistent().set(&DataKey::Subscription(user.clone()), &subscription);
+        env.storage().persistent().set(&DataKey::TrialUsed(user.clone()), &true);
+        env.storage().persistent().remove(&DataKey::PromoRedemption(user.clone()));
 
-        // Update total subscribers
         let total: u64 = env.storage().persistent().get(&DataKey::TotalSubscribers).unwrap_or(0);
         env.storage().persistent().set(&DataKey::TotalSubscribers, &(total + 1));
+
+        env.events().publish((symbol_short!("trial"), user), subscription.expiry_time);
     }
 
     /// Auto-renew subscription (requires user authorization)
@@ -253,21 +500,114 @@ impl SubscriptionContract {
             panic!("Subscription expired beyond grace period");
         }
 
-        // Process payment
-        let price = Self::get_tier_price(&subscription.tier, &config);
+        // Process payment, at the downgraded tier if one is pending
+        let next_tier = Self::get_pending_tier(env.clone(), user.clone()).unwrap_or(subscription.tier);
+        let price = Self::get_period_price(env.clone(), next_tier, subscription.billing_period);
+        let price = Self::apply_promo(&env, &user, next_tier, price);
         let token_client = token::Client::new(&env, &config.payment_token);
         token_client.transfer(&user, &env.current_contract_address(), &price);
 
         // Renew subscription
-        subscription.expiry_time = current_time + MONTH_IN_SECONDS;
+        subscription.tier = next_tier;
+        subscription.period_price = price;
+        env.storage().persistent().remove(&DataKey::PendingTier(user.clone()));
+        Self::consume_promo_period(&env, &user);
+        subscription.is_trial = false;
+        subscription.expiry_time = current_time + Self::period_seconds(subscription.billing_period);
         subscription.total_renewals += 1;
+        subscription.benefits_used = 0;
+        subscription.payment_failed = false;
         env.storage().persistent().set(&DataKey::Subscription(user.clone()), &subscription);
     }
 
+    /// Renew every listed subscription that is due, pulling payment from the
+    /// allowance each user granted this contract. Anyone can call; the keeper
+    /// earns `keeper_fee_bps` of what is collected. Users whose allowance or
+    /// balance falls short stay in their grace period. Returns the number renewed.
+    pub fn renew_due(env: Env, keeper: Address, users: Vec<Address>) -> u32 {
+        keeper.require_auth();
+        Self::assert_not_paused(&env);
+
+        let config: Config = env.storage().persistent().get(&DataKey::Config).unwrap();
+        let token_client = token::Client::new(&env, &config.payment_token);
+        let contract = env.current_contract_address();
+        let current_time = env.ledger().timestamp();
+
+        let mut renewed: u32 = 0;
+        let mut collected: i128 = 0;
+
+        for user in users.iter() {
+            let key = DataKey::Subscription(user.clone());
+            let mut subscription: Subscription = match env.storage().persistent().get(&key) {
+                Some(subscription) => subscription,
+                None => continue,
+            };
+
+            if !subscription.auto_renew || !subscription.is_active || current_time < subscription.expiry_time {
+                continue;
+            }
+
+            if current_time > subscription.expiry_time + GRACE_PERIOD_SECONDS {
+                subscription.is_active = false;
+                env.storage().persistent().set(&key, &subscription);
+                env.events().publish((symbol_short!("expired"), user), subscription.expiry_time);
+                continue;
+            }
+
+            let next_tier = Self::get_pending_tier(env.clone(), user.clone()).unwrap_or(subscription.tier);
+            let price = Self::get_period_price(env.clone(), next_tier, subscription.billing_period);
+            let price = Self::apply_promo(&env, &user, next_tier, price);
+            let failure = if token_client.allowance(&user, &contract) < price {
+                Some(symbol_short!("allowance"))
+            } else if token_client.balance(&user) < price {
+                Some(symbol_short!("balance"))
+            } else {
+                None
+            };
+
+            if let Some(reason) = failure {
+                subscription.payment_failed = true;
+                env.storage().persistent().set(&key, &subscription);
+                env.events().publish(
+                    (symbol_short!("rnw_fail"), user),
+                    (reason, subscription.expiry_time + GRACE_PERIOD_SECONDS),
+                );
+                continue;
+            }
+
+            token_client.transfer_from(&contract, &user, &contract, &price);
+            collected += price;
+
+            // Extend from the old expiry so a late keeper doesn't cost the user time
+            subscription.tier = next_tier;
+            subscription.period_price = price;
+            env.storage().persistent().remove(&DataKey::PendingTier(user.clone()));
+            Self::consume_promo_period(&env, &user);
+            subscription.is_trial = false;
+            subscription.expiry_time += Self::period_seconds(subscription.billing_period);
+            subscription.total_renewals += 1;
+            subscription.benefits_used = 0;
+            subscription.payment_failed = false;
+            env.storage().persistent().set(&key, &subscription);
+            env.events().publish((symbol_short!("renewed"), user), subscription.expiry_time);
+            renewed += 1;
+        }
+
+        let fee = collected * config.keeper_fee_bps as i128 / BASIS_POINTS;
+        if fee > 0 {
+            token_client.transfer(&contract, &keeper, &fee);
+        }
+
+        renewed
+    }
+
     // ───────────── SUBSCRIPTION MANAGEMENT ─────────────
 
-    /// Cancel subscription (stops auto-renewal, keeps benefits until expiry)
-    pub fn cancel_subscription(env: Env, user: Address) {
+    /// Cancel subscription. Under the default policy this stops auto-renewal and
+    /// keeps benefits until expiry; under a prorated policy the unused time is
+    /// refunded, less the cancellation fee, and access ends now.
+    /// Returns the amount refunded.
+    pub fn cancel_subscription(env: Env, user: Address) -> i128 {
         user.require_auth();
 
         let mut subscription: Subscription = env.storage().persistent()
@@ -275,7 +615,29 @@ impl SubscriptionContract {
             .expect("No subscription found");
 
         subscription.auto_renew = false;
+        env.storage().persistent().remove(&DataKey::PendingTier(user.clone()));
+
+        let policy = Self::get_refund_policy(env.clone());
+        let current_time = env.ledger().timestamp();
+        let mut refund: i128 = 0;
+
+        // Gifts aren't refunded to the recipient
+        if policy.mode == RefundMode::Prorated && !subscription.is_gifted && subscription.is_active {
+            let unused = Self::unused_value(&subscription, current_time);
+            refund = unused - unused * policy.cancellation_fee_bps as i128 / BASIS_POINTS;
+
+            subscription.is_active = false;
+            subscription.expiry_time = subscription.expiry_time.min(current_time);
+
+            if refund > 0 {
+                let config: Config = env.storage().persistent().get(&DataKey::Config).unwrap();
+                let token_client = token::Client::new(&env, &config.payment_token);
+                token_client.transfer(&env.current_contract_address(), &user, &refund);
+            }
+        }
+
         env.storage().persistent().set(&DataKey::Subscription(user.clone()), &subscription);
+        refund
     }
 
     /// Toggle auto-renewal
@@ -303,6 +665,15 @@ impl SubscriptionContract {
             panic!("Subscription not active");
         }
 
+        if subscription.is_trial {
+            panic!("Cannot upgrade during trial");
+        }
+
+        // Nothing is left of the paid period to prorate against; renew first
+        if env.ledger().timestamp() >= subscription.expiry_time {
+            panic!("Cannot upgrade during grace period");
+        }
+
         let old_tier_value = subscription.tier as u32;
         let new_tier_value = new_tier as u32;
 
@@ -311,23 +682,47 @@ impl SubscriptionContract {
         }
 
         let config: Config = env.storage().persistent().get(&DataKey::Config).unwrap();
-        let old_price = Self::get_tier_price(&subscription.tier, &config);
-        let new_price = Self::get_tier_price(&new_tier, &config);
-        let price_diff = new_price - old_price;
+        let new_price = Self::get_period_price(env.clone(), new_tier, subscription.billing_period);
+        let new_price = Self::apply_promo(&env, &user, new_tier, new_price);
 
-        // Charge the difference
+        // Charge the difference for the time left in the period
+        let period = Self::period_seconds(subscription.billing_period) as i128;
+        let remaining = subscription.expiry_time.saturating_sub(env.ledger().timestamp()) as i128;
+        let price_diff = (new_price - subscription.period_price) * remaining / period;
         if price_diff > 0 {
             let token_client = token::Client::new(&env, &config.payment_token);
             token_client.transfer(&user, &env.current_contract_address(), &price_diff);
         }
 
         subscription.tier = new_tier;
+        subscription.period_price = new_price;
+        env.storage().persistent().remove(&DataKey::PendingTier(user.clone()));
         env.storage().persistent().set(&DataKey::Subscription(user.clone()), &subscription);
     }
 
+    /// Move to a lower tier from the next renewal; the current period keeps its tier
+    pub fn downgrade_subscription(env: Env, user: Address, new_tier: SubscriptionTier) {
+        user.require_auth();
+        Self::assert_not_paused(&env);
+
+        let subscription: Subscription = env.storage().persistent()
+            .get(&DataKey::Subscription(user.clone()))
+            .expect("No subscription found");
+
+        if !subscription.is_active {
+            panic!("Subscription not active");
+        }
+
+        if new_tier as u32 >= subscription.tier as u32 {
+            panic!("Can only downgrade to lower tier");
+        }
+
+        env.storage().persistent().set(&DataKey::PendingTier(user), &new_tier);
+    }
+
     // ───────────── BENEFITS TRACKING ─────────────
 
-    /// Track benefit usage
+    /// Track benefit usage (resets at each renewal)
     pub fn use_benefit(env: Env, user: Address) {
         user.require_auth();
 
@@ -339,6 +734,10 @@ impl SubscriptionContract {
             panic!("Subscription not valid");
         }
 
+        if subscription.benefits_used >= Self::get_benefit_limit(subscription.tier) {
+            panic!("Benefit limit reached");
+        }
+
         subscription.benefits_used += 1;
         env.storage().persistent().set(&DataKey::Subscription(user.clone()), &subscription);
     }
@@ -352,6 +751,85 @@ impl SubscriptionContract {
         }
     }
 
+    /// Set how much of a named benefit each billing period includes for a tier (admin only)
+    pub fn set_benefit_quota(env: Env, admin: Address, feature: Symbol, tier: SubscriptionTier, quota: u32) {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin);
+
+        env.storage().persistent().set(&DataKey::BenefitQuota(feature, tier), &quota);
+    }
+
+    /// Allow or revoke a contract's right to meter benefits (admin only)
+    pub fn set_benefit_consumer(env: Env, admin: Address, consumer: Address, allowed: bool) {
+        admin.require_auth();
+        Self::assert_admin(&env, &admin);
+
+        if allowed {
+            env.storage().persistent().set(&DataKey::BenefitConsumer(consumer), &true);
+        } else {
+            env.storage().persistent().remove(&DataKey::BenefitConsumer(consumer));
+        }
+    }
+
+    /// Consume `qty` of a user's benefit for the current billing period.
+    /// Only allowlisted contracts (e.g. hint marketplace, energy) may call this.
+    /// Returns what remains of the quota.
+    pub fn consume_benefit(env: Env, consumer: Address, user: Address, feature: Symbol, qty: u32) -> u32 {
+        consumer.require_auth();
+        Self::assert_not_paused(&env);
+
+        if !env.storage().persistent().has(&DataKey::BenefitConsumer(consumer)) {
+            panic!("Consumer not allowed");
+        }
+
+        if qty == 0 {
+            panic!("Quantity must be positive");
+        }
+
+        let (tier, period_end) = Self::current_period(&env, &user).expect("Subscription not valid");
+        let quota = Self::get_benefit_quota(env.clone(), feature.clone(), tier);
+        let mut usage = Self::current_usage(&env, &user, &feature, period_end);
+
+        if usage.used + qty > quota {
+            panic!("Benefit quota exceeded");
+        }
+
+        usage.used += qty;
+        env.storage().persistent().set(&DataKey::BenefitUsage(user.clone(), feature.clone()), &usage);
+        env.events().publish((symbol_short!("benefit"), user, feature), qty);
+
+        quota - usage.used
+    }
+
+    /// Get a benefit's per-period quota for a tier
+    pub fn get_benefit_quota(env: Env, feature: Symbol, tier: SubscriptionTier) -> u32 {
+        env.storage().persistent().get(&DataKey::BenefitQuota(feature, tier)).unwrap_or(0)
+    }
+
+    /// Get how much of a benefit the user has used this billing period
+    pub fn get_benefit_used(env: Env, user: Address, feature: Symbol) -> u32 {
+        match Self::current_period(&env, &user) {
+            Some((_, period_end)) => Self::current_usage(&env, &user, &feature, period_end).used,
+            None => 0,
+        }
+    }
+
+    /// Get how much of a benefit the user has left this billing period
+    pub fn get_benefit_remaining(env: Env, user: Address, feature: Symbol) -> u32 {
+        match Self::current_period(&env, &user) {
+            Some((tier, period_end)) => {
+                let quota = Self::get_benefit_quota(env.clone(), feature.clone(), tier);
+                quota.saturating_sub(Self::current_usage(&env, &user, &feature, period_end).used)
+            }
+            None => 0,
+        }
+    }
+
+    /// Check whether a contract may meter benefits
+    pub fn is_benefit_consumer(env: Env, consumer: Address) -> bool {
+        env.storage().persistent().has(&DataKey::BenefitConsumer(consumer))
+    }
+
     // ───────────── GROUP SUBSCRIPTIONS ─────────────
 
     /// Create a family/group subscription
@@ -365,7 +843,7 @@ impl SubscriptionContract {
         owner.require_auth();
         Self::assert_not_paused(&env);
 
-        if max_members < 2 || max_members > 10 {
+        if !(2..=10).contains(&max_members) {
             panic!("Max members must be between 2 and 10");
         }
 
@@ -513,9 +991,14 @@ impl SubscriptionContract {
             benefits_used: 0,
             is_gifted: true,
             gifted_by: Some(gifter),
+            payment_failed: false,
+            billing_period: BillingPeriod::Monthly,
+            period_price: price,
+            is_trial: false,
         };
 
         env.storage().persistent().set(&DataKey::Subscription(recipient.clone()), &subscription);
+        env.storage().persistent().remove(&DataKey::PromoRedemption(recipient.clone()));
 
         // Update total subscribers
         let total: u64 = env.storage().persistent().get(&DataKey::TotalSubscribers).unwrap_or(0);
@@ -586,6 +1069,68 @@ impl SubscriptionContract {
         env.storage().persistent().get(&DataKey::TotalSubscribers).unwrap_or(0)
     }
 
+    /// Price of one billing period at a tier, after the period discount
+    pub fn get_period_price(env: Env, tier: SubscriptionTier, period: BillingPeriod) -> i128 {
+        let config: Config = env.storage().persistent().get(&DataKey::Config).unwrap();
+        let discount = Self::get_period_discount(env, period) as i128;
+        let full_price = Self::get_tier_price(&tier, &config) * period as i128;
+        full_price - full_price * discount / BASIS_POINTS
+    }
+
+    /// Discount for a billing period in basis points (defaults: 5% quarterly, 15% annual)
+    pub fn get_period_discount(env: Env, period: BillingPeriod) -> u32 {
+        env.storage().persistent().get(&DataKey::PeriodDiscount(period)).unwrap_or(match period {
+            BillingPeriod::Monthly => 0,
+            BillingPeriod::Quarterly => 500,
+            BillingPeriod::Annual => 1_500,
+        })
+    }
+
+    /// Tier a scheduled downgrade will switch to at the next renewal
+    pub fn get_pending_tier(env: Env, user: Address) -> Option<SubscriptionTier> {
+        env.storage().persistent().get(&DataKey::PendingTier(user))
+    }
+
+    /// Get a promo code's terms and redemption count
+    pub fn get_promo_code(env: Env, code: Symbol) -> Option<PromoCode> {
+        env.storage().persistent().get(&DataKey::PromoCode(code))
+    }
+
+    /// Promo still discounting a user's renewals, if any
+    pub fn get_promo_redemption(env: Env, user: Address) -> Option<PromoRedemption> {
+        env.storage().persistent().get(&DataKey::PromoRedemption(user))
+    }
+
+    /// Free trial length in seconds (7 days by default)
+    pub fn get_trial_duration(env: Env) -> u64 {
+        env.storage().persistent().get(&DataKey::TrialDuration).unwrap_or(DEFAULT_TRIAL_SECONDS)
+    }
+
+    /// Check if an address has already had its free trial
+    pub fn has_used_trial(env: Env, user: Address) -> bool {
+        env.storage().persistent().has(&DataKey::TrialUsed(user))
+    }
+
+    /// Get the cancellation refund policy
+    pub fn get_refund_policy(env: Env) -> RefundPolicy {
+        env.storage().persistent().get(&DataKey::RefundPolicy).unwrap_or(RefundPolicy {
+            mode: RefundMode::NoRefund,
+            cancellation_fee_bps: 0,
+        })
+    }
+
+    /// Amount cancelling now would refund under the current policy
+    pub fn get_refund_quote(env: Env, user: Address) -> i128 {
+        let policy = Self::get_refund_policy(env.clone());
+        match Self::get_subscription(env.clone(), user) {
+            Some(sub) if policy.mode == RefundMode::Prorated && !sub.is_gifted && sub.is_active => {
+                let unused = Self::unused_value(&sub, env.ledger().timestamp());
+                unused - unused * policy.cancellation_fee_bps as i128 / BASIS_POINTS
+            }
+            _ => 0,
+        }
+    }
+
     /// Get configuration
     pub fn get_config(env: Env) -> Config {
         env.storage().persistent().get(&DataKey::Config).unwrap()
@@ -607,11 +1152,7 @@ impl SubscriptionContract {
     pub fn get_time_until_expiry(env: Env, user: Address) -> u64 {
         if let Some(sub) = Self::get_subscription(env.clone(), user) {
             let current_time = env.ledger().timestamp();
-            if current_time >= sub.expiry_time {
-                0
-            } else {
-                sub.expiry_time - current_time
-            }
+            sub.expiry_time.saturating_sub(current_time)
         } else {
             0
         }
@@ -639,6 +1180,123 @@ impl SubscriptionContract {
         current_time <= group_sub.expiry_time + GRACE_PERIOD_SECONDS
     }
 
+    /// The tier and billing period end that benefits are metered against,
+    /// from the user's own subscription or else their group's
+    fn current_period(env: &Env, user: &Address) -> Option<(SubscriptionTier, u64)> {
+        if let Some(sub) = Self::get_subscription(env.clone(), user.clone()) {
+            if Self::is_subscription_valid(env, &sub) {
+                return Some((sub.tier, sub.expiry_time));
+            }
+        }
+
+        if let Some(group_id) = env.storage().persistent().get::<DataKey, u64>(&DataKey::UserGroup(user.clone())) {
+            if let Some(group_sub) = env.storage().persistent().get::<DataKey, GroupSubscription>(&DataKey::GroupSubscription(group_id)) {
+                if Self::is_group_subscription_valid(env, &group_sub) {
+                    return Some((group_sub.tier, group_sub.expiry_time));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn current_usage(env: &Env, user: &Address, feature: &Symbol, period_end: u64) -> BenefitUsage {
+        match env.storage().persistent().get::<DataKey, BenefitUsage>(&DataKey::BenefitUsage(user.clone(), feature.clone())) {
+            Some(usage) if usage.period_end == period_end => usage,
+            _ => BenefitUsage { period_end, used: 0 },
+        }
+    }
+
+    /// Charge for and store a fresh subscription, applying any redeemed promo
+    fn open_subscription(
+        env: &Env,
+        user: &Address,
+        tier: SubscriptionTier,
+        billing_period: BillingPeriod,
+        auto_renew: bool,
+    ) {
+        // Check if user already has an active subscription
+        if Self::has_active_subscription(env.clone(), user.clone()) {
+            panic!("Already has active subscription");
+        }
+
+        let config: Config = env.storage().persistent().get(&DataKey::Config).unwrap();
+        let price = Self::get_period_price(env.clone(), tier, billing_period);
+        let price = Self::apply_promo(env, user, tier, price);
+
+        // Transfer payment
+        let token_client = token::Client::new(env, &config.payment_token);
+        token_client.transfer(user, &env.current_contract_address(), &price);
+
+        // Create subscription
+        let current_time = env.ledger().timestamp();
+        let subscription = Subscription {
+            tier,
+            start_time: current_time,
+            expiry_time: current_time + Self::period_seconds(billing_period),
+            auto_renew,
+            is_active: true,
+            total_renewals: 0,
+            benefits_used: 0,
+            is_gifted: false,
+            gifted_by: None,
+            payment_failed: false,
+            billing_period,
+            period_price: price,
+            is_trial: false,
+        };
+
+        env.storage().persistent().set(&DataKey::Subscription(user.clone()), &subscription);
+        Self::consume_promo_period(env, user);
+
+        // Update total subscribers
+        let total: u64 = env.storage().persistent().get(&DataKey::TotalSubscribers).unwrap_or(0);
+        env.storage().persistent().set(&DataKey::TotalSubscribers, &(total + 1));
+    }
+
+    /// Price after the user's redeemed promo, if it still has periods left and covers the tier
+    fn apply_promo(env: &Env, user: &Address, tier: SubscriptionTier, price: i128) -> i128 {
+        let redemption: PromoRedemption = match env.storage().persistent().get(&DataKey::PromoRedemption(user.clone())) {
+            Some(redemption) => redemption,
+            None => return price,
+        };
+        let promo: PromoCode = env.storage().persistent().get(&DataKey::PromoCode(redemption.code)).unwrap();
+
+        if !promo.tiers.is_empty() && !promo.tiers.contains(tier) {
+            return price;
+        }
+
+        let discount = match promo.kind {
+            PromoKind::Percent => price * promo.value / BASIS_POINTS,
+            PromoKind::Fixed => promo.value.min(price),
+        };
+        price - discount
+    }
+
+    /// Use up one discounted period of the user's promo
+    fn consume_promo_period(env: &Env, user: &Address) {
+        let key = DataKey::PromoRedemption(user.clone());
+        if let Some(mut redemption) = env.storage().persistent().get::<_, PromoRedemption>(&key) {
+            redemption.periods_left -= 1;
+            if redemption.periods_left == 0 {
+                env.storage().persistent().remove(&key);
+            } else {
+                env.storage().persistent().set(&key, &redemption);
+            }
+        }
+    }
+
+    fn period_seconds(period: BillingPeriod) -> u64 {
+        MONTH_IN_SECONDS * period as u64
+    }
+
+    /// Value of the time left in the current period at what it was paid
+    fn unused_value(subscription: &Subscription, current_time: u64) -> i128 {
+        let period = Self::period_seconds(subscription.billing_period) as i128;
+        let remaining = subscription.expiry_time.saturating_sub(current_time) as i128;
+        subscription.period_price * remaining.min(period) / period
+    }
+
     fn get_tier_price(tier: &SubscriptionTier, config: &Config) -> i128 {
         match tier {
             SubscriptionTier::Basic => config.basic_price,
@@ -671,8 +1329,9 @@ impl SubscriptionContract {
 #[cfg(test)]
 mod test {
     use super::*;
-    use soroban_sdk::testutils::{Address as _, Ledger};
+    use soroban_sdk::testutils::{Address as _, Events, Ledger};
     use soroban_sdk::token::StellarAssetClient;
+    use soroban_sdk::{IntoVal, Val};
 
     fn create_token_contract(env: &Env, admin: &Address) -> Address {
         env.register_stellar_asset_contract_v2(admin.clone()).address()
@@ -761,7 +1420,7 @@ mod test {
 
     #[test]
     fn test_subscription_validity() {
-        let (env, _admin, _payment_token, user, token_admin_client, client) = setup();
+        let (env, _admin, _payment_token, user, _token_admin_client, client) = setup();
 
         client.purchase_subscription(&user, &SubscriptionTier::Basic, &true);
         assert!(client.has_active_subscription(&user));
@@ -777,7 +1436,7 @@ mod test {
 
     #[test]
     fn test_grace_period() {
-        let (env, _admin, _payment_token, user, token_admin_client, client) = setup();
+        let (env, _admin, _payment_token, user, _token_admin_client, client) = setup();
 
         client.purchase_subscription(&user, &SubscriptionTier::Basic, &true);
 
@@ -800,7 +1459,7 @@ mod test {
 
     #[test]
     fn test_auto_renewal() {
-        let (env, _admin, _payment_token, user, token_admin_client, client) = setup();
+        let (env, _admin, _payment_token, user, _token_admin_client, client) = setup();
 
         client.purchase_subscription(&user, &SubscriptionTier::Basic, &true);
         
@@ -821,7 +1480,7 @@ mod test {
     #[test]
     #[should_panic(expected = "Not yet time to renew")]
     fn test_cannot_renew_before_expiry() {
-        let (env, _admin, _payment_token, user, token_admin_client, client) = setup();
+        let (env, _admin, _payment_token, user, _token_admin_client, client) = setup();
 
         client.purchase_subscription(&user, &SubscriptionTier::Basic, &true);
         
@@ -832,7 +1491,7 @@ mod test {
     #[test]
     #[should_panic(expected = "Auto-renew not enabled")]
     fn test_cannot_auto_renew_when_disabled() {
-        let (env, _admin, _payment_token, user, token_admin_client, client) = setup();
+        let (env, _admin, _payment_token, user, _token_admin_client, client) = setup();
 
         client.purchase_subscription(&user, &SubscriptionTier::Basic, &false);
         
@@ -840,11 +1499,94 @@ mod test {
         client.process_renewal(&user);
     }
 
+    #[test]
+    fn test_keeper_renews_with_allowance() {
+        let (env, _admin, payment_token, user, token_admin_client, client) = setup();
+        let token_client = token::Client::new(&env, &payment_token);
+        let keeper = Address::generate(&env);
+        let no_allowance = Address::generate(&env);
+        let broke = Address::generate(&env);
+
+        token_admin_client.mint(&no_allowance, &1_000_000);
+        token_admin_client.mint(&broke, &1_000_000);
+        client.purchase_subscription(&user, &SubscriptionTier::Basic, &true);
+        client.purchase_subscription(&no_allowance, &SubscriptionTier::Basic, &true);
+        client.purchase_subscription(&broke, &SubscriptionTier::Basic, &true);
+
+        // One-time allowances covering future renewals
+        token_client.approve(&user, &client.address, &10_000_000, &1_000);
+        token_client.approve(&broke, &client.address, &10_000_000, &1_000);
+
+        let start = client.get_subscription(&user).unwrap().start_time;
+        let mut users = Vec::new(&env);
+        users.push_back(user.clone());
+        users.push_back(no_allowance.clone());
+        users.push_back(broke.clone());
+
+        // Nothing is due yet
+        assert_eq!(client.renew_due(&keeper, &users), 0);
+
+        env.ledger().with_mut(|li| li.timestamp += MONTH_IN_SECONDS + 1);
+        assert_eq!(client.renew_due(&keeper, &users), 1);
+
+        let sub = client.get_subscription(&user).unwrap();
+        assert_eq!(sub.total_renewals, 1);
+        assert_eq!(sub.expiry_time, start + 2 * MONTH_IN_SECONDS);
+        assert_eq!(token_client.balance(&user), 100_000_000 - 2_000_000);
+        assert_eq!(token_client.balance(&keeper), 10_000); // 1% of 1 token
+
+        // Missing allowance or balance leaves the subscription in grace
+        assert!(client.get_subscription(&no_allowance).unwrap().payment_failed);
+        assert!(client.get_subscription(&broke).unwrap().payment_failed);
+        assert!(client.is_in_grace_period(&no_allowance));
+
+        // Topping up before grace ends lets the next run succeed
+        token_admin_client.mint(&broke, &1_000_000);
+        assert_eq!(client.renew_due(&keeper, &users), 1);
+        assert!(!client.get_subscription(&broke).unwrap().payment_failed);
+
+        // Past grace the unpaid subscription is closed
+        env.ledger().with_mut(|li| li.timestamp += GRACE_PERIOD_SECONDS);
+        assert_eq!(client.renew_due(&keeper, &users), 0);
+        assert!(!client.get_subscription(&no_allowance).unwrap().is_active);
+        assert!(client.get_subscription(&user).unwrap().is_active);
+    }
+
+    #[test]
+    fn test_keeper_renewal_failure_event() {
+        let (env, _admin, _payment_token, user, _token_admin_client, client) = setup();
+        let keeper = Address::generate(&env);
+
+        client.purchase_subscription(&user, &SubscriptionTier::Premium, &true);
+        env.ledger().with_mut(|li| li.timestamp += MONTH_IN_SECONDS + 1);
+
+        let mut users = Vec::new(&env);
+        users.push_back(user.clone());
+        client.renew_due(&keeper, &users);
+
+        let expected_topics: Vec<Val> = (symbol_short!("rnw_fail"), user.clone()).into_val(&env);
+        let failed = env
+            .events()
+            .all()
+            .iter()
+            .any(|(contract, topics, _)| contract == client.address && topics == expected_topics);
+        assert!(failed);
+    }
+
+    #[test]
+    fn test_set_keeper_fee() {
+        let (_env, admin, _payment_token, _user, _token_admin_client, client) = setup();
+
+        client.set_keeper_fee(&admin, &250);
+        assert_eq!(client.get_config().keeper_fee_bps, 250);
+        assert!(client.try_set_keeper_fee(&admin, &5_000).is_err());
+    }
+
     // ───────────── SUBSCRIPTION MANAGEMENT TESTS ─────────────
 
     #[test]
     fn test_cancel_subscription() {
-        let (env, _admin, _payment_token, user, token_admin_client, client) = setup();
+        let (env, _admin, _payment_token, user, _token_admin_client, client) = setup();
 
         client.purchase_subscription(&user, &SubscriptionTier::Basic, &true);
         
@@ -901,6 +1643,100 @@ mod test {
         client.upgrade_subscription(&user, &SubscriptionTier::Basic);
     }
 
+    // ───────────── BILLING PERIOD TESTS ─────────────
+
+    #[test]
+    fn test_annual_plan_discount() {
+        let (env, admin, payment_token, user, _token_admin_client, client) = setup();
+        let token_client = token::Client::new(&env, &payment_token);
+
+        // 12 months less the default 15%
+        assert_eq!(client.get_period_price(&SubscriptionTier::Basic, &BillingPeriod::Annual), 10_200_000);
+        client.set_period_discount(&admin, &BillingPeriod::Quarterly, &1_000);
+        assert_eq!(client.get_period_price(&SubscriptionTier::Basic, &BillingPeriod::Quarterly), 2_700_000);
+
+        client.purchase_plan(&user, &SubscriptionTier::Basic, &BillingPeriod::Annual, &true);
+        assert_eq!(token_client.balance(&user), 100_000_000 - 10_200_000);
+
+        let sub = client.get_subscription(&user).unwrap();
+        assert_eq!(sub.billing_period, BillingPeriod::Annual);
+        assert_eq!(sub.expiry_time, sub.start_time + MONTH_IN_SECONDS * 12);
+
+        // Renewals keep the annual period and price
+        env.ledger().with_mut(|li| li.timestamp += MONTH_IN_SECONDS * 12 + 1);
+        client.process_renewal(&user);
+        assert_eq!(token_client.balance(&user), 100_000_000 - 20_400_000);
+    }
+
+    #[test]
+    fn test_prorated_upgrade() {
+        let (env, _admin, payment_token, user, _token_admin_client, client) = setup();
+        let token_client = token::Client::new(&env, &payment_token);
+
+        client.purchase_subscription(&user, &SubscriptionTier::Basic, &true);
+        let balance = token_client.balance(&user);
+
+        // Half the month left: pay half the 4 token difference
+        env.ledger().with_mut(|li| li.timestamp += MONTH_IN_SECONDS / 2);
+        client.upgrade_subscription(&user, &SubscriptionTier::Premium);
+
+        assert_eq!(token_client.balance(&user), balance - 2_000_000);
+        let sub = client.get_subscription(&user).unwrap();
+        assert_eq!(sub.period_price, 5_000_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot upgrade during grace period")]
+    fn test_upgrade_rejected_in_grace_period() {
+        let (env, _admin, _payment_token, user, _token_admin_client, client) = setup();
+
+        client.purchase_subscription(&user, &SubscriptionTier::Basic, &false);
+        env.ledger().with_mut(|li| li.timestamp += MONTH_IN_SECONDS + 1);
+        assert!(client.has_active_subscription(&user));
+
+        client.upgrade_subscription(&user, &SubscriptionTier::Premium);
+    }
+
+    #[test]
+    fn test_downgrade_at_next_renewal() {
+        let (env, _admin, payment_token, user, _token_admin_client, client) = setup();
+        let token_client = token::Client::new(&env, &payment_token);
+
+        client.purchase_subscription(&user, &SubscriptionTier::Premium, &true);
+        client.downgrade_subscription(&user, &SubscriptionTier::Basic);
+
+        // Current period keeps the paid tier
+        assert_eq!(client.get_user_tier(&user), Some(SubscriptionTier::Premium));
+        assert_eq!(client.get_pending_tier(&user), Some(SubscriptionTier::Basic));
+
+        let balance = token_client.balance(&user);
+        env.ledger().with_mut(|li| li.timestamp += MONTH_IN_SECONDS + 1);
+        client.process_renewal(&user);
+
+        assert_eq!(token_client.balance(&user), balance - 1_000_000);
+        assert_eq!(client.get_user_tier(&user), Some(SubscriptionTier::Basic));
+        assert_eq!(client.get_pending_tier(&user), None);
+    }
+
+    #[test]
+    fn test_prorated_refund_on_cancel() {
+        let (env, admin, payment_token, user, _token_admin_client, client) = setup();
+        let token_client = token::Client::new(&env, &payment_token);
+
+        client.set_refund_policy(&admin, &RefundMode::Prorated, &1_000);
+        client.purchase_plan(&user, &SubscriptionTier::Basic, &BillingPeriod::Quarterly, &true);
+        let balance = token_client.balance(&user);
+
+        // Two thirds of 2.85 tokens unused, less the 10% fee
+        env.ledger().with_mut(|li| li.timestamp += MONTH_IN_SECONDS);
+        assert_eq!(client.get_refund_quote(&user), 1_710_000);
+        assert_eq!(client.cancel_subscription(&user), 1_710_000);
+
+        assert_eq!(token_client.balance(&user), balance + 1_710_000);
+        assert!(!client.has_active_subscription(&user));
+        assert_eq!(client.get_refund_quote(&user), 0);
+    }
+
     // ───────────── BENEFITS TRACKING TESTS ─────────────
 
     #[test]
@@ -919,6 +1755,82 @@ mod test {
         assert_eq!(sub.benefits_used, 2);
     }
 
+    #[test]
+    fn test_benefit_limit_enforced_and_reset() {
+        let (env, _admin, _payment_token, user, _token_admin_client, client) = setup();
+
+        client.purchase_subscription(&user, &SubscriptionTier::Basic, &true);
+        for _ in 0..10 {
+            client.use_benefit(&user);
+        }
+        assert!(client.try_use_benefit(&user).is_err());
+
+        env.ledger().with_mut(|li| li.timestamp += MONTH_IN_SECONDS + 1);
+        client.process_renewal(&user);
+        assert_eq!(client.get_subscription(&user).unwrap().benefits_used, 0);
+        client.use_benefit(&user);
+    }
+
+    #[test]
+    fn test_consume_benefit_quota() {
+        let (env, admin, _payment_token, user, _token_admin_client, client) = setup();
+        let hint_market = Address::generate(&env);
+        let stranger = Address::generate(&env);
+        let hints = symbol_short!("hints");
+        let energy = symbol_short!("energy");
+
+        client.set_benefit_quota(&admin, &hints, &SubscriptionTier::Basic, &3);
+        client.set_benefit_quota(&admin, &hints, &SubscriptionTier::Premium, &10);
+        client.set_benefit_consumer(&admin, &hint_market, &true);
+        assert!(client.is_benefit_consumer(&hint_market));
+
+        client.purchase_subscription(&user, &SubscriptionTier::Basic, &true);
+
+        assert_eq!(client.consume_benefit(&hint_market, &user, &hints, &2), 1);
+        assert_eq!(client.get_benefit_used(&user, &hints), 2);
+        assert!(client.try_consume_benefit(&hint_market, &user, &hints, &2).is_err());
+
+        // Features without a quota for the tier can't be consumed
+        assert!(client.try_consume_benefit(&hint_market, &user, &energy, &1).is_err());
+
+        // Only allowlisted contracts can meter
+        assert!(client.try_consume_benefit(&stranger, &user, &hints, &1).is_err());
+        client.set_benefit_consumer(&admin, &hint_market, &false);
+        assert!(client.try_consume_benefit(&hint_market, &user, &hints, &1).is_err());
+        client.set_benefit_consumer(&admin, &hint_market, &true);
+
+        // Renewal starts a fresh period
+        env.ledger().with_mut(|li| li.timestamp += MONTH_IN_SECONDS + 1);
+        client.process_renewal(&user);
+        assert_eq!(client.get_benefit_remaining(&user, &hints), 3);
+
+        // Upgrading applies the new tier's quota to the same period
+        client.consume_benefit(&hint_market, &user, &hints, &3);
+        client.upgrade_subscription(&user, &SubscriptionTier::Premium);
+        assert_eq!(client.get_benefit_remaining(&user, &hints), 7);
+    }
+
+    #[test]
+    fn test_group_members_consume_benefits() {
+        let (env, admin, _payment_token, owner, _token_admin_client, client) = setup();
+        let energy_contract = Address::generate(&env);
+        let member = Address::generate(&env);
+        let refills = symbol_short!("refills");
+
+        client.set_benefit_quota(&admin, &refills, &SubscriptionTier::Premium, &5);
+        client.set_benefit_consumer(&admin, &energy_contract, &true);
+
+        let group_id = client.create_group_subscription(&owner, &SubscriptionTier::Premium, &3, &false);
+        client.add_group_member(&owner, &group_id, &member);
+
+        assert_eq!(client.consume_benefit(&energy_contract, &member, &refills, &5), 0);
+        assert_eq!(client.get_benefit_remaining(&owner, &refills), 5);
+
+        // No subscription, no benefits
+        let outsider = Address::generate(&env);
+        assert!(client.try_consume_benefit(&energy_contract, &outsider, &refills, &1).is_err());
+    }
+
     #[test]
     fn test_benefit_limits() {
         let basic_limit = SubscriptionContract::get_benefit_limit(SubscriptionTier::Basic);
@@ -1076,6 +1988,99 @@ mod test {
         client.gift_subscription(&gifter, &recipient, &SubscriptionTier::Premium);
     }
 
+    // ───────────── PROMO CODE & TRIAL TESTS ─────────────
+
+    #[test]
+    fn test_percent_promo_code_for_limited_periods() {
+        let (env, admin, payment_token, user, _token_admin_client, client) = setup();
+        let token_client = token::Client::new(&env, &payment_token);
+        let code = symbol_short!("HALF");
+
+        client.create_promo_code(&admin, &code, &PromoKind::Percent, &5_000, &2, &10, &1_000, &Vec::new(&env));
+        client.purchase_with_promo(&user, &SubscriptionTier::Basic, &BillingPeriod::Monthly, &true, &code);
+
+        assert_eq!(token_client.balance(&user), 100_000_000 - 500_000);
+        assert_eq!(client.get_promo_code(&code).unwrap().uses, 1);
+        assert_eq!(client.get_promo_redemption(&user).unwrap().periods_left, 1);
+
+        // Second period still discounted, third at full price
+        env.ledger().with_mut(|li| li.timestamp += MONTH_IN_SECONDS + 1);
+        client.process_renewal(&user);
+        assert_eq!(token_client.balance(&user), 100_000_000 - 1_000_000);
+        assert!(client.get_promo_redemption(&user).is_none());
+
+        env.ledger().with_mut(|li| li.timestamp += MONTH_IN_SECONDS + 1);
+        client.process_renewal(&user);
+        assert_eq!(token_client.balance(&user), 100_000_000 - 2_000_000);
+    }
+
+    #[test]
+    fn test_fixed_promo_code_restrictions() {
+        let (env, admin, payment_token, user, token_admin_client, client) = setup();
+        let token_client = token::Client::new(&env, &payment_token);
+        let other = Address::generate(&env);
+        token_admin_client.mint(&other, &100_000_000);
+
+        let code = symbol_short!("PREMIUM");
+        let mut tiers = Vec::new(&env);
+        tiers.push_back(SubscriptionTier::Premium);
+        client.create_promo_code(&admin, &code, &PromoKind::Fixed, &3_000_000, &1, &1, &1_000, &tiers);
+
+        // Wrong tier
+        assert!(client.try_purchase_with_promo(&user, &SubscriptionTier::Basic, &BillingPeriod::Monthly, &true, &code).is_err());
+
+        client.purchase_with_promo(&user, &SubscriptionTier::Premium, &BillingPeriod::Monthly, &true, &code);
+        assert_eq!(token_client.balance(&user), 100_000_000 - 2_000_000);
+
+        // Usage cap reached
+        assert!(client.try_purchase_with_promo(&other, &SubscriptionTier::Premium, &BillingPeriod::Monthly, &true, &code).is_err());
+
+        // Expired code
+        let late = symbol_short!("LATE");
+        client.create_promo_code(&admin, &late, &PromoKind::Percent, &1_000, &1, &5, &1_000, &Vec::new(&env));
+        env.ledger().with_mut(|li| li.timestamp += 1_001);
+        assert!(client.try_purchase_with_promo(&other, &SubscriptionTier::Basic, &BillingPeriod::Monthly, &true, &late).is_err());
+    }
+
+    #[test]
+    fn test_trial_converts_when_auto_renew() {
+        let (env, _admin, payment_token, user, _token_admin_client, client) = setup();
+        let token_client = token::Client::new(&env, &payment_token);
+
+        client.start_trial(&user, &SubscriptionTier::Premium, &true);
+
+        let sub = client.get_subscription(&user).unwrap();
+        assert!(sub.is_trial);
+        assert_eq!(sub.expiry_time, sub.start_time + client.get_trial_duration());
+        assert_eq!(token_client.balance(&user), 100_000_000);
+        assert!(client.has_active_subscription(&user));
+
+        // First renewal charges the full monthly price
+        env.ledger().with_mut(|li| li.timestamp += client.get_trial_duration() + 1);
+        client.process_renewal(&user);
+
+        let sub = client.get_subscription(&user).unwrap();
+        assert!(!sub.is_trial);
+        assert_eq!(sub.period_price, 5_000_000);
+        assert_eq!(token_client.balance(&user), 100_000_000 - 5_000_000);
+    }
+
+    #[test]
+    fn test_trial_is_one_time() {
+        let (env, _admin, _payment_token, user, _token_admin_client, client) = setup();
+
+        client.start_trial(&user, &SubscriptionTier::Basic, &false);
+        assert!(client.has_used_trial(&user));
+
+        // Lapses without auto-renew, and can't be restarted
+        env.ledger().with_mut(|li| li.timestamp += client.get_trial_duration() + GRACE_PERIOD_SECONDS + 1);
+        assert!(!client.has_active_subscription(&user));
+        assert!(client.try_start_trial(&user, &SubscriptionTier::Basic, &false).is_err());
+
+        client.purchase_subscription(&user, &SubscriptionTier::Basic, &true);
+        assert!(client.has_active_subscription(&user));
+    }
+
     // ───────────── ADMIN TESTS ─────────────
 
     #[test]
@@ -1113,7 +2118,7 @@ mod test {
 
     #[test]
     fn test_get_time_until_expiry() {
-        let (env, _admin, _payment_token, user, token_admin_client, client) = setup();
+        let (env, _admin, _payment_token, user, _token_admin_client, client) = setup();
 
         client.purchase_subscription(&user, &SubscriptionTier::Basic, &true);
 